@@ -0,0 +1,199 @@
+use crate::common::{BulkResult, NitData};
+use eva_common::prelude::*;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const AUDIT_FILE_MAX_SIZE: u64 = 10_485_760;
+pub const AUDIT_FILE_KEEP: usize = 5;
+
+lazy_static! {
+    static ref AUDIT_LOCK: Mutex<()> = <_>::default();
+}
+
+/// A mutating operation record, stored as a single line of the audit file
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditRecord {
+    pub t: f64,
+    pub path: String,
+    pub login: Option<String>,
+    pub node: String,
+    pub op: String,
+    #[serde(default)]
+    pub targets: Vec<String>,
+    pub payload: Option<String>,
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+    /// Checks if any of the text fields contains the given string (case-insensitive)
+    pub fn matches(&self, s: &str) -> bool {
+        let s = s.to_lowercase();
+        self.path.to_lowercase().contains(&s)
+            || self
+                .login
+                .as_ref()
+                .map_or(false, |l| l.to_lowercase().contains(&s))
+            || self.targets.iter().any(|t| t.to_lowercase().contains(&s))
+            || self
+                .payload
+                .as_ref()
+                .map_or(false, |p| p.to_lowercase().contains(&s))
+            || self
+                .error
+                .as_ref()
+                .map_or(false, |e| e.to_lowercase().contains(&s))
+    }
+}
+
+fn rotated(file: &Path, n: usize) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+fn rotate(file: &Path) -> EResult<()> {
+    let _r = std::fs::remove_file(rotated(file, AUDIT_FILE_KEEP));
+    for n in (1..AUDIT_FILE_KEEP).rev() {
+        let src = rotated(file, n);
+        if src.exists() {
+            std::fs::rename(src, rotated(file, n + 1))?;
+        }
+    }
+    std::fs::rename(file, rotated(file, 1))?;
+    Ok(())
+}
+
+fn append(file: &Path, record: &AuditRecord) -> EResult<()> {
+    let mut line = serde_json::to_vec(record).map_err(Error::invalid_data)?;
+    line.push(b'\n');
+    let _lock = AUDIT_LOCK.lock().unwrap();
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if std::fs::metadata(file).map_or(false, |m| m.len() >= AUDIT_FILE_MAX_SIZE) {
+        rotate(file)?;
+    }
+    let mut f = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?;
+    f.write_all(&line)?;
+    Ok(())
+}
+
+/// Records the operation outcome. Bulk operations with continue-on-error are recorded as failed
+/// if any of the resources has failed
+pub fn log(path: &str, login: Option<&str>, nit: &NitData, result: &EResult<Value>) {
+    let error = match result {
+        Ok(v) if nit.continue_on_error() => Vec::<BulkResult>::deserialize(v.clone())
+            .ok()
+            .and_then(|report| {
+                let failed: Vec<String> = report
+                    .into_iter()
+                    .filter_map(|r| r.error.map(|e| format!("{}: {}", r.id, e)))
+                    .collect();
+                if failed.is_empty() {
+                    None
+                } else {
+                    Some(failed.join("; "))
+                }
+            }),
+        Ok(_) => None,
+        Err(e) => Some(e.to_string()),
+    };
+    let record = AuditRecord {
+        t: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64()),
+        path: path.to_owned(),
+        login: login.filter(|l| !l.is_empty()).map(ToOwned::to_owned),
+        node: nit.node().name().to_owned(),
+        op: nit.kind().to_string(),
+        targets: nit.kind().targets(),
+        payload: nit.kind().payload_summary(),
+        error,
+    };
+    if let Some(file) = crate::AUDIT_FILE.as_ref() {
+        if let Err(e) = append(file, &record) {
+            eprintln!("unable to write audit log: {}", e);
+        }
+    }
+}
+
+/// Loads all records, including the rotated files, the oldest first
+pub fn load() -> EResult<Vec<AuditRecord>> {
+    let mut records = Vec::new();
+    let file = if let Some(file) = crate::AUDIT_FILE.as_ref() {
+        file
+    } else {
+        return Ok(records);
+    };
+    let _lock = AUDIT_LOCK.lock().unwrap();
+    let mut files: Vec<PathBuf> = (1..=AUDIT_FILE_KEEP)
+        .rev()
+        .map(|n| rotated(file, n))
+        .collect();
+    files.push(file.clone());
+    for f in files {
+        if !f.exists() {
+            continue;
+        }
+        for line in BufReader::new(std::fs::File::open(f)?).lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => eprintln!("invalid audit record: {}", e),
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Saves records in the audit file format
+pub fn save(fname: &str, records: &[AuditRecord]) -> EResult<()> {
+    let mut f = std::fs::OpenOptions::new()
+        .create(true)
+        .append(false)
+        .truncate(true)
+        .write(true)
+        .open(fname)?;
+    for record in records {
+        let mut line = serde_json::to_vec(record).map_err(Error::invalid_data)?;
+        line.push(b'\n');
+        f.write_all(&line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rotate() {
+        let dir = std::env::temp_dir().join(format!("ecmui-test-audit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("audit.jsonl");
+        let read = |n: usize| std::fs::read_to_string(rotated(&file, n)).ok();
+        for i in 0..=AUDIT_FILE_KEEP {
+            std::fs::write(&file, i.to_string()).unwrap();
+            rotate(&file).unwrap();
+        }
+        assert!(!file.exists());
+        for n in 1..=AUDIT_FILE_KEEP {
+            assert_eq!(read(n), Some((AUDIT_FILE_KEEP + 1 - n).to_string()));
+        }
+        assert_eq!(read(AUDIT_FILE_KEEP + 1), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use crate::bus;
+use crate::common::{load_data, save_yaml, ItemInfo, NitData, NodeRef, PayloadLvarSet, SvcData};
+use crate::resources::{export_item, export_svc};
+use chrono::{Local, SecondsFormat};
+use eva_common::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub const BACKUP_VERSION: u16 = 1;
+
+/// Node configuration archive. The svcs and items sections have the same format as in resource
+/// files, so backups can be imported as resources as well
+#[derive(Serialize, Deserialize)]
+pub struct NodeBackup {
+    pub backup_version: u16,
+    pub node: String,
+    pub created: String,
+    pub svcs: Vec<Value>,
+    pub items: Vec<Value>,
+    #[serde(default)]
+    pub lvars: Vec<LvarState>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LvarState {
+    pub oid: String,
+    pub status: Option<ItemStatus>,
+    pub value: Option<Value>,
+}
+
+impl NodeBackup {
+    /// Collects all services, local items and lvar states of the node
+    pub fn create(node: &NodeRef) -> EResult<Self> {
+        let mut svcs = Vec::new();
+        let services: Vec<SvcData> = bus::call(Arc::new(NitData::new_services(node)))?;
+        for svc in services {
+            let params =
+                bus::call::<Value>(Arc::new(NitData::new_svc_get_params(node, svc.id.clone())))?;
+            export_svc(&mut svcs, &svc.id, params);
+        }
+        let mut items = Vec::new();
+        let mut lvars = Vec::new();
+        let item_list: Vec<ItemInfo> = bus::call(Arc::new(NitData::new_item_list(
+            node,
+            Some("#".to_owned()),
+            Some(node.name().to_owned()),
+        )))?;
+        for item in item_list {
+            let oid = item.oid.to_string();
+            let config =
+                bus::call::<Value>(Arc::new(NitData::new_item_get_config(node, oid.clone())))?;
+            export_item(&mut items, &oid, config);
+            if item.oid.kind() == ItemKind::Lvar {
+                lvars.push(LvarState {
+                    oid,
+                    status: item.status,
+                    value: item.value,
+                });
+            }
+        }
+        Ok(Self {
+            backup_version: BACKUP_VERSION,
+            node: node.name().to_owned(),
+            created: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            svcs,
+            items,
+            lvars,
+        })
+    }
+    /// Collects the listed services and items only, used to undo destructive operations
+    pub fn snapshot(node: &NodeRef, svcs: &[String], oids: &[String]) -> EResult<Self> {
+        let mut svc_configs = Vec::with_capacity(svcs.len());
+        for id in svcs {
+            let params =
+                bus::call::<Value>(Arc::new(NitData::new_svc_get_params(node, id.clone())))?;
+            export_svc(&mut svc_configs, id, params);
+        }
+        let mut items = Vec::with_capacity(oids.len());
+        let mut lvars = Vec::new();
+        for oid in oids {
+            let config =
+                bus::call::<Value>(Arc::new(NitData::new_item_get_config(node, oid.clone())))?;
+            export_item(&mut items, oid, config);
+            let oid_p: OID = oid.parse()?;
+            if oid_p.kind() == ItemKind::Lvar {
+                let state = bus::item_state(node, oid_p)?;
+                lvars.push(LvarState {
+                    oid: oid.clone(),
+                    status: Some(state.status),
+                    value: state.value,
+                });
+            }
+        }
+        Ok(Self {
+            backup_version: BACKUP_VERSION,
+            node: node.name().to_owned(),
+            created: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            svcs: svc_configs,
+            items,
+            lvars,
+        })
+    }
+    pub fn load(fname: &str) -> EResult<Self> {
+        let backup: Self =
+            load_data(fname)?.ok_or_else(|| Error::not_found("backup file not found"))?;
+        if backup.backup_version > BACKUP_VERSION {
+            return Err(Error::unsupported(format!(
+                "unsupported backup version: {}",
+                backup.backup_version
+            )));
+        }
+        Ok(backup)
+    }
+    pub fn save(&self, fname: &str) -> EResult<()> {
+        save_yaml(fname, self)
+    }
+    pub fn summary(&self) -> String {
+        format!(
+            "{} svcs, {} items, {} lvar states",
+            self.svcs.len(),
+            self.items.len(),
+            self.lvars.len()
+        )
+    }
+    /// Deploys the archive to the node. Services go first as items may refer to them, lvar
+    /// states are set after the items are deployed
+    pub fn restore(self, node: &NodeRef) -> EResult<()> {
+        if !self.svcs.is_empty() {
+            bus::call::<()>(Arc::new(NitData::new_svc_deploy_multi(node, self.svcs)))?;
+        }
+        if !self.items.is_empty() {
+            bus::call::<()>(Arc::new(NitData::new_item_deploy_multi(node, self.items)))?;
+        }
+        for lvar in self.lvars {
+            if lvar.status.is_none() && lvar.value.is_none() {
+                continue;
+            }
+            let p_set = PayloadLvarSet {
+                i: None,
+                status: lvar.status,
+                value: lvar.value,
+            };
+            bus::call::<()>(Arc::new(NitData::new_lvar_set(node, vec![lvar.oid], p_set)))?;
+        }
+        Ok(())
+    }
+}
@@ -1,26 +1,245 @@
+use crate::audit;
 use crate::common::{
-    ConnectionOptions, ItemConfig, ItemState, Nit, NitData, NitKind, NodeInfo, PayloadLvarSet,
-    ServiceParams,
+    BulkResult, ConnId, ConnectionOptions, ItemConfig, ItemInfo, ItemState, Nit, NitData, NitKind,
+    NodeInfo, NodeRef, PayloadLvarSet, ServiceParams, StateEvent,
 };
 use crate::ui::{self, set_status, StatusKind};
+use busrt::client::AsyncClient;
+use busrt::QoS;
 use eva_client::{EvaClient, EvaCloudClient, NodeMap};
 use eva_common::common_payloads::{ParamsId, ParamsUuid};
 use eva_common::prelude::*;
 use lazy_static::lazy_static;
 use serde::{de::DeserializeOwned, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic;
 use std::sync::{mpsc as mpsc_std, Arc};
 use std::time::Duration;
-use tokio::sync::{mpsc, oneshot};
-use tokio::task::JoinHandle;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::task::{AbortHandle, JoinHandle};
 
 lazy_static! {
-    static ref WATCHERS: std::sync::Mutex<HashMap<uuid::Uuid, JoinHandle<()>>> = <_>::default();
+    static ref CONNECTIONS: std::sync::Mutex<BTreeMap<ConnId, Connection>> = <_>::default();
+    static ref OPS: std::sync::Mutex<HashMap<uuid::Uuid, Op>> = <_>::default();
+}
+
+thread_local! {
+    static CURRENT_OP: std::cell::Cell<Option<uuid::Uuid>> = std::cell::Cell::new(None);
 }
 
 const SVC_CORE: &str = "eva.core";
 
+const LOCAL_STATE_TOPIC: &str = "ST/LOC/";
+const REMOTE_STATE_TOPIC: &str = "ST/REM/";
+
+const COMMAND_QUEUE_SIZE: usize = 1024;
+
+static MAX_PARALLEL: atomic::AtomicUsize =
+    atomic::AtomicUsize::new(crate::common::DEFAULT_MAX_PARALLEL);
+
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(60);
+
+static NEXT_CONN_ID: atomic::AtomicUsize = atomic::AtomicUsize::new(1);
+
+static READ_ONLY: atomic::AtomicBool = atomic::AtomicBool::new(false);
+
+pub const ERR_READ_ONLY: &str = "The operation is not allowed in read-only mode";
+
+/// A connection to a cloud, owns its command channel, tasks and watchers
+#[derive(Default)]
+struct Connection {
+    path: String,
+    login: Option<String>,
+    read_only: bool,
+    client_name: Option<String>,
+    channel: Option<CommandTx>,
+    fut: Option<JoinHandle<()>>,
+    nit_handler: Option<JoinHandle<()>>,
+    sub_channel: Option<SubTx>,
+    sub_handler: Option<JoinHandle<()>>,
+    watchers: HashMap<uuid::Uuid, Watcher>,
+}
+
+impl Connection {
+    fn new(path: &str, login: Option<&str>) -> Self {
+        Self {
+            path: path.to_owned(),
+            login: login.map(ToOwned::to_owned),
+            ..Self::default()
+        }
+    }
+    /// Stops the command handler and watcher tasks, the watchers are kept to be restarted
+    fn suspend(&mut self) {
+        self.channel.take();
+        if let Some(fut) = self.nit_handler.take() {
+            fut.abort();
+        }
+        self.sub_channel.take();
+        if let Some(fut) = self.sub_handler.take() {
+            fut.abort();
+        }
+        for watcher in self.watchers.values() {
+            watcher.fut.abort();
+        }
+    }
+    fn shutdown(mut self) {
+        self.suspend();
+        if let Some(fut) = self.fut.take() {
+            fut.abort();
+        }
+    }
+}
+
+fn with_connection<R, F>(conn: ConnId, f: F) -> Option<R>
+where
+    F: FnOnce(&mut Connection) -> R,
+{
+    CONNECTIONS.lock().unwrap().get_mut(&conn).map(f)
+}
+
+/// Sets the read-only mode for all connections
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, atomic::Ordering::SeqCst);
+}
+
+/// Sets the read-only mode for a single connection, e.g. from the connection profile
+pub fn set_conn_read_only(conn: ConnId, read_only: bool) {
+    with_connection(conn, |c| c.read_only = read_only);
+}
+
+pub fn is_read_only(conn: ConnId) -> bool {
+    READ_ONLY.load(atomic::Ordering::SeqCst)
+        || with_connection(conn, |c| c.read_only).unwrap_or_default()
+}
+
+/// Rejects mutating operations if the connection is in read-only mode
+fn check_writable(nit: &NitData) -> EResult<()> {
+    if nit.kind().is_mutating() && is_read_only(nit.conn()) {
+        Err(Error::access(ERR_READ_ONLY))
+    } else {
+        Ok(())
+    }
+}
+
+/// Writes a mutating operation to the audit log
+fn audit(conn: ConnId, nit: &NitData, result: &EResult<Value>) {
+    if let Some((path, login)) = with_connection(conn, |c| (c.path.clone(), c.login.clone())) {
+        audit::log(&path, login.as_deref(), nit, result);
+    }
+}
+
+#[derive(Clone)]
+enum WatcherKind {
+    Item(String, OID, Duration),
+    Action(String, uuid::Uuid, Duration), // UUID = action UUID
+}
+
+struct Watcher {
+    kind: WatcherKind,
+    fut: JoinHandle<()>,
+}
+
+/// Item state subscription owners, each one holds a list of (node, OID) pairs
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+enum SubKey {
+    Watcher(uuid::Uuid),
+    View,
+}
+
+enum SubCommand {
+    Set(SubKey, Vec<(String, OID)>),
+    Remove(SubKey),
+}
+
+type SubTx = mpsc::UnboundedSender<SubCommand>;
+
+/// Removes the subscription when dropped, e.g. when a watcher task is aborted
+struct SubGuard {
+    key: SubKey,
+    tx: SubTx,
+}
+
+impl SubGuard {
+    fn new(tx: SubTx, key: SubKey, items: Vec<(String, OID)>) -> Self {
+        let _r = tx.send(SubCommand::Set(key, items));
+        Self { key, tx }
+    }
+}
+
+impl Drop for SubGuard {
+    fn drop(&mut self) {
+        let _r = self.tx.send(SubCommand::Remove(self.key));
+    }
+}
+
+/// A group of calls made by a single UI operation, can be cancelled as a whole
+#[derive(Default)]
+struct Op {
+    cancelled: bool,
+    tasks: Vec<AbortHandle>,
+}
+
+fn op_register_task(op: uuid::Uuid, task: AbortHandle) {
+    if let Some(o) = OPS.lock().unwrap().get_mut(&op) {
+        if o.cancelled {
+            task.abort();
+        } else {
+            o.tasks.push(task);
+        }
+    } else {
+        // the operation is already finished or cancelled
+        task.abort();
+    }
+}
+
+fn spawn_watcher(
+    conn: ConnId,
+    client: Arc<EvaCloudClient>,
+    u: uuid::Uuid,
+    kind: WatcherKind,
+    sub: Option<SubTx>,
+) -> JoinHandle<()> {
+    match kind {
+        WatcherKind::Item(node, oid, int) => tokio::spawn(async move {
+            if let Some(sub) = sub {
+                item_subscriber(conn, client, u, node, oid, sub).await;
+            } else {
+                item_watcher(conn, client, u, &node, &oid, int).await;
+            }
+        }),
+        WatcherKind::Action(node, action_uuid, int) => tokio::spawn(async move {
+            action_watcher(conn, client, u, &node, action_uuid, int).await;
+        }),
+    }
+}
+
+fn start_watcher(conn: ConnId, client: Arc<EvaCloudClient>, u: uuid::Uuid, kind: WatcherKind) {
+    with_connection(conn, |c| {
+        let fut = spawn_watcher(conn, client, u, kind.clone(), c.sub_channel.clone());
+        if let Some(prev) = c.watchers.insert(u, Watcher { kind, fut }) {
+            prev.fut.abort();
+        }
+    });
+}
+
+fn restart_watchers(conn: ConnId, client: &Arc<EvaCloudClient>) {
+    with_connection(conn, |c| {
+        for (u, watcher) in &mut c.watchers {
+            watcher.fut.abort();
+            watcher.fut = spawn_watcher(
+                conn,
+                client.clone(),
+                *u,
+                watcher.kind.clone(),
+                c.sub_channel.clone(),
+            );
+        }
+    });
+}
+
 async fn item_watcher(
+    conn: ConnId,
     client: Arc<EvaCloudClient>,
     u: uuid::Uuid,
     node: &str,
@@ -37,7 +256,7 @@ async fn item_watcher(
                     .call::<Value>(node, SVC_CORE, "item.state", Some(payload.clone()))
                     .await
                 {
-                    ui::command(ui::Command::ProcessItemWatch(u, value));
+                    ui::command(ui::Command::ProcessItemWatch(conn, u, value));
                 }
             }
         }
@@ -47,7 +266,34 @@ async fn item_watcher(
     }
 }
 
+/// Pushes item state changes received from the bus, the current state is polled once
+async fn item_subscriber(
+    conn: ConnId,
+    client: Arc<EvaCloudClient>,
+    u: uuid::Uuid,
+    node: String,
+    oid: OID,
+    sub: SubTx,
+) {
+    let _guard = SubGuard::new(sub, SubKey::Watcher(u), vec![(node.clone(), oid.clone())]);
+    match to_value(ParamsId { i: oid.as_str() }) {
+        Ok(payload) => {
+            if let Ok(value) = client
+                .call::<Value>(&node, SVC_CORE, "item.state", Some(payload))
+                .await
+            {
+                ui::command(ui::Command::ProcessItemWatch(conn, u, value));
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+        }
+    }
+    std::future::pending::<()>().await;
+}
+
 async fn action_watcher(
+    conn: ConnId,
     client: Arc<EvaCloudClient>,
     u: uuid::Uuid,
     node: &str,
@@ -64,9 +310,9 @@ async fn action_watcher(
                     .call::<Value>(node, SVC_CORE, "action.result", Some(payload.clone()))
                     .await
                 {
-                    Ok(value) => ui::command(ui::Command::ProcessActionWatch(u, value)),
+                    Ok(value) => ui::command(ui::Command::ProcessActionWatch(conn, u, value)),
                     Err(e) if e.kind() == ErrorKind::ResourceNotFound => {
-                        ui::command(ui::Command::ProcessActionWatch(u, Value::Unit));
+                        ui::command(ui::Command::ProcessActionWatch(conn, u, Value::Unit));
                     }
                     Err(e) => {
                         eprintln!("{}", e);
@@ -80,103 +326,364 @@ async fn action_watcher(
     }
 }
 
-async fn launch_connection(
-    path: &str,
-    _tx: oneshot::Sender<()>,
-    timeout: Duration,
-    creds: Option<(String, String)>,
-) {
-    crate::CLIENT_CHANNEL.lock().unwrap().take();
-    set_status(format!("Connecting to {path}..."), StatusKind::Info);
-    macro_rules! set_err {
-        ($e: expr) => {
-            set_status(
-                $e.message().unwrap_or("Connection error"),
-                StatusKind::Error,
-            );
-        };
-    }
-    let mut client_config = eva_client::Config::new().timeout(timeout);
-    if let Some(c) = creds {
-        client_config = client_config.credentials(&c.0, &c.1);
-    }
-    let client = match EvaClient::connect(path, crate::BUS_CLIENT_NAME, client_config).await {
-        Ok(c) => c,
-        Err(e) => {
-            set_err!(e);
-            return;
+fn is_http(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+fn state_topics(oid: &OID) -> [String; 2] {
+    [
+        format!("{}{}", LOCAL_STATE_TOPIC, oid.as_path()),
+        format!("{}{}", REMOTE_STATE_TOPIC, oid.as_path()),
+    ]
+}
+
+/// Subscribes to state topics of the watched items and pushes state changes to the UI
+async fn state_subscriber(
+    conn: ConnId,
+    mut bus: busrt::ipc::Client,
+    system_name: String,
+    mut rx: mpsc::UnboundedReceiver<SubCommand>,
+) -> EResult<()> {
+    let events = bus
+        .take_event_channel()
+        .ok_or_else(|| Error::core("bus event channel is not available"))?;
+    let mut subs: HashMap<SubKey, Vec<(String, OID)>> = HashMap::new();
+    let mut topics: HashSet<String> = HashSet::new();
+    loop {
+        tokio::select! {
+            cmd = rx.recv() => {
+                match cmd {
+                    Some(SubCommand::Set(key, items)) => {
+                        subs.insert(key, items);
+                    }
+                    Some(SubCommand::Remove(key)) => {
+                        subs.remove(&key);
+                    }
+                    None => break,
+                }
+                let required: HashSet<String> = subs
+                    .values()
+                    .flatten()
+                    .flat_map(|(_, oid)| state_topics(oid))
+                    .collect();
+                let to_unsub: Vec<&str> = topics.difference(&required).map(String::as_str).collect();
+                if !to_unsub.is_empty() {
+                    bus.unsubscribe_bulk(&to_unsub, QoS::No).await.map_err(Error::io)?;
+                }
+                let to_sub: Vec<&str> = required.difference(&topics).map(String::as_str).collect();
+                if !to_sub.is_empty() {
+                    bus.subscribe_bulk(&to_sub, QoS::No).await.map_err(Error::io)?;
+                }
+                topics = required;
+            }
+            frame = events.recv() => {
+                let frame = frame.map_err(|_| Error::io("bus connection closed"))?;
+                if let Some(topic) = frame.topic() {
+                    process_state_event(conn, &system_name, &subs, topic, frame.payload());
+                }
+            }
         }
+    }
+    Ok(())
+}
+
+fn process_state_event(
+    conn: ConnId,
+    system_name: &str,
+    subs: &HashMap<SubKey, Vec<(String, OID)>>,
+    topic: &str,
+    payload: &[u8],
+) {
+    let (path, remote) = if let Some(path) = topic.strip_prefix(LOCAL_STATE_TOPIC) {
+        (path, false)
+    } else if let Some(path) = topic.strip_prefix(REMOTE_STATE_TOPIC) {
+        (path, true)
+    } else {
+        return;
     };
-    set_status(format!("Loading data from {path}..."), StatusKind::Info);
-    crate::CLIENT_NAME
-        .lock()
-        .unwrap()
-        .replace(client.name().to_owned());
-    let sys_info: eva_client::SystemInfo = match client.call(SVC_CORE, "test", None).await {
+    let event: StateEvent = match rmp_serde::from_slice(payload) {
         Ok(v) => v,
         Err(e) => {
-            set_err!(e);
+            eprintln!("invalid state event {}: {}", topic, e);
             return;
         }
     };
-    let system_name = sys_info.system_name;
-    let mut node_list: Vec<NodeInfo> = match client.call(SVC_CORE, "node.list", None).await {
-        Ok(v) => v,
-        Err(e) => {
-            set_err!(e);
+    let node = if remote {
+        if let Some(ref node) = event.node {
+            node.as_str()
+        } else {
             return;
         }
+    } else {
+        system_name
     };
+    let mut state: Option<ItemInfo> = None;
+    for (key, items) in subs {
+        if let Some((_, oid)) = items
+            .iter()
+            .find(|(n, oid)| n == node && oid.as_path() == path)
+        {
+            let state = state.get_or_insert_with(|| event.to_item_info(oid.clone(), node));
+            match key {
+                SubKey::Watcher(u) => match to_value(vec![&*state]) {
+                    Ok(value) => ui::command(ui::Command::ProcessItemWatch(conn, *u, value)),
+                    Err(e) => eprintln!("{}", e),
+                },
+                SubKey::View => ui::command(ui::Command::ProcessItemState(conn, state.clone())),
+            }
+        }
+    }
+}
+
+async fn launch_state_subscriber(
+    conn: ConnId,
+    path: &str,
+    client_name: &str,
+    system_name: &str,
+    cloud_client: &Arc<EvaCloudClient>,
+    timeout: Duration,
+) -> EResult<()> {
+    let config = busrt::ipc::Config::new(path, &format!("{}.sub", client_name)).timeout(timeout);
+    let bus = busrt::ipc::Client::connect(&config)
+        .await
+        .map_err(Error::io)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+    let system_name = system_name.to_owned();
+    let cloud_client = cloud_client.clone();
+    let fut = tokio::spawn(async move {
+        if let Err(e) = state_subscriber(conn, bus, system_name, rx).await {
+            eprintln!("state subscription failed: {}", e);
+            // fall back to polling
+            with_connection(conn, |c| c.sub_channel.take());
+            restart_watchers(conn, &cloud_client);
+        }
+    });
+    with_connection(conn, |c| {
+        c.sub_channel.replace(tx);
+        c.sub_handler.replace(fut);
+    });
+    Ok(())
+}
+
+/// Sets items displayed in the item list, their states are updated in-place from the bus if
+/// the connection supports it. Subscriptions of other connections are dropped
+pub fn set_item_view(conn: Option<ConnId>, items: Vec<(String, OID)>) {
+    let mut items = Some(items);
+    for (id, c) in CONNECTIONS.lock().unwrap().iter() {
+        if let Some(tx) = c.sub_channel.as_ref() {
+            let cmd = if conn == Some(*id) {
+                SubCommand::Set(SubKey::View, items.take().unwrap_or_default())
+            } else {
+                SubCommand::Remove(SubKey::View)
+            };
+            let _r = tx.send(cmd);
+        }
+    }
+}
+
+async fn open_client(
+    path: &str,
+    timeout: Duration,
+    creds: Option<(String, String)>,
+) -> EResult<EvaClient> {
+    let mut client_config = eva_client::Config::new().timeout(timeout);
+    if let Some(c) = creds {
+        client_config = client_config.credentials(&c.0, &c.1);
+    }
+    EvaClient::connect(path, crate::BUS_CLIENT_NAME, client_config).await
+}
+
+/// Loads the cloud node list and creates a cloud client, returns the client, the system name and
+/// the sorted node list
+async fn open_cloud_client(client: EvaClient) -> EResult<(EvaCloudClient, String, Vec<NodeInfo>)> {
+    let sys_info: eva_client::SystemInfo = client.call(SVC_CORE, "test", None).await?;
+    let system_name = sys_info.system_name;
+    let mut node_list: Vec<NodeInfo> = client.call(SVC_CORE, "node.list", None).await?;
     node_list.sort();
-    ui::command(ui::Command::MarkConnected(
-        path.to_owned(),
-        node_list.clone(),
-    ));
+    let mut node_map = NodeMap::new();
+    for node in &node_list {
+        if let Some(ref svc) = node.svc {
+            node_map.insert(node.name.clone(), svc.clone());
+        }
+    }
+    let cloud_client = EvaCloudClient::new(&system_name, client, node_map);
+    Ok((cloud_client, system_name, node_list))
+}
+
+async fn launch_connection(
+    conn: ConnId,
+    path: &str,
+    timeout: Duration,
+    creds: Option<(String, String)>,
+    attempt: u32,
+    connected: &mut bool,
+) -> EResult<()> {
+    with_connection(conn, |c| c.channel.take());
+    if attempt == 0 {
+        set_status(format!("Connecting to {path}..."), StatusKind::Info);
+    } else {
+        set_status(
+            format!("Reconnecting to {path} (attempt {attempt})..."),
+            StatusKind::Info,
+        );
+    }
+    let client = open_client(path, timeout, creds).await?;
+    set_status(format!("Loading data from {path}..."), StatusKind::Info);
+    let client_name = client.name().to_owned();
+    with_connection(conn, |c| c.client_name.replace(client_name.clone()));
+    let (cloud_client, system_name, node_list) = open_cloud_client(client).await?;
+    let cloud_client = Arc::new(cloud_client);
+    ui::command(ui::Command::MarkConnected(conn, path.to_owned(), node_list));
+    *connected = true;
     //draw_node_tree(&node_list);
     //set_status(&format!("Connected: {path}"), StatusKind::Okay);
     let mut int = tokio::time::interval(timeout / 2);
     int.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-    let mut node_map = NodeMap::new();
-    for node in node_list {
-        if let Some(svc) = node.svc {
-            node_map.insert(node.name, svc);
-        }
-    }
-    let cloud_client = Arc::new(EvaCloudClient::new(&system_name, client, node_map));
-    let (tx, rx) = mpsc::channel(1);
-    crate::CLIENT_CHANNEL.lock().unwrap().replace(tx);
+    let (tx, rx) = mpsc::channel(COMMAND_QUEUE_SIZE);
     let cloud_client_c = cloud_client.clone();
     let fut = tokio::spawn(async move {
-        process_commands(cloud_client_c, rx).await;
+        process_commands(conn, cloud_client_c, rx).await;
+    });
+    with_connection(conn, |c| {
+        c.channel.replace(tx);
+        c.nit_handler.replace(fut);
     });
-    crate::NIT_HANDLER.lock().unwrap().replace(fut);
-    let n = Arc::new(NitData::new_state(&system_name));
-    ui::command(ui::Command::ProcessNit(n));
+    // HTTP connections can not receive bus events, the watchers poll the node instead
+    if !is_http(path) {
+        if let Err(e) = launch_state_subscriber(
+            conn,
+            path,
+            &client_name,
+            &system_name,
+            &cloud_client,
+            timeout,
+        )
+        .await
+        {
+            eprintln!("unable to subscribe to state events: {}", e);
+        }
+    }
+    restart_watchers(conn, &cloud_client);
+    if attempt == 0 {
+        // on reconnect the UI restores its previous view
+        let n = Arc::new(NitData::new_state(&NodeRef::new(conn, &system_name)));
+        ui::command(ui::Command::ProcessNit(n));
+    }
     loop {
-        if let Err(e) = cloud_client.get_system_info(&system_name).await {
-            set_err!(e);
-            return;
-        };
+        cloud_client.get_system_info(&system_name).await?;
         int.tick().await;
     }
 }
 
+fn reconnect_delay(attempt: u32) -> Duration {
+    RECONNECT_DELAY_MIN
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(RECONNECT_DELAY_MAX)
+}
+
+/// Keeps the connection alive: once established, a dropped connection is re-launched with an
+/// exponential backoff until disconnect() is called
+async fn supervise_connection(conn: ConnId, opts: ConnectionOptions, _tx: oneshot::Sender<()>) {
+    let mut established = false;
+    let mut attempt: u32 = 0;
+    loop {
+        let mut connected = false;
+        let result = launch_connection(
+            conn,
+            &opts.path,
+            opts.timeout,
+            opts.credentials.clone(),
+            attempt,
+            &mut connected,
+        )
+        .await;
+        with_connection(conn, Connection::suspend);
+        let err = result.err().map_or_else(
+            || "Connection error".to_owned(),
+            |e| e.message().unwrap_or("Connection error").to_owned(),
+        );
+        if connected {
+            established = true;
+            attempt = 0;
+        }
+        if !established {
+            set_status(format!("{}: {}", opts.path, err), StatusKind::Error);
+            return;
+        }
+        attempt += 1;
+        let delay = reconnect_delay(attempt);
+        set_status(
+            format!(
+                "{}: {err}, reconnecting (attempt {attempt}) in {} sec...",
+                opts.path,
+                delay.as_secs()
+            ),
+            StatusKind::Error,
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Collects per-resource results of a bulk operation
+struct BulkReport {
+    continue_on_error: bool,
+    results: Vec<BulkResult>,
+}
+
+impl BulkReport {
+    fn new(continue_on_error: bool) -> Self {
+        Self {
+            continue_on_error,
+            results: Vec::new(),
+        }
+    }
+    /// Returns an error if the operation must be stopped
+    fn push<T>(&mut self, id: &str, result: EResult<T>) -> EResult<()> {
+        match result {
+            Ok(_) => {
+                if self.continue_on_error {
+                    self.results.push(BulkResult {
+                        id: id.to_owned(),
+                        error: None,
+                    });
+                }
+                Ok(())
+            }
+            Err(e) if self.continue_on_error => {
+                self.results.push(BulkResult {
+                    id: id.to_owned(),
+                    error: Some(e.to_string()),
+                });
+                Ok(())
+            }
+            Err(e) => Err(Error::failed(format!("{}: {}", id, e))),
+        }
+    }
+    fn into_value(self) -> EResult<Value> {
+        if self.continue_on_error {
+            Ok(to_value(self.results)?)
+        } else {
+            Ok(Value::Unit)
+        }
+    }
+}
+
 async fn process_bulk(
     client: &EvaCloudClient,
     node: &str,
     target: &str,
     method: &str,
     items: &[String],
+    continue_on_error: bool,
 ) -> EResult<Value> {
+    let mut report = BulkReport::new(continue_on_error);
     for item in items {
-        if let Err(e) = client
+        let result = client
             .call::<Value>(node, target, method, Some(to_value(ParamsId { i: item })?))
-            .await
-        {
-            return Err(Error::failed(format!("{}: {}", item, e)));
-        }
+            .await;
+        report.push(item, result)?;
     }
-    Ok(Value::Unit)
+    report.into_value()
 }
 
 async fn process_lvar_set(
@@ -184,18 +691,18 @@ async fn process_lvar_set(
     node: &str,
     lvars: &[String],
     p_set: &PayloadLvarSet,
+    continue_on_error: bool,
 ) -> EResult<Value> {
+    let mut report = BulkReport::new(continue_on_error);
     for lvar in lvars {
         let mut payload = p_set.clone();
         payload.i.replace(lvar.clone());
-        if let Err(e) = client
+        let result = client
             .call::<Value>(node, SVC_CORE, "lvar.set", Some(to_value(payload)?))
-            .await
-        {
-            return Err(Error::failed(format!("{}: {}", lvar, e)));
-        }
+            .await;
+        report.push(lvar, result)?;
     }
-    Ok(Value::Unit)
+    report.into_value()
 }
 
 async fn process_svcs(
@@ -301,51 +808,45 @@ async fn s_call<S: Serialize>(
 }
 
 #[allow(clippy::too_many_lines)]
-async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Value> {
+async fn do_process_command(conn: ConnId, client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Value> {
+    let node = nit.node().name();
+    let continue_on_error = nit.continue_on_error();
     match nit.kind() {
         NitKind::StartItemWatcher(u, oid, int) => {
-            let u = *u;
-            let int = *int;
-            let node: String = nit.node().to_owned();
-            let oid: OID = oid.clone();
-            let client = client.clone();
-            let fut = tokio::spawn(async move {
-                item_watcher(client, u, &node, &oid, int).await;
-            });
-            WATCHERS.lock().unwrap().insert(u, fut);
+            start_watcher(
+                conn,
+                client.clone(),
+                *u,
+                WatcherKind::Item(node.to_owned(), oid.clone(), *int),
+            );
             Ok(Value::Unit)
         }
         NitKind::StopWatcher(u) => {
-            if let Some(fut) = WATCHERS.lock().unwrap().remove(u) {
-                fut.abort();
+            if let Some(watcher) = with_connection(conn, |c| c.watchers.remove(u)).flatten() {
+                watcher.fut.abort();
             }
             Ok(Value::Unit)
         }
         NitKind::StartActionWatcher(u, action_uuid, int) => {
-            let u = *u;
-            let int = *int;
-            let node: String = nit.node().to_owned();
-            let client = client.clone();
-            let a_uuid = *action_uuid;
-            let fut = tokio::spawn(async move {
-                action_watcher(client, u, &node, a_uuid, int).await;
-            });
-            WATCHERS.lock().unwrap().insert(u, fut);
+            start_watcher(
+                conn,
+                client.clone(),
+                *u,
+                WatcherKind::Action(node.to_owned(), *action_uuid, *int),
+            );
             Ok(Value::Unit)
         }
         NitKind::State => {
-            let state = client
-                .call::<Value>(nit.node(), SVC_CORE, "test", None)
-                .await?;
+            let state = client.call::<Value>(node, SVC_CORE, "test", None).await?;
             let node_list = client
-                .call::<Value>(nit.node(), SVC_CORE, "node.list", None)
+                .call::<Value>(node, SVC_CORE, "node.list", None)
                 .await?;
             Ok(Value::Seq(vec![state, node_list]))
         }
         NitKind::Log(filter) => {
             if let Some(f) = filter {
                 client
-                    .call::<Value>(nit.node(), SVC_CORE, "log.get", Some(to_value(f)?))
+                    .call::<Value>(node, SVC_CORE, "log.get", Some(to_value(f)?))
                     .await
             } else {
                 Ok(Value::Seq(Vec::new()))
@@ -354,31 +855,31 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
         NitKind::Actions(filter) => {
             if let Some(f) = filter {
                 client
-                    .call::<Value>(nit.node(), SVC_CORE, "action.list", Some(to_value(f)?))
+                    .call::<Value>(node, SVC_CORE, "action.list", Some(to_value(f)?))
                     .await
             } else {
                 Ok(Value::Seq(Vec::new()))
             }
         }
-        NitKind::Items(oid, node) => {
+        NitKind::Items(oid, src) => {
             #[derive(Serialize, Debug)]
             struct PayloadItemList<'a> {
                 i: &'a str,
                 src: Option<&'a str>,
             }
             if let Some(oid) = oid {
-                let node = if let Some(node) = node {
-                    if node.is_empty() || node == "*" || node == "#" {
+                let src = if let Some(src) = src {
+                    if src.is_empty() || src == "*" || src == "#" {
                         None
                     } else {
-                        Some(node.as_str())
+                        Some(src.as_str())
                     }
                 } else {
                     None
                 };
-                let payload = PayloadItemList { i: oid, src: node };
+                let payload = PayloadItemList { i: oid, src };
                 client
-                    .call::<Value>(nit.node(), SVC_CORE, "item.list", Some(to_value(payload)?))
+                    .call::<Value>(node, SVC_CORE, "item.list", Some(to_value(payload)?))
                     .await
             } else {
                 Ok(Value::Seq(Vec::new()))
@@ -387,7 +888,7 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
         NitKind::ItemGetState(oid) => {
             s_call(
                 &client,
-                nit.node(),
+                node,
                 SVC_CORE,
                 "item.state",
                 ParamsId { i: oid.as_str() },
@@ -397,38 +898,36 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
         NitKind::ItemGetConfig(oid) => {
             s_call(
                 &client,
-                nit.node(),
+                node,
                 SVC_CORE,
                 "item.get_config",
                 ParamsId { i: oid },
             )
             .await
         }
-        NitKind::Broker => {
-            client
-                .call(nit.node(), ".broker", "client.list", None)
-                .await
-        }
-        NitKind::Services => client.call(nit.node(), SVC_CORE, "svc.list", None).await,
-        NitKind::Save => client.call(nit.node(), SVC_CORE, "save", None).await,
-        NitKind::Restart => {
-            client
-                .call(nit.node(), SVC_CORE, "core.shutdown", None)
-                .await
-        }
+        NitKind::Broker => client.call(node, ".broker", "client.list", None).await,
+        NitKind::Services => client.call(node, SVC_CORE, "svc.list", None).await,
+        NitKind::Save => client.call(node, SVC_CORE, "save", None).await,
+        NitKind::Restart => client.call(node, SVC_CORE, "core.shutdown", None).await,
         NitKind::SvcRestart(svcs) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "svc.restart", svcs).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "svc.restart",
+                svcs,
+                continue_on_error,
+            )
+            .await
         }
         NitKind::SvcDestroy(svcs) => {
-            process_svcs(&client, nit.node(), SVC_CORE, "svc.undeploy", svcs).await
-        }
-        NitKind::SvcPurge(svcs) => {
-            process_svcs(&client, nit.node(), SVC_CORE, "svc.purge", svcs).await
+            process_svcs(&client, node, SVC_CORE, "svc.undeploy", svcs).await
         }
+        NitKind::SvcPurge(svcs) => process_svcs(&client, node, SVC_CORE, "svc.purge", svcs).await,
         NitKind::SvcGetParams(svc) => {
             s_call(
                 &client,
-                nit.node(),
+                node,
                 SVC_CORE,
                 "svc.get_params",
                 ParamsId { i: svc },
@@ -437,11 +936,11 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
         }
         NitKind::SvcGetParamsX(svc) => {
             let spoint_list = client
-                .call::<Value>(nit.node(), SVC_CORE, "spoint.list", None)
+                .call::<Value>(node, SVC_CORE, "spoint.list", None)
                 .await?;
             let params = s_call(
                 &client,
-                nit.node(),
+                node,
                 SVC_CORE,
                 "svc.get_params",
                 ParamsId { i: svc },
@@ -452,7 +951,7 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
         NitKind::SvcDeploySingle(params) => {
             client
                 .call(
-                    nit.node(),
+                    node,
                     SVC_CORE,
                     "svc.deploy",
                     Some(to_value(TryInto::<SvcDeployPayload>::try_into(&**params)?)?),
@@ -462,33 +961,35 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
         NitKind::SvcDeployMultiple(svcs) => {
             client
                 .call(
-                    nit.node(),
+                    node,
                     SVC_CORE,
                     "svc.deploy",
                     Some(to_value(SvcDeployPayloadVals { svcs })?),
                 )
                 .await
         }
-        NitKind::SvcGetInfo(svc) => client.call(nit.node(), svc, "info", None).await,
+        NitKind::SvcGetInfo(svc) => client.call(node, svc, "info", None).await,
         NitKind::SvcCall(u, svc, method, payload) => {
-            let node = nit.node().to_owned();
+            let node = node.to_owned();
             let u = *u;
             let svc = svc.clone();
             let method = method.clone();
             let payload = payload.clone();
+            let nit = nit.clone();
             tokio::spawn(async move {
                 let result = client.call(&node, &svc, &method, payload).await;
-                ui::command(ui::Command::ProcessSvcCallResult(u, result));
+                audit(conn, &nit, &result);
+                ui::command(ui::Command::ProcessSvcCallResult(conn, u, result));
             });
             Ok(Value::Unit)
         }
         NitKind::ItemGetConfigX(oid) => {
             let items = client
-                .call::<Value>(nit.node(), SVC_CORE, "svc.list", None)
+                .call::<Value>(node, SVC_CORE, "svc.list", None)
                 .await?;
             let params = s_call(
                 &client,
-                nit.node(),
+                node,
                 SVC_CORE,
                 "item.get_config",
                 ParamsId { i: oid },
@@ -499,7 +1000,7 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
         NitKind::ItemDeploySingle(config) => {
             client
                 .call(
-                    nit.node(),
+                    node,
                     SVC_CORE,
                     "item.deploy",
                     Some(to_value(TryInto::<ItemDeployPayload>::try_into(
@@ -511,7 +1012,7 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
         NitKind::ItemDeployMultiple(oids) => {
             client
                 .call(
-                    nit.node(),
+                    node,
                     SVC_CORE,
                     "item.deploy",
                     Some(to_value(ItemDeployPayloadVals { items: oids })?),
@@ -519,69 +1020,225 @@ async fn do_process_command(client: Arc<EvaCloudClient>, nit: Nit) -> EResult<Va
                 .await
         }
         NitKind::ItemDestroy(oids) => {
-            process_items(&client, nit.node(), SVC_CORE, "item.undeploy", oids).await
+            process_items(&client, node, SVC_CORE, "item.undeploy", oids).await
         }
         NitKind::ItemAnnounce(oids) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "item.announce", oids).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "item.announce",
+                oids,
+                continue_on_error,
+            )
+            .await
         }
         NitKind::ItemDisable(oids) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "item.disable", oids).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "item.disable",
+                oids,
+                continue_on_error,
+            )
+            .await
         }
         NitKind::ItemEnable(oids) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "item.enable", oids).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "item.enable",
+                oids,
+                continue_on_error,
+            )
+            .await
         }
         NitKind::UnitAction(p_action) => {
             client
-                .call(nit.node(), SVC_CORE, "action", Some(to_value(p_action)?))
+                .call(node, SVC_CORE, "action", Some(to_value(p_action)?))
                 .await
         }
         NitKind::LmacroRun(p_action) => {
             client
-                .call(nit.node(), SVC_CORE, "run", Some(to_value(p_action)?))
+                .call(node, SVC_CORE, "run", Some(to_value(p_action)?))
                 .await
         }
         NitKind::UnitActionToggle(oid) => {
             client
                 .call(
-                    nit.node(),
+                    node,
                     SVC_CORE,
                     "action.toggle",
                     Some(to_value(ParamsId { i: oid })?),
                 )
                 .await
         }
-        NitKind::LvarSet(oids, p_set) => process_lvar_set(&client, nit.node(), oids, p_set).await,
+        NitKind::LvarSet(oids, p_set) => {
+            process_lvar_set(&client, node, oids, p_set, continue_on_error).await
+        }
         NitKind::LvarReset(oids) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "lvar.reset", oids).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "lvar.reset",
+                oids,
+                continue_on_error,
+            )
+            .await
         }
         NitKind::LvarClear(oids) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "lvar.clear", oids).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "lvar.clear",
+                oids,
+                continue_on_error,
+            )
+            .await
         }
         NitKind::LvarToggle(oids) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "lvar.toggle", oids).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "lvar.toggle",
+                oids,
+                continue_on_error,
+            )
+            .await
         }
         NitKind::LvarIncr(oids) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "lvar.incr", oids).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "lvar.incr",
+                oids,
+                continue_on_error,
+            )
+            .await
         }
         NitKind::LvarDecr(oids) => {
-            process_bulk(&client, nit.node(), SVC_CORE, "lvar.decr", oids).await
+            process_bulk(
+                &client,
+                node,
+                SVC_CORE,
+                "lvar.decr",
+                oids,
+                continue_on_error,
+            )
+            .await
         }
-        NitKind::SPoints => client.call(nit.node(), SVC_CORE, "spoint.list", None).await,
+        NitKind::SPoints => client.call(node, SVC_CORE, "spoint.list", None).await,
+    }
+}
+
+async fn process_command(
+    conn: ConnId,
+    client: Arc<EvaCloudClient>,
+    nit: Nit,
+    reply: CommandReply,
+    parallel: &Semaphore,
+) {
+    // svc calls are audited when completed
+    let audit_nit = if nit.kind().is_mutating() && !matches!(nit.kind(), NitKind::SvcCall(..)) {
+        Some(nit.clone())
+    } else {
+        None
+    };
+    let result = if let Ok(_permit) = parallel.acquire().await {
+        // each command is executed in a dedicated task, so it can be aborted without affecting
+        // the queue
+        let fut = tokio::spawn(do_process_command(conn, client, nit));
+        if let Some(op) = reply.op {
+            op_register_task(op, fut.abort_handle());
+        }
+        match fut.await {
+            Ok(v) => v,
+            Err(e) if e.is_cancelled() => Err(Error::failed(ERR_CANCELLED)),
+            Err(e) => Err(Error::failed(e)),
+        }
+    } else {
+        Err(Error::failed("command processor closed"))
+    };
+    if let Some(nit) = audit_nit {
+        audit(conn, &nit, &result);
     }
+    reply.send(result);
 }
 
 async fn process_commands(
+    conn: ConnId,
     client: Arc<EvaCloudClient>,
-    mut rx: mpsc::Receiver<(Nit, CommandReplyTx)>,
+    mut rx: mpsc::Receiver<(Nit, CommandReply)>,
 ) {
+    let parallel = Arc::new(Semaphore::new(
+        MAX_PARALLEL.load(atomic::Ordering::SeqCst).max(1),
+    ));
+    // mutating commands are queued per node to keep them ordered
+    let mut node_queues: HashMap<String, mpsc::UnboundedSender<(Nit, CommandReply)>> =
+        HashMap::new();
     while let Some((nit, reply)) = rx.recv().await {
-        let result = do_process_command(client.clone(), nit).await;
-        let _r = reply.send(result);
+        if nit.kind().is_local() {
+            let result = do_process_command(conn, client.clone(), nit).await;
+            reply.send(result);
+        } else if nit.kind().is_mutating() {
+            let queue = node_queues
+                .entry(nit.node().name().to_owned())
+                .or_insert_with(|| {
+                    let (tx, mut rx) = mpsc::unbounded_channel::<(Nit, CommandReply)>();
+                    let client = client.clone();
+                    let parallel = parallel.clone();
+                    tokio::spawn(async move {
+                        while let Some((nit, reply)) = rx.recv().await {
+                            process_command(conn, client.clone(), nit, reply, &parallel).await;
+                        }
+                    });
+                    tx
+                });
+            if let Err(e) = queue.send((nit, reply)) {
+                let (_, reply) = e.0;
+                reply.send(Err(Error::failed("node queue closed")));
+            }
+        } else {
+            let client = client.clone();
+            let parallel = parallel.clone();
+            tokio::spawn(async move {
+                process_command(conn, client, nit, reply, &parallel).await;
+            });
+        }
     }
 }
 
-pub fn connect(opts: ConnectionOptions) {
-    disconnect();
+/// Sets the max number of commands processed in parallel, applied on the next connect
+pub fn set_max_parallel(max_parallel: usize) {
+    MAX_PARALLEL.store(max_parallel, atomic::Ordering::SeqCst);
+}
+
+/// Opens a new connection. If there is an existing connection with the same path, it is closed
+pub fn connect(opts: ConnectionOptions) -> ConnId {
+    let existing: Vec<ConnId> = CONNECTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, c)| c.path == opts.path)
+        .map(|(id, _)| *id)
+        .collect();
+    for conn in existing {
+        disconnect(conn);
+    }
+    let conn = NEXT_CONN_ID.fetch_add(1, atomic::Ordering::SeqCst);
+    CONNECTIONS.lock().unwrap().insert(
+        conn,
+        Connection::new(
+            &opts.path,
+            opts.credentials.as_ref().map(|(login, _)| login.as_str()),
+        ),
+    );
     std::thread::spawn(move || {
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
@@ -590,42 +1247,190 @@ pub fn connect(opts: ConnectionOptions) {
         rt.block_on(async move {
             let (tx, rx) = oneshot::channel::<()>();
             let fut = tokio::spawn(async move {
-                launch_connection(&opts.path, tx, opts.timeout, opts.credentials).await;
-                crate::CLIENT_CHANNEL.lock().unwrap().take();
+                supervise_connection(conn, opts, tx).await;
+                // the connection has not been established
+                let connection = CONNECTIONS.lock().unwrap().remove(&conn);
+                if let Some(c) = connection {
+                    c.shutdown();
+                    ui::command(ui::Command::MarkDisconnected(conn));
+                }
             });
-            crate::CONNECTION.lock().unwrap().replace(fut);
+            if with_connection(conn, |c| c.fut.replace(fut)).is_none() {
+                // disconnected while starting
+                return;
+            }
             let _r = rx.await;
         });
     });
+    conn
 }
 
-pub fn disconnect() {
-    crate::CLIENT_CHANNEL.lock().unwrap().take();
-    if let Some(fut) = crate::CONNECTION.lock().unwrap().take() {
-        fut.abort();
+pub fn disconnect(conn: ConnId) {
+    let connection = CONNECTIONS.lock().unwrap().remove(&conn);
+    if let Some(c) = connection {
+        c.shutdown();
+        let mut last_nit = crate::LAST_NIT.lock().unwrap();
+        if last_nit.as_ref().map_or(false, |nit| nit.conn() == conn) {
+            last_nit.take();
+        }
+        ui::command(ui::Command::MarkDisconnected(conn));
     }
-    if let Some(fut) = crate::NIT_HANDLER.lock().unwrap().take() {
-        fut.abort();
+}
+
+pub fn disconnect_all() {
+    let connections: Vec<ConnId> = CONNECTIONS.lock().unwrap().keys().copied().collect();
+    for conn in connections {
+        disconnect(conn);
     }
-    crate::LAST_NIT.lock().unwrap().take();
-    ui::command(ui::Command::MarkDisconnected);
-    let mut watchers = WATCHERS.lock().unwrap();
-    for fut in watchers.values() {
-        fut.abort();
+}
+
+/// A connection for the headless mode. The commands are processed in the caller's runtime with
+/// the same handlers the UI uses
+pub struct HeadlessClient {
+    client: Arc<EvaCloudClient>,
+    system_name: String,
+    path: String,
+    login: Option<String>,
+}
+
+impl HeadlessClient {
+    pub async fn connect(opts: ConnectionOptions) -> EResult<Self> {
+        let login = opts.credentials.as_ref().map(|(login, _)| login.clone());
+        let client = open_client(&opts.path, opts.timeout, opts.credentials).await?;
+        let (cloud_client, system_name, _) = open_cloud_client(client).await?;
+        Ok(Self {
+            client: Arc::new(cloud_client),
+            system_name,
+            path: opts.path,
+            login,
+        })
+    }
+    /// Name of the node the client is connected to
+    pub fn system_name(&self) -> &str {
+        &self.system_name
+    }
+    pub async fn process(&self, nit: Nit) -> EResult<Value> {
+        if nit.kind().is_local() || matches!(nit.kind(), NitKind::SvcCall(_, _, _, _)) {
+            return Err(Error::unsupported("the operation requires UI"));
+        }
+        check_writable(&nit)?;
+        let mutating = nit.kind().is_mutating();
+        let result = do_process_command(nit.conn(), self.client.clone(), nit.clone()).await;
+        if mutating {
+            audit::log(&self.path, self.login.as_deref(), &nit, &result);
+        }
+        result
     }
-    watchers.clear();
+    /// Calls a service method directly. The call is rejected in read-only mode and written to
+    /// the audit log
+    pub async fn call(&self, nit: Nit) -> EResult<Value> {
+        let (svc, method, payload) = if let NitKind::SvcCall(_, svc, method, payload) = nit.kind() {
+            (svc, method, payload)
+        } else {
+            return Err(Error::invalid_params("not a service call"));
+        };
+        check_writable(&nit)?;
+        let result = self
+            .client
+            .call(nit.node().name(), svc, method, payload.clone())
+            .await;
+        audit::log(&self.path, self.login.as_deref(), &nit, &result);
+        result
+    }
+}
+
+/// The connection path, e.g. the bus socket or the HTTP API URL
+pub fn connection_path(conn: ConnId) -> Option<String> {
+    with_connection(conn, |c| c.path.clone())
+}
+
+/// Name of the bus client of the connection
+pub fn client_name(conn: ConnId) -> Option<String> {
+    with_connection(conn, |c| c.client_name.clone()).flatten()
 }
 
 pub fn call<T: DeserializeOwned>(nit: Nit) -> EResult<T> {
-    let ch = get_client_channel()?;
+    let op = CURRENT_OP.with(std::cell::Cell::get);
+    if let Some(op) = op {
+        if OPS.lock().unwrap().get(&op).map_or(true, |o| o.cancelled) {
+            return Err(Error::failed(ERR_CANCELLED));
+        }
+    }
+    check_writable(&nit)?;
+    let ch = get_client_channel(nit.conn())?;
     let (tx, rx) = mpsc_std::sync_channel(1);
-    ch.try_send((nit, tx))
-        .map_err(|_| Error::failed("Operations pending"))?;
+    ch.try_send((
+        nit,
+        CommandReply {
+            kind: Some(CommandReplyKind::Sync(tx)),
+            op,
+        },
+    ))
+    .map_err(|_| Error::failed("Too many operations pending"))?;
     let val = T::deserialize(rx.recv().map_err(Error::failed)??)?;
     Ok(val)
 }
 
-pub fn item_state(node: &str, oid: OID) -> EResult<ItemState> {
+/// Sends a command without waiting for its result. The result is delivered to the UI with
+/// ui::Command::ProcessCallResult tagged with the given UUID
+pub fn call_async(nit: Nit, u: uuid::Uuid) -> EResult<()> {
+    check_writable(&nit)?;
+    let ch = get_client_channel(nit.conn())?;
+    ch.try_send((
+        nit,
+        CommandReply {
+            kind: Some(CommandReplyKind::Async(u)),
+            op: None,
+        },
+    ))
+    .map_err(|_| Error::failed("Too many operations pending"))?;
+    Ok(())
+}
+
+/// Runs a blocking operation (usually in a separate thread), all calls made by the operation can
+/// be aborted with cancel()
+pub fn run_op<F, R>(op: uuid::Uuid, f: F) -> EResult<R>
+where
+    F: FnOnce() -> EResult<R>,
+{
+    OPS.lock().unwrap().insert(op, Op::default());
+    CURRENT_OP.with(|c| c.set(Some(op)));
+    let result = f();
+    CURRENT_OP.with(|c| c.set(None));
+    let cancelled = OPS
+        .lock()
+        .unwrap()
+        .remove(&op)
+        .map_or(true, |o| o.cancelled);
+    if cancelled {
+        Err(Error::failed(ERR_CANCELLED))
+    } else {
+        result
+    }
+}
+
+/// Runs the closure outside of the current operation, so its calls are not affected by the
+/// operation cancellation (e.g. to roll back changes of a cancelled operation)
+pub fn detached<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let op = CURRENT_OP.with(|c| c.replace(None));
+    let result = f();
+    CURRENT_OP.with(|c| c.set(op));
+    result
+}
+
+pub fn cancel(op: uuid::Uuid) {
+    if let Some(o) = OPS.lock().unwrap().get_mut(&op) {
+        o.cancelled = true;
+        for task in o.tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+pub fn item_state(node: &NodeRef, oid: OID) -> EResult<ItemState> {
     let mut res: Vec<ItemState> = call(Arc::new(NitData::new_item_get_state(node, oid.clone())))?;
     if res.len() == 1 {
         let state = res.remove(0);
@@ -639,13 +1444,48 @@ pub fn item_state(node: &str, oid: OID) -> EResult<ItemState> {
     }
 }
 
-fn get_client_channel() -> EResult<CommandTx> {
-    if let Some(client) = crate::CLIENT_CHANNEL.lock().unwrap().as_ref() {
-        Ok(client.clone())
+fn get_client_channel(conn: ConnId) -> EResult<CommandTx> {
+    if let Some(client) = with_connection(conn, |c| c.channel.clone()).flatten() {
+        Ok(client)
     } else {
         Err(Error::io("Not connected"))
     }
 }
 
-pub type CommandReplyTx = mpsc_std::SyncSender<EResult<Value>>;
-pub type CommandTx = mpsc::Sender<(Nit, CommandReplyTx)>;
+pub const ERR_CANCELLED: &str = "Operation cancelled";
+
+enum CommandReplyKind {
+    Sync(mpsc_std::SyncSender<EResult<Value>>),
+    Async(uuid::Uuid),
+}
+
+pub struct CommandReply {
+    kind: Option<CommandReplyKind>,
+    op: Option<uuid::Uuid>,
+}
+
+impl CommandReply {
+    fn send(mut self, result: EResult<Value>) {
+        self.reply(result);
+    }
+    fn reply(&mut self, result: EResult<Value>) {
+        match self.kind.take() {
+            Some(CommandReplyKind::Sync(tx)) => {
+                let _r = tx.send(result);
+            }
+            Some(CommandReplyKind::Async(u)) => {
+                ui::command(ui::Command::ProcessCallResult(u, result));
+            }
+            None => {}
+        }
+    }
+}
+
+impl Drop for CommandReply {
+    fn drop(&mut self) {
+        // the command has been dropped unprocessed (e.g. the connection is lost)
+        self.reply(Err(Error::io("Not connected")));
+    }
+}
+
+pub type CommandTx = mpsc::Sender<(Nit, CommandReply)>;
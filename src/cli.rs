@@ -0,0 +1,501 @@
+use crate::bus::{self, HeadlessClient};
+use crate::common::{
+    save_data, Args, BulkResult, Config, ConnId, ConnectionOptions, FileFormat, ItemInfo, Nit,
+    NitData, NodeRef, PayloadLvarSet, SvcData,
+};
+use crate::resources::{
+    export_data, export_item, export_svc, find_export_entry, import_nit, load_cloud_deploy,
+    ResourceKind,
+};
+use crate::vars;
+use clap::Subcommand;
+use eva_common::prelude::*;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Headless commands do not belong to any UI connection
+const HEADLESS_CONN: ConnId = 0;
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[clap(about = "Item operations")]
+    Items {
+        #[clap(subcommand)]
+        command: ItemsCommand,
+    },
+    #[clap(about = "Service operations")]
+    Svc {
+        #[clap(subcommand)]
+        command: SvcCommand,
+    },
+    #[clap(about = "Lvar operations")]
+    Lvar {
+        #[clap(subcommand)]
+        command: LvarCommand,
+    },
+    #[clap(about = "Export resources into a YAML, JSON or TOML file")]
+    Export {
+        #[clap(short = 'n', long = "node", help = "Source node")]
+        node: Option<String>,
+        #[clap(short = 'o', long = "output", help = "Output file")]
+        file: String,
+        #[clap(
+            short = 'f',
+            long = "format",
+            value_enum,
+            help = "Output format (default: by the file extension or YAML)"
+        )]
+        format: Option<FileFormat>,
+        #[clap(
+            long = "cloud-deploy",
+            help = "Export as cloud deploy for the specified node"
+        )]
+        cloud_node: Option<String>,
+        #[clap(long = "merge", help = "Merge with the existing file")]
+        merge: bool,
+        #[clap(
+            short = 's',
+            long = "secret",
+            help = "Config path to replace with a ${VAR} placeholder, e.g. config.*.password"
+        )]
+        secrets: Vec<String>,
+        #[clap(value_enum)]
+        kind: ResourceKind,
+        #[clap(
+            required = true,
+            help = "Service IDs or item OIDs (masks are allowed for items)"
+        )]
+        resources: Vec<String>,
+    },
+    #[clap(about = "Import resources from a YAML, JSON or TOML file")]
+    Import {
+        #[clap(short = 'n', long = "node", help = "Target node")]
+        node: Option<String>,
+        #[clap(
+            short = 'e',
+            long = "env-file",
+            help = "Environment file with placeholder variables"
+        )]
+        env_files: Vec<String>,
+        #[clap(value_enum)]
+        kind: ResourceKind,
+        file: String,
+    },
+    #[clap(about = "Deploy services and items of a cloud deploy file to its nodes")]
+    Deploy {
+        #[clap(
+            short = 'e',
+            long = "env-file",
+            help = "Environment file with placeholder variables"
+        )]
+        env_files: Vec<String>,
+        file: String,
+    },
+    #[clap(about = "Call a service method")]
+    Call {
+        node: String,
+        svc: String,
+        method: String,
+        #[clap(help = "Call parameters (JSON)")]
+        params: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ItemsCommand {
+    #[clap(about = "List items")]
+    List {
+        #[clap(short = 'n', long = "node", help = "Target node")]
+        node: Option<String>,
+        #[clap(short = 's', long = "source", help = "Item source node")]
+        source: Option<String>,
+        #[clap(default_value = "#")]
+        mask: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SvcCommand {
+    #[clap(about = "List services")]
+    List {
+        #[clap(short = 'n', long = "node", help = "Target node")]
+        node: Option<String>,
+    },
+    #[clap(about = "Restart services")]
+    Restart {
+        #[clap(short = 'n', long = "node", help = "Target node")]
+        node: Option<String>,
+        #[clap(
+            long = "continue-on-error",
+            help = "Do not stop at the first failed service"
+        )]
+        continue_on_error: bool,
+        #[clap(required = true)]
+        svcs: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LvarCommand {
+    #[clap(about = "Set lvar status and/or value")]
+    Set {
+        #[clap(short = 'n', long = "node", help = "Target node")]
+        node: Option<String>,
+        #[clap(short = 's', long = "status")]
+        status: Option<ItemStatus>,
+        #[clap(short = 'v', long = "value")]
+        value: Option<String>,
+        #[clap(
+            long = "continue-on-error",
+            help = "Do not stop at the first failed lvar"
+        )]
+        continue_on_error: bool,
+        #[clap(required = true)]
+        oids: Vec<String>,
+    },
+}
+
+/// Runs a headless command, returns the process exit code
+pub fn run(args: &Args, command: Command) -> i32 {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    match rt.block_on(execute(args, command)) {
+        Ok(true) => 0,
+        Ok(false) => 2,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// Returns the connection options and the read-only flag of the connection profile
+fn connection_options(args: &Args) -> EResult<(ConnectionOptions, bool)> {
+    if let Some(name) = args.profile() {
+        let config = Config::load_from_disk()?
+            .ok_or_else(|| Error::not_found("no config file, no profiles"))?;
+        let profile = config
+            .profile(name)
+            .ok_or_else(|| Error::not_found(format!("Connection profile not found: {}", name)))?;
+        let mut opts = profile.connection_options();
+        if let Some(password) = args.password() {
+            if let Some(creds) = opts.credentials.as_mut() {
+                creds.1 = password.to_owned();
+            }
+        }
+        Ok((opts, profile.read_only))
+    } else {
+        let opts = args
+            .connection_options()
+            .ok_or_else(|| Error::invalid_params("connection path or profile not specified"))?;
+        Ok((opts, false))
+    }
+}
+
+fn target_node(client: &HeadlessClient, node: Option<String>) -> NodeRef {
+    NodeRef::new(
+        HEADLESS_CONN,
+        node.as_deref().unwrap_or_else(|| client.system_name()),
+    )
+}
+
+/// Returns false if some resources of a bulk operation have failed
+async fn execute(args: &Args, command: Command) -> EResult<bool> {
+    let (opts, profile_read_only) = connection_options(args)?;
+    bus::set_read_only(args.read_only() || profile_read_only);
+    let client = HeadlessClient::connect(opts).await?;
+    let json = args.json();
+    match command {
+        Command::Items {
+            command: ItemsCommand::List { node, source, mask },
+        } => {
+            let node = target_node(&client, node);
+            let result = client
+                .process(Arc::new(NitData::new_item_list(&node, Some(mask), source)))
+                .await?;
+            if json {
+                print_json(&result)?;
+            } else {
+                print_items(Vec::<ItemInfo>::deserialize(result)?);
+            }
+            Ok(true)
+        }
+        Command::Svc {
+            command: SvcCommand::List { node },
+        } => {
+            let node = target_node(&client, node);
+            let result = client
+                .process(Arc::new(NitData::new_services(&node)))
+                .await?;
+            if json {
+                print_json(&result)?;
+            } else {
+                print_svcs(Vec::<SvcData>::deserialize(result)?);
+            }
+            Ok(true)
+        }
+        Command::Svc {
+            command:
+                SvcCommand::Restart {
+                    node,
+                    continue_on_error,
+                    svcs,
+                },
+        } => {
+            let node = target_node(&client, node);
+            let mut nd = NitData::new_svc_restart(&node, svcs);
+            if continue_on_error {
+                nd = nd.with_continue_on_error();
+            }
+            process_bulk(&client, Arc::new(nd), json).await
+        }
+        Command::Lvar {
+            command:
+                LvarCommand::Set {
+                    node,
+                    status,
+                    value,
+                    continue_on_error,
+                    oids,
+                },
+        } => {
+            let node = target_node(&client, node);
+            let p_set = PayloadLvarSet {
+                i: None,
+                status,
+                value: value.map(|v| v.parse()).transpose()?,
+            };
+            let mut nd = NitData::new_lvar_set(&node, oids, p_set);
+            if continue_on_error {
+                nd = nd.with_continue_on_error();
+            }
+            process_bulk(&client, Arc::new(nd), json).await
+        }
+        Command::Export {
+            node,
+            file,
+            format,
+            cloud_node,
+            merge,
+            secrets,
+            kind,
+            resources,
+        } => {
+            let secrets = vars::parse_paths(&secrets.join(","))?;
+            let mut var_names = Vec::new();
+            let node = target_node(&client, node);
+            let cloud_deploy = cloud_node.is_some();
+            let mut data = export_data(&file, cloud_deploy, merge)?;
+            let entry =
+                find_export_entry(&mut data, kind.key(), cloud_deploy, cloud_node.as_deref())?;
+            let mut cnt = 0;
+            match kind {
+                ResourceKind::Svcs => {
+                    for res in resources {
+                        let mut svc_config = client
+                            .process(Arc::new(NitData::new_svc_get_params(&node, res.clone())))
+                            .await?;
+                        var_names.extend(vars::strip_secrets(&res, &mut svc_config, &secrets));
+                        export_svc(entry, &res, svc_config);
+                        cnt += 1;
+                    }
+                }
+                ResourceKind::Items => {
+                    let mut oids = Vec::new();
+                    for mask in resources {
+                        let result = client
+                            .process(Arc::new(NitData::new_item_list(&node, Some(mask), None)))
+                            .await?;
+                        for item in Vec::<ItemInfo>::deserialize(result)? {
+                            let oid = item.oid.to_string();
+                            if !oids.contains(&oid) {
+                                oids.push(oid);
+                            }
+                        }
+                    }
+                    for oid in oids {
+                        let mut item_config = client
+                            .process(Arc::new(NitData::new_item_get_config(&node, oid.clone())))
+                            .await?;
+                        var_names.extend(vars::strip_secrets(&oid, &mut item_config, &secrets));
+                        export_item(entry, &oid, item_config);
+                        cnt += 1;
+                    }
+                }
+            }
+            let format = format
+                .or_else(|| FileFormat::from_extension(&file))
+                .unwrap_or_default();
+            save_data(&file, &data, format)?;
+            eprintln!("{} {} exported", cnt, kind.key());
+            if !var_names.is_empty() {
+                eprintln!("secrets replaced with variables: {}", var_names.join(", "));
+            }
+            Ok(true)
+        }
+        Command::Import {
+            node,
+            env_files,
+            kind,
+            file,
+        } => {
+            let node = target_node(&client, node);
+            let (nd, cnt) = import_nit(&node, kind, &file, &env_files)?;
+            client.process(Arc::new(nd)).await?;
+            eprintln!("{} {} imported", cnt, kind.key());
+            Ok(true)
+        }
+        Command::Deploy { env_files, file } => {
+            let mut deploy = load_cloud_deploy(&file)?
+                .ok_or_else(|| Error::invalid_data("not a cloud deploy file"))?;
+            vars::resolve_env(
+                deploy
+                    .iter_mut()
+                    .flat_map(|d| d.svcs.iter_mut().chain(d.items.iter_mut()))
+                    .collect(),
+                &env_files,
+            )?;
+            let mut report = Vec::with_capacity(deploy.len());
+            for d in deploy {
+                eprintln!("deploying {} ({})", d.node, d.summary());
+                let mut error = None;
+                for nit in d.nits(HEADLESS_CONN) {
+                    if let Err(e) = client.process(Arc::new(nit)).await {
+                        error = Some(e.to_string());
+                        break;
+                    }
+                }
+                report.push(BulkResult { id: d.node, error });
+            }
+            print_report(&report, json)?;
+            Ok(report.iter().all(BulkResult::is_ok))
+        }
+        Command::Call {
+            node,
+            svc,
+            method,
+            params,
+        } => {
+            let params: Option<Value> = params
+                .map(|p| serde_json::from_str(&p))
+                .transpose()
+                .map_err(Error::invalid_params)?;
+            let nit = Arc::new(NitData::new_svc_call(
+                uuid::Uuid::nil(),
+                &NodeRef::new(HEADLESS_CONN, &node),
+                svc,
+                method,
+                params,
+            ));
+            let result = client.call(nit).await?;
+            print_json(&result)?;
+            Ok(true)
+        }
+    }
+}
+
+async fn process_bulk(client: &HeadlessClient, nit: Nit, json: bool) -> EResult<bool> {
+    let continue_on_error = nit.continue_on_error();
+    let result = client.process(nit).await?;
+    if !continue_on_error {
+        return Ok(true);
+    }
+    let report = Vec::<BulkResult>::deserialize(result)?;
+    print_report(&report, json)?;
+    Ok(report.iter().all(BulkResult::is_ok))
+}
+
+fn print_report(report: &[BulkResult], json: bool) -> EResult<()> {
+    if json {
+        print_json(&to_value(report)?)?;
+    } else {
+        print_table(
+            &["id", "result"],
+            report
+                .iter()
+                .map(|r| {
+                    vec![
+                        r.id.clone(),
+                        r.error.clone().unwrap_or_else(|| "OK".to_owned()),
+                    ]
+                })
+                .collect(),
+        );
+    }
+    Ok(())
+}
+
+fn print_json(value: &Value) -> EResult<()> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).map_err(Error::invalid_data)?
+    );
+    Ok(())
+}
+
+fn print_items(data: Vec<ItemInfo>) {
+    let rows = data
+        .into_iter()
+        .map(|d| {
+            vec![
+                d.oid.to_string(),
+                d.enabled.to_string(),
+                d.connected.to_string(),
+                d.status.map(|s| s.to_string()).unwrap_or_default(),
+                d.value.map(|v| v.to_string()).unwrap_or_default(),
+                d.node,
+            ]
+        })
+        .collect();
+    print_table(
+        &["oid", "enabled", "connected", "status", "value", "node"],
+        rows,
+    );
+}
+
+fn print_svcs(data: Vec<SvcData>) {
+    let rows = data
+        .into_iter()
+        .map(|d| {
+            vec![
+                d.id,
+                d.status,
+                d.pid.map(|p| p.to_string()).unwrap_or_default(),
+                d.launcher,
+            ]
+        })
+        .collect();
+    print_table(&["id", "status", "pid", "launcher"], rows);
+}
+
+fn print_table(header: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (w, col) in widths.iter_mut().zip(row) {
+            *w = (*w).max(col.chars().count());
+        }
+    }
+    let format_row = |cols: Vec<&str>| {
+        cols.iter()
+            .zip(&widths)
+            .map(|(col, w)| format!("{:w$}", col, w = w))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_owned()
+    };
+    println!("{}", format_row(header.to_vec()));
+    println!(
+        "{}",
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<String>>()
+            .join("  ")
+    );
+    for row in &rows {
+        println!("{}", format_row(row.iter().map(String::as_str).collect()));
+    }
+}
@@ -19,6 +19,9 @@ use std::time::Duration;
 pub const LAUNCHER_MAIN: &str = "eva.launcher.main";
 pub const LAUNCHER_PFX: &str = "eva.launcher.";
 pub const DEFAULT_TIMEOUT_SEC: f64 = 5.0;
+pub const DEFAULT_MAX_PARALLEL: usize = 4;
+pub const MAX_RECENT_CONNECTIONS: usize = 10;
+pub const PROFILE_COLORS: &[&str] = &["red", "orange", "yellow", "green", "blue", "purple"];
 
 pub const DEFAULT_BUS_TYPE: &str = "native";
 pub const DEFAULT_BUS_PATH: &str = "var/bus.ipc";
@@ -43,9 +46,49 @@ pub struct Args {
     user: Option<String>,
     #[clap(short = 'P', long = "password")]
     password: Option<String>,
+    #[clap(
+        long = "max-parallel",
+        help = "Max number of operations executed in parallel"
+    )]
+    max_parallel: Option<usize>,
+    #[clap(
+        long = "profile",
+        help = "Connect using a saved connection profile",
+        conflicts_with = "connection_path"
+    )]
+    profile: Option<String>,
+    #[clap(
+        short = 'J',
+        long = "json",
+        help = "Output JSON instead of tables (headless mode)"
+    )]
+    json: bool,
+    #[clap(long = "read-only", help = "Block all operations which modify nodes")]
+    read_only: bool,
+    #[clap(subcommand)]
+    command: Option<crate::cli::Command>,
 }
 
 impl Args {
+    /// Headless mode command, if specified, the UI is not started
+    pub fn take_command(&mut self) -> Option<crate::cli::Command> {
+        self.command.take()
+    }
+    pub fn json(&self) -> bool {
+        self.json
+    }
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+    pub fn max_parallel(&self) -> Option<usize> {
+        self.max_parallel
+    }
     pub fn connection_options(&self) -> Option<ConnectionOptions> {
         self.connection_path.as_ref().map(|path| ConnectionOptions {
             path: if let Some(p) = path.strip_prefix("rt://") {
@@ -86,31 +129,148 @@ pub unsafe fn splitter_sizes(splitter: &QPtr<QSplitter>) -> Option<(c_int, c_int
     }
 }
 
-pub fn load_yaml<O: DeserializeOwned>(fname: &str) -> EResult<Option<O>> {
+/// Resource file format
+#[derive(clap::ValueEnum, Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FileFormat {
+    #[default]
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl FileFormat {
+    pub fn from_extension(fname: &str) -> Option<Self> {
+        match Path::new(fname)
+            .extension()?
+            .to_str()?
+            .to_lowercase()
+            .as_str()
+        {
+            "yml" | "yaml" => Some(FileFormat::Yaml),
+            "json" => Some(FileFormat::Json),
+            "toml" => Some(FileFormat::Toml),
+            _ => None,
+        }
+    }
+    /// Detects the format by the first meaningful line, YAML is the default
+    pub fn detect(buf: &[u8]) -> Self {
+        let s = String::from_utf8_lossy(buf);
+        let line = if let Some(line) = s
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+        {
+            line
+        } else {
+            return FileFormat::Yaml;
+        };
+        if line.starts_with('{')
+            || (line.starts_with('[') && serde_json::from_slice::<Value>(buf).is_ok())
+        {
+            FileFormat::Json
+        } else if line.starts_with('[')
+            || line
+                .find('=')
+                .map_or(false, |pos| line.find(':').map_or(true, |c| pos < c))
+        {
+            FileFormat::Toml
+        } else {
+            FileFormat::Yaml
+        }
+    }
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileFormat::Yaml => "yml",
+            FileFormat::Json => "json",
+            FileFormat::Toml => "toml",
+        }
+    }
+    pub fn parse<O: DeserializeOwned>(self, buf: &[u8]) -> EResult<O> {
+        match self {
+            FileFormat::Yaml => serde_yaml::from_slice(buf).map_err(Error::invalid_data),
+            FileFormat::Json => serde_json::from_slice(buf).map_err(Error::invalid_data),
+            FileFormat::Toml => {
+                toml::from_str(std::str::from_utf8(buf).map_err(Error::invalid_data)?)
+                    .map_err(Error::invalid_data)
+            }
+        }
+    }
+    pub fn serialize<S: Serialize>(self, value: &S) -> EResult<Vec<u8>> {
+        match self {
+            FileFormat::Yaml => serde_yaml::to_vec(value).map_err(Error::invalid_data),
+            FileFormat::Json => {
+                let mut buf = serde_json::to_vec_pretty(value).map_err(Error::invalid_data)?;
+                buf.extend(CRLF.as_bytes());
+                Ok(buf)
+            }
+            FileFormat::Toml => {
+                // TOML has no null values, so unset fields are omitted
+                let mut value = serde_json::to_value(value).map_err(Error::invalid_data)?;
+                strip_nulls(&mut value);
+                Ok(toml::to_string(&value)
+                    .map_err(Error::invalid_data)?
+                    .into_bytes())
+            }
+        }
+    }
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FileFormat::Yaml => "YAML",
+                FileFormat::Json => "JSON",
+                FileFormat::Toml => "TOML",
+            }
+        )
+    }
+}
+
+fn strip_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(m) => {
+            m.retain(|_, v| !v.is_null());
+            m.values_mut().for_each(strip_nulls);
+        }
+        serde_json::Value::Array(a) => a.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+/// Loads a YAML, JSON or TOML file. The format is taken from the file extension or detected
+/// from the content
+pub fn load_data<O: DeserializeOwned>(fname: &str) -> EResult<Option<O>> {
     let path = Path::new(fname);
     if path.exists() {
         let mut f = std::fs::File::open(path)?;
         let mut buf = Vec::new();
         f.read_to_end(&mut buf)?;
-        Ok(Some(
-            serde_yaml::from_slice(&buf).map_err(Error::invalid_data)?,
-        ))
+        let format = FileFormat::from_extension(fname).unwrap_or_else(|| FileFormat::detect(&buf));
+        Ok(Some(format.parse(&buf)?))
     } else {
         Ok(None)
     }
 }
 
-pub fn save_yaml<S: Serialize>(fname: &str, value: &S) -> EResult<()> {
+pub fn save_data<S: Serialize>(fname: &str, value: &S, format: FileFormat) -> EResult<()> {
+    let data = format.serialize(value)?;
     let mut f = std::fs::OpenOptions::new()
         .create(true)
         .append(false)
         .truncate(true)
         .write(true)
         .open(fname)?;
-    f.write_all(&serde_yaml::to_vec(value).map_err(Error::invalid_data)?)?;
+    f.write_all(&data)?;
     Ok(())
 }
 
+pub fn save_yaml<S: Serialize>(fname: &str, value: &S) -> EResult<()> {
+    save_data(fname, value, FileFormat::Yaml)
+}
+
 #[derive(Deserialize)]
 pub struct SPointInfo {
     pub name: String,
@@ -379,6 +539,14 @@ pub struct Config {
     connection: ConnectionOptionsSaved,
     #[serde(default)]
     auto_reload: f64,
+    #[serde(default)]
+    max_parallel: Option<usize>,
+    #[serde(default)]
+    continue_on_error: bool,
+    #[serde(default)]
+    profiles: Vec<ConnectionProfile>,
+    #[serde(default)]
+    recent: Vec<String>,
     ui: UiConfig,
 }
 
@@ -387,9 +555,47 @@ impl Config {
         Self {
             connection: con_opts.into(),
             auto_reload,
+            max_parallel: None,
+            continue_on_error: false,
+            profiles: <_>::default(),
+            recent: <_>::default(),
             ui: <_>::default(),
         }
     }
+    pub fn profiles(&self) -> &[ConnectionProfile] {
+        &self.profiles
+    }
+    pub fn profile(&self, name: &str) -> Option<&ConnectionProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+    /// Inserts a new profile or replaces the existing one with the same name
+    pub fn set_profile(&mut self, profile: ConnectionProfile) {
+        if let Some(p) = self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            *p = profile;
+        } else {
+            self.profiles.push(profile);
+            self.profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+    }
+    pub fn remove_profile(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+        self.recent.retain(|r| r != name);
+    }
+    /// Names of recently used profiles, the most recent first
+    pub fn recent(&self) -> &[String] {
+        &self.recent
+    }
+    pub fn mark_recent(&mut self, name: &str) {
+        self.recent.retain(|r| r != name);
+        self.recent.insert(0, name.to_owned());
+        self.recent.truncate(MAX_RECENT_CONNECTIONS);
+    }
+    pub fn max_parallel(&self) -> Option<usize> {
+        self.max_parallel
+    }
+    pub fn set_continue_on_error(&mut self, value: bool) {
+        self.continue_on_error = value;
+    }
     pub fn set_auto_reload(&mut self, r: f64) {
         self.auto_reload = r;
     }
@@ -426,7 +632,11 @@ impl Config {
                 .set_sizes(&new_size(size.s1, size.s2));
         }
         u.window.auto_reload.set_value(self.auto_reload);
+        u.window
+            .action_continue_on_error
+            .set_checked(self.continue_on_error);
         u.dialog_connect.set_data(self.connection.clone().into());
+        u.dialog_connect.set_profiles(&self.profiles);
     }
     pub fn save_to_disk(&self) {
         match serde_yaml::to_vec(&self) {
@@ -515,6 +725,50 @@ pub struct ConnectionOptionsSaved {
     timeout: f64,
 }
 
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ConnectionProfile {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub login: String,
+    pub timeout: f64,
+    #[serde(default)]
+    pub auto_reload: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl ConnectionProfile {
+    pub fn new(
+        name: &str,
+        opts: &ConnectionOptions,
+        auto_reload: f64,
+        color: Option<String>,
+        read_only: bool,
+    ) -> Self {
+        let saved: ConnectionOptionsSaved = opts.clone().into();
+        Self {
+            name: name.to_owned(),
+            path: saved.path,
+            login: saved.login,
+            timeout: saved.timeout,
+            auto_reload,
+            color,
+            read_only,
+        }
+    }
+    pub fn connection_options(&self) -> ConnectionOptions {
+        ConnectionOptionsSaved {
+            path: self.path.clone(),
+            login: self.login.clone(),
+            timeout: self.timeout,
+        }
+        .into()
+    }
+}
+
 #[derive(Deserialize)]
 pub struct BrokerInfo {
     pub clients: Vec<BrokerClientInfo>,
@@ -581,7 +835,7 @@ pub struct ItemState {
     pub value: Option<Value>,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ItemInfo {
     pub oid: OID,
     pub status: Option<ItemStatus>,
@@ -593,6 +847,44 @@ pub struct ItemInfo {
     pub enabled: bool,
 }
 
+/// Result of a bulk operation for a single resource
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BulkResult {
+    pub id: String,
+    pub error: Option<String>,
+}
+
+impl BulkResult {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Item state event, published by the node core to the bus
+#[derive(Deserialize)]
+pub struct StateEvent {
+    pub status: ItemStatus,
+    pub value: Option<Value>,
+    pub t: f64,
+    // remote events only
+    pub node: Option<String>,
+    pub connected: Option<bool>,
+}
+
+impl StateEvent {
+    pub fn to_item_info(&self, oid: OID, node: &str) -> ItemInfo {
+        ItemInfo {
+            oid,
+            status: Some(self.status),
+            value: self.value.clone(),
+            t: Some(self.t),
+            node: node.to_owned(),
+            connected: self.connected.unwrap_or(true),
+            enabled: true,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ItemConfig {
     pub oid: OID,
@@ -641,9 +933,41 @@ impl Eq for NodeInfo {}
 
 pub type Nit = Arc<NitData>;
 
+/// Connection identifier, unique for the application session
+pub type ConnId = usize;
+
+/// A node of a particular connection
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct NodeRef {
+    conn: ConnId,
+    name: String,
+}
+
+impl NodeRef {
+    pub fn new(conn: ConnId, name: &str) -> Self {
+        Self {
+            conn,
+            name: name.to_owned(),
+        }
+    }
+    pub fn conn(&self) -> ConnId {
+        self.conn
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for NodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
 pub struct NitData {
-    node: String,
+    node: NodeRef,
     kind: NitKind,
+    continue_on_error: bool,
 }
 
 impl fmt::Display for NitData {
@@ -653,251 +977,177 @@ impl fmt::Display for NitData {
 }
 
 impl NitData {
-    pub fn new_state(node: &str) -> Self {
+    fn new(node: &NodeRef, kind: NitKind) -> Self {
         Self {
-            node: node.to_owned(),
-            kind: NitKind::State,
+            node: node.clone(),
+            kind,
+            continue_on_error: false,
         }
     }
-    pub fn new_log(node: &str, filter: LogFilter) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::Log(Some(filter)),
-        }
+    pub fn new_state(node: &NodeRef) -> Self {
+        Self::new(node, NitKind::State)
     }
-    pub fn new_actions(node: &str, filter: ActionFilter) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::Actions(Some(filter)),
-        }
+    pub fn new_log(node: &NodeRef, filter: LogFilter) -> Self {
+        Self::new(node, NitKind::Log(Some(filter)))
     }
-    pub fn new_save(node: &str) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::Save,
-        }
+    pub fn new_actions(node: &NodeRef, filter: ActionFilter) -> Self {
+        Self::new(node, NitKind::Actions(Some(filter)))
     }
-    pub fn new_restart(node: &str) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::Restart,
-        }
+    pub fn new_save(node: &NodeRef) -> Self {
+        Self::new(node, NitKind::Save)
     }
-    pub fn new_services(node: &str) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::Services,
-        }
+    pub fn new_restart(node: &NodeRef) -> Self {
+        Self::new(node, NitKind::Restart)
     }
-    pub fn new_spoints(node: &str) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SPoints,
-        }
+    pub fn new_services(node: &NodeRef) -> Self {
+        Self::new(node, NitKind::Services)
     }
-    pub fn new_svc_restart(node: &str, svcs: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcRestart(svcs),
-        }
+    pub fn new_spoints(node: &NodeRef) -> Self {
+        Self::new(node, NitKind::SPoints)
     }
-    pub fn new_svc_destroy(node: &str, svcs: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcDestroy(svcs),
-        }
+    pub fn new_svc_restart(node: &NodeRef, svcs: Vec<String>) -> Self {
+        Self::new(node, NitKind::SvcRestart(svcs))
     }
-    pub fn new_svc_purge(node: &str, svcs: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcPurge(svcs),
-        }
+    pub fn new_svc_destroy(node: &NodeRef, svcs: Vec<String>) -> Self {
+        Self::new(node, NitKind::SvcDestroy(svcs))
     }
-    pub fn new_svc_get_params(node: &str, svc: String) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcGetParams(svc),
-        }
+    pub fn new_svc_purge(node: &NodeRef, svcs: Vec<String>) -> Self {
+        Self::new(node, NitKind::SvcPurge(svcs))
     }
-    pub fn new_svc_get_params_x(node: &str, svc: String) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcGetParamsX(svc),
-        }
+    pub fn new_svc_get_params(node: &NodeRef, svc: String) -> Self {
+        Self::new(node, NitKind::SvcGetParams(svc))
     }
-    pub fn new_svc_get_info(node: &str, svc: String) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcGetInfo(svc),
-        }
+    pub fn new_svc_get_params_x(node: &NodeRef, svc: String) -> Self {
+        Self::new(node, NitKind::SvcGetParamsX(svc))
+    }
+    pub fn new_svc_get_info(node: &NodeRef, svc: String) -> Self {
+        Self::new(node, NitKind::SvcGetInfo(svc))
     }
     pub fn new_svc_call(
         u: uuid::Uuid,
-        node: &str,
+        node: &NodeRef,
         svc: String,
         method: String,
         payload: Option<Value>,
     ) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcCall(u, svc, method, payload),
-        }
+        Self::new(node, NitKind::SvcCall(u, svc, method, payload))
     }
-    pub fn new_item_get_state(node: &str, oid: OID) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemGetState(oid),
-        }
+    pub fn new_item_get_state(node: &NodeRef, oid: OID) -> Self {
+        Self::new(node, NitKind::ItemGetState(oid))
     }
-    pub fn new_item_get_config_x(node: &str, oid: String) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemGetConfigX(oid),
-        }
+    pub fn new_item_get_config_x(node: &NodeRef, oid: String) -> Self {
+        Self::new(node, NitKind::ItemGetConfigX(oid))
     }
-    pub fn new_svc_deploy(node: &str, params: ServiceParams) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcDeploySingle(Box::new(params)),
-        }
+    pub fn new_svc_deploy(node: &NodeRef, params: ServiceParams) -> Self {
+        Self::new(node, NitKind::SvcDeploySingle(Box::new(params)))
     }
-    pub fn new_svc_deploy_multi(node: &str, svcs: Vec<Value>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::SvcDeployMultiple(svcs),
-        }
+    pub fn new_svc_deploy_multi(node: &NodeRef, svcs: Vec<Value>) -> Self {
+        Self::new(node, NitKind::SvcDeployMultiple(svcs))
     }
-    pub fn new_item_list(node: &str, oid: Option<String>, item_node: Option<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::Items(oid, item_node),
-        }
+    pub fn new_item_list(node: &NodeRef, oid: Option<String>, item_node: Option<String>) -> Self {
+        Self::new(node, NitKind::Items(oid, item_node))
     }
-    pub fn new_item_get_config(node: &str, oid: String) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemGetConfig(oid),
-        }
+    pub fn new_item_get_config(node: &NodeRef, oid: String) -> Self {
+        Self::new(node, NitKind::ItemGetConfig(oid))
     }
-    pub fn new_item_deploy(node: &str, config: ItemConfig) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemDeploySingle(Box::new(config)),
-        }
+    pub fn new_item_deploy(node: &NodeRef, config: ItemConfig) -> Self {
+        Self::new(node, NitKind::ItemDeploySingle(Box::new(config)))
     }
-    pub fn new_item_deploy_multi(node: &str, items: Vec<Value>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemDeployMultiple(items),
-        }
+    pub fn new_item_deploy_multi(node: &NodeRef, items: Vec<Value>) -> Self {
+        Self::new(node, NitKind::ItemDeployMultiple(items))
     }
-    pub fn new_item_announce(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemAnnounce(oids),
-        }
+    pub fn new_item_announce(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::ItemAnnounce(oids))
     }
-    pub fn new_item_destroy(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemDestroy(oids),
-        }
+    pub fn new_item_destroy(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::ItemDestroy(oids))
     }
-    pub fn new_item_disable(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemDisable(oids),
-        }
+    pub fn new_item_disable(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::ItemDisable(oids))
     }
-    pub fn new_item_enable(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::ItemEnable(oids),
-        }
+    pub fn new_item_enable(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::ItemEnable(oids))
     }
-    pub fn new_unit_action_toggle(node: &str, oid: String) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::UnitActionToggle(oid),
-        }
+    pub fn new_unit_action_toggle(node: &NodeRef, oid: String) -> Self {
+        Self::new(node, NitKind::UnitActionToggle(oid))
     }
-    pub fn new_unit_action(node: &str, p_action: PayloadAction) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::UnitAction(p_action),
-        }
+    pub fn new_unit_action(node: &NodeRef, p_action: PayloadAction) -> Self {
+        Self::new(node, NitKind::UnitAction(p_action))
     }
-    pub fn new_lmacro_run(node: &str, p_action: PayloadAction) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::LmacroRun(p_action),
-        }
+    pub fn new_lmacro_run(node: &NodeRef, p_action: PayloadAction) -> Self {
+        Self::new(node, NitKind::LmacroRun(p_action))
     }
-    pub fn new_lvar_set(node: &str, oids: Vec<String>, p_set: PayloadLvarSet) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::LvarSet(oids, p_set),
-        }
+    pub fn new_lvar_set(node: &NodeRef, oids: Vec<String>, p_set: PayloadLvarSet) -> Self {
+        Self::new(node, NitKind::LvarSet(oids, p_set))
     }
-    pub fn new_lvar_reset(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::LvarReset(oids),
-        }
+    pub fn new_lvar_reset(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::LvarReset(oids))
     }
-    pub fn new_lvar_clear(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::LvarClear(oids),
-        }
+    pub fn new_lvar_clear(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::LvarClear(oids))
     }
-    pub fn new_lvar_toggle(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::LvarToggle(oids),
-        }
+    pub fn new_lvar_toggle(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::LvarToggle(oids))
     }
-    pub fn new_lvar_incr(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::LvarIncr(oids),
-        }
+    pub fn new_lvar_incr(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::LvarIncr(oids))
     }
-    pub fn new_lvar_decr(node: &str, oids: Vec<String>) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::LvarDecr(oids),
-        }
+    pub fn new_lvar_decr(node: &NodeRef, oids: Vec<String>) -> Self {
+        Self::new(node, NitKind::LvarDecr(oids))
     }
-    pub fn start_item_watcher(u: uuid::Uuid, node: &str, oid: OID, int: Duration) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::StartItemWatcher(u, oid, int),
-        }
+    pub fn start_item_watcher(u: uuid::Uuid, node: &NodeRef, oid: OID, int: Duration) -> Self {
+        Self::new(node, NitKind::StartItemWatcher(u, oid, int))
     }
     pub fn start_action_watcher(
         u: uuid::Uuid,
-        node: &str,
+        node: &NodeRef,
         action_uuid: uuid::Uuid,
         int: Duration,
     ) -> Self {
-        Self {
-            node: node.to_owned(),
-            kind: NitKind::StartActionWatcher(u, action_uuid, int),
-        }
+        Self::new(node, NitKind::StartActionWatcher(u, action_uuid, int))
     }
-    pub fn stop_watcher(u: uuid::Uuid) -> Self {
-        Self {
-            node: String::new(),
-            kind: NitKind::StopWatcher(u),
-        }
+    pub fn stop_watcher(conn: ConnId, u: uuid::Uuid) -> Self {
+        Self::new(&NodeRef::new(conn, ""), NitKind::StopWatcher(u))
     }
-    pub fn node(&self) -> &str {
+    pub fn node(&self) -> &NodeRef {
         &self.node
     }
+    pub fn conn(&self) -> ConnId {
+        self.node.conn
+    }
     pub fn kind(&self) -> &NitKind {
         &self.kind
     }
+    /// Bulk operations are not stopped at the first failed resource and return a per-resource
+    /// report
+    pub fn with_continue_on_error(mut self) -> Self {
+        self.continue_on_error = true;
+        self
+    }
+    pub fn continue_on_error(&self) -> bool {
+        self.continue_on_error
+    }
+    /// Creates the same bulk operation for the specified resources only
+    pub fn retry(&self, resources: Vec<String>) -> Option<Self> {
+        let kind = match self.kind {
+            NitKind::ItemAnnounce(_) => NitKind::ItemAnnounce(resources),
+            NitKind::ItemDisable(_) => NitKind::ItemDisable(resources),
+            NitKind::ItemEnable(_) => NitKind::ItemEnable(resources),
+            NitKind::LvarSet(_, ref p_set) => NitKind::LvarSet(resources, p_set.clone()),
+            NitKind::LvarReset(_) => NitKind::LvarReset(resources),
+            NitKind::LvarClear(_) => NitKind::LvarClear(resources),
+            NitKind::LvarToggle(_) => NitKind::LvarToggle(resources),
+            NitKind::LvarIncr(_) => NitKind::LvarIncr(resources),
+            NitKind::LvarDecr(_) => NitKind::LvarDecr(resources),
+            NitKind::SvcRestart(_) => NitKind::SvcRestart(resources),
+            _ => return None,
+        };
+        Some(Self {
+            node: self.node.clone(),
+            kind,
+            continue_on_error: self.continue_on_error,
+        })
+    }
     #[allow(dead_code)]
     pub fn set_kind(&mut self, kind: NitKind) {
         self.kind = kind;
@@ -946,6 +1196,184 @@ pub enum NitKind {
     StopWatcher(uuid::Uuid),
 }
 
+fn value_ids(values: &[Value], key: &str) -> Vec<String> {
+    let key = Value::String(key.to_owned());
+    values
+        .iter()
+        .filter_map(|v| {
+            if let Value::Map(m) = v {
+                m.get(&key).map(ToString::to_string)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+impl NitKind {
+    /// Operations which alter the node state. These are executed one-by-one for each node in the
+    /// order they have been called and are not allowed in read-only mode. The match is exhaustive
+    /// so a new operation can not be added without being classified
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            NitKind::ItemDeploySingle(_)
+            | NitKind::ItemDeployMultiple(_)
+            | NitKind::ItemAnnounce(_)
+            | NitKind::ItemDestroy(_)
+            | NitKind::ItemDisable(_)
+            | NitKind::ItemEnable(_)
+            | NitKind::LvarSet(_, _)
+            | NitKind::LvarReset(_)
+            | NitKind::LvarClear(_)
+            | NitKind::LvarToggle(_)
+            | NitKind::LvarIncr(_)
+            | NitKind::LvarDecr(_)
+            | NitKind::UnitActionToggle(_)
+            | NitKind::UnitAction(_)
+            | NitKind::LmacroRun(_)
+            | NitKind::Save
+            | NitKind::Restart
+            | NitKind::SvcRestart(_)
+            | NitKind::SvcDestroy(_)
+            | NitKind::SvcPurge(_)
+            | NitKind::SvcDeploySingle(_)
+            | NitKind::SvcDeployMultiple(_)
+            | NitKind::SvcCall(_, _, _, _) => true,
+            NitKind::State
+            | NitKind::Services
+            | NitKind::Items(_, _)
+            | NitKind::Log(_)
+            | NitKind::Actions(_)
+            | NitKind::ItemGetConfig(_)
+            | NitKind::ItemGetState(_)
+            | NitKind::Broker
+            | NitKind::SvcGetParams(_)
+            | NitKind::SvcGetParamsX(_)
+            | NitKind::ItemGetConfigX(_)
+            | NitKind::SvcGetInfo(_)
+            | NitKind::SPoints
+            | NitKind::StartItemWatcher(_, _, _)
+            | NitKind::StartActionWatcher(_, _, _)
+            | NitKind::StopWatcher(_) => false,
+        }
+    }
+    /// Operations which are called for each resource one-by-one and may continue on errors
+    pub fn is_bulk(&self) -> bool {
+        matches!(
+            self,
+            NitKind::ItemAnnounce(_)
+                | NitKind::ItemDisable(_)
+                | NitKind::ItemEnable(_)
+                | NitKind::LvarSet(_, _)
+                | NitKind::LvarReset(_)
+                | NitKind::LvarClear(_)
+                | NitKind::LvarToggle(_)
+                | NitKind::LvarIncr(_)
+                | NitKind::LvarDecr(_)
+                | NitKind::SvcRestart(_)
+        )
+    }
+    /// Operations processed by the client itself, without calling the node
+    pub fn is_local(&self) -> bool {
+        matches!(
+            self,
+            NitKind::StartItemWatcher(_, _, _)
+                | NitKind::StartActionWatcher(_, _, _)
+                | NitKind::StopWatcher(_)
+        )
+    }
+    /// Resources (OIDs or service IDs) the operation is applied to
+    pub fn targets(&self) -> Vec<String> {
+        match self {
+            NitKind::ItemAnnounce(v)
+            | NitKind::ItemDestroy(v)
+            | NitKind::ItemDisable(v)
+            | NitKind::ItemEnable(v)
+            | NitKind::LvarSet(v, _)
+            | NitKind::LvarReset(v)
+            | NitKind::LvarClear(v)
+            | NitKind::LvarToggle(v)
+            | NitKind::LvarIncr(v)
+            | NitKind::LvarDecr(v)
+            | NitKind::SvcRestart(v)
+            | NitKind::SvcDestroy(v)
+            | NitKind::SvcPurge(v) => v.clone(),
+            NitKind::UnitActionToggle(oid) => vec![oid.clone()],
+            NitKind::UnitAction(p) | NitKind::LmacroRun(p) => p.i.iter().cloned().collect(),
+            NitKind::ItemDeploySingle(config) => vec![config.oid.to_string()],
+            NitKind::ItemDeployMultiple(items) => value_ids(items, "oid"),
+            NitKind::SvcDeploySingle(params) => params.id.iter().cloned().collect(),
+            NitKind::SvcDeployMultiple(svcs) => value_ids(svcs, "id"),
+            NitKind::SvcCall(_, svc, _, _) => vec![svc.clone()],
+            _ => Vec::new(),
+        }
+    }
+    /// A short description of the operation parameters
+    pub fn payload_summary(&self) -> Option<String> {
+        match self {
+            NitKind::LvarSet(_, p_set) => {
+                let mut s = Vec::new();
+                if let Some(status) = p_set.status {
+                    s.push(format!("status={}", status));
+                }
+                if let Some(ref value) = p_set.value {
+                    s.push(format!("value={}", value));
+                }
+                Some(s.join(", "))
+            }
+            NitKind::UnitAction(p) | NitKind::LmacroRun(p) => serde_json::to_string(&p.params).ok(),
+            NitKind::SvcCall(_, _, method, payload) => Some(if let Some(p) = payload {
+                format!("{} {}", method, summarize_payload(p))
+            } else {
+                method.clone()
+            }),
+            _ => None,
+        }
+    }
+}
+
+const PAYLOAD_SUMMARY_MAX_LEN: usize = 200;
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    ["password", "key", "token", "secret"]
+        .iter()
+        .any(|s| key.contains(s))
+}
+
+/// Top-level payload fields with scalar values, secret-looking values are redacted, nested values
+/// are not shown. The summary is truncated to PAYLOAD_SUMMARY_MAX_LEN chars
+fn summarize_payload(payload: &Value) -> String {
+    let summary = match payload {
+        Value::Map(m) => m
+            .iter()
+            .map(|(k, v)| {
+                let key = k.to_string();
+                let value = if is_secret_key(&key) {
+                    "***".to_owned()
+                } else {
+                    match v {
+                        Value::Map(_) => "{..}".to_owned(),
+                        Value::Seq(_) => "[..]".to_owned(),
+                        v => v.to_string(),
+                    }
+                };
+                format!("{}={}", key, value)
+            })
+            .collect::<Vec<String>>()
+            .join(", "),
+        Value::Seq(_) => "[..]".to_owned(),
+        v => v.to_string(),
+    };
+    if summary.chars().count() > PAYLOAD_SUMMARY_MAX_LEN {
+        let mut s: String = summary.chars().take(PAYLOAD_SUMMARY_MAX_LEN).collect();
+        s.push_str("...");
+        s
+    } else {
+        summary
+    }
+}
+
 impl fmt::Display for NitKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -967,50 +1395,36 @@ impl fmt::Display for NitKind {
                 NitKind::LvarToggle(_) => "lvar.toggle",
                 NitKind::LvarIncr(_) => "lvar.incr",
                 NitKind::LvarDecr(_) => "lvar.decr",
+                NitKind::ItemDeploySingle(_) | NitKind::ItemDeployMultiple(_) => "item.deploy",
+                NitKind::UnitActionToggle(_) => "unit.action_toggle",
+                NitKind::UnitAction(_) => "unit.action",
+                NitKind::LmacroRun(_) => "lmacro.run",
+                NitKind::SvcDeploySingle(_) | NitKind::SvcDeployMultiple(_) => "svc.deploy",
+                NitKind::SvcCall(_, _, _, _) => "svc.call",
                 _ => "",
             }
         )
     }
 }
 
-pub fn nd_from_path(path: &[&str]) -> Option<NitData> {
+pub fn nd_from_path(conn: ConnId, path: &[&str]) -> Option<NitData> {
     if let Some(node_name) = path.first() {
+        let node = NodeRef::new(conn, node_name);
         match path.get(1) {
             #[allow(clippy::match_single_binding)]
             Some(node_leaf) => match *node_leaf {
-                "broker" => Some(NitData {
-                    node: (*node_name).to_owned(),
-                    kind: NitKind::Broker,
-                }),
-                "services" => Some(NitData {
-                    node: (*node_name).to_owned(),
-                    kind: NitKind::Services,
-                }),
-                "spoints" => Some(NitData {
-                    node: (*node_name).to_owned(),
-                    kind: NitKind::SPoints,
-                }),
-                "items" => Some(NitData {
-                    node: (*node_name).to_owned(),
-                    kind: NitKind::Items(None, None),
-                }),
-                "log" => Some(NitData {
-                    node: (*node_name).to_owned(),
-                    kind: NitKind::Log(None),
-                }),
-                "actions" => Some(NitData {
-                    node: (*node_name).to_owned(),
-                    kind: NitKind::Actions(None),
-                }),
+                "broker" => Some(NitData::new(&node, NitKind::Broker)),
+                "services" => Some(NitData::new(&node, NitKind::Services)),
+                "spoints" => Some(NitData::new(&node, NitKind::SPoints)),
+                "items" => Some(NitData::new(&node, NitKind::Items(None, None))),
+                "log" => Some(NitData::new(&node, NitKind::Log(None))),
+                "actions" => Some(NitData::new(&node, NitKind::Actions(None))),
                 _ => {
                     dbg!(node_name, node_leaf);
                     None
                 }
             },
-            None => Some(NitData {
-                node: (*node_name).to_owned(),
-                kind: NitKind::State,
-            }),
+            None => Some(NitData::new(&node, NitKind::State)),
         }
     } else {
         None
@@ -1074,3 +1488,27 @@ pub unsafe fn copy_from_table(tables: &[&QPtr<QTableWidget>]) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_payload_summary() {
+        let payload: Value =
+            serde_yaml::from_str("host: plc1\nport: 502\napi_key: abc\nauth:\n  password: xyz")
+                .unwrap();
+        let summary = summarize_payload(&payload);
+        assert!(summary.contains("host=plc1"));
+        assert!(summary.contains("port=502"));
+        assert!(summary.contains("api_key=***"));
+        assert!(summary.contains("auth={..}"));
+        assert!(!summary.contains("abc"));
+        assert!(!summary.contains("xyz"));
+        let payload = Value::String("x".repeat(1000));
+        assert_eq!(
+            summarize_payload(&payload).len(),
+            PAYLOAD_SUMMARY_MAX_LEN + 3
+        );
+    }
+}
@@ -1,33 +1,44 @@
+use crate::audit::{self, AuditRecord};
 use crate::bus;
 use crate::common::{
-    self, copy_from_table, new_size, splitter_sizes, ActionRecordFull, ConnectionOptions,
-    ItemActionConfig, ItemConfig, ItemInfo, ItemLogicConfig, ItemState, NitData, PayloadAction,
-    PayloadLvarSet, SPointInfo, ServiceParams, SvcData, SvcInfo, SvcMethodInfoParam,
+    self, copy_from_table, new_size, splitter_sizes, ActionRecordFull, BulkResult, ConnId,
+    ConnectionOptions, ConnectionProfile, FileFormat, ItemActionConfig, ItemConfig, ItemInfo,
+    ItemLogicConfig, ItemState, Nit, NitData, NodeRef, PayloadAction, PayloadLvarSet, SPointInfo,
+    ServiceParams, SvcData, SvcInfo, SvcMethodInfoParam,
 };
+use crate::generator::generate_items;
 use crate::output;
+use crate::rename::Rename;
+use crate::resources::{
+    format_diff, uniform_field, CompareEntry, CompareStatus, ImportEntry, ImportStatus,
+    ItemFieldsUpdate, NodeDeploy, ResourceKind,
+};
 use crate::smart_table::{FormattedValue, FormattedValueColor, Table};
+use crate::templates;
 use crate::ui;
+use crate::vars;
 use crate::CONTROLLER_SVC_PFX;
 use arboard::Clipboard;
 use busrt::{DEFAULT_BUF_SIZE, DEFAULT_BUF_TTL, DEFAULT_QUEUE_SIZE};
 use chrono::{DateTime, Local, SecondsFormat};
-use cpp_core::{Ptr, StaticUpcast};
+use cpp_core::{CppBox, Ptr, StaticUpcast};
 use eva_common::prelude::*;
 use qt_charts::{QChart, QChartView, QLineSeries};
 use qt_core::{
-    qs, slot, CheckState, QBox, QObject, QPtr, QVariant, SlotNoArgs, SlotOfBool, SlotOfDouble,
-    SlotOfQString,
+    qs, slot, CheckState, ItemFlag, QBox, QObject, QPtr, QVariant, SlotNoArgs, SlotOfBool,
+    SlotOfDouble, SlotOfInt, SlotOfQString,
 };
 use qt_gui::q_key_sequence::StandardKey;
 use qt_gui::q_painter::RenderHint;
-use qt_gui::{QColor, QKeySequence, QPixmap};
+use qt_gui::q_text_cursor::{MoveMode, SelectionType};
+use qt_gui::{QBrush, QColor, QKeySequence, QPixmap, QTextCharFormat, QTextCursor};
 use qt_ui_tools::ui_form;
 use qt_widgets::{
     QAction, QCheckBox, QComboBox, QDialogButtonBox, QDoubleSpinBox, QFileDialog, QFormLayout,
-    QGridLayout, QLabel, QLineEdit, QPlainTextEdit, QPushButton, QRadioButton, QSpinBox, QSplitter,
-    QTabWidget, QTableWidget, QToolButton, QTreeWidget, QWidget,
+    QGridLayout, QLabel, QLineEdit, QMenu, QMessageBox, QPlainTextEdit, QPushButton, QRadioButton,
+    QSpinBox, QSplitter, QStatusBar, QTabWidget, QTableWidget, QToolButton, QTreeWidget, QWidget,
 };
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap};
 use std::os::raw::c_int;
@@ -37,9 +48,15 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::time::Instant;
 
-const OUT_FILE: &str = "Select output file";
+pub const OUT_FILE: &str = "Select output file";
 pub const IN_FILE: &str = "Select input file";
 pub const YAML_FILTER: &str = "*.yml";
+pub const JSON_FILTER: &str = "*.json";
+pub const TOML_FILTER: &str = "*.toml";
+pub const CSV_FILTER: &str = "*.csv";
+pub const ENV_FILTER: &str = "*.env;;*";
+pub const RESOURCE_FILTER: &str = "*.yml *.yaml *.json *.toml";
+const JSONL_FILTER: &str = "*.jsonl";
 
 thread_local! {
     static LAST_DIR: RefCell<Option<String>> = RefCell::new(None);
@@ -137,6 +154,8 @@ pub struct Main {
     pub(crate) btn_auto_reload_start_stop: QPtr<QPushButton>,
     status: QPtr<QLabel>,
     nit_status: QPtr<QLabel>,
+    status_bar: QPtr<QStatusBar>,
+    pub(crate) menu_recent: QPtr<QMenu>,
     pub(crate) splitter_workspace: QPtr<QSplitter>,
     pub(crate) splitter_tables: QPtr<QSplitter>,
     pub(crate) main_tree: QPtr<QTreeWidget>,
@@ -145,12 +164,18 @@ pub struct Main {
     pub(crate) action_connect: QPtr<QAction>,
     pub(crate) action_copy: QPtr<QAction>,
     pub(crate) action_select_all: QPtr<QAction>,
+    pub(crate) action_continue_on_error: QPtr<QAction>,
+    pub(crate) action_read_only: QPtr<QAction>,
     pub(crate) action_disconnect: QPtr<QAction>,
     pub(crate) action_exit: QPtr<QAction>,
     pub(crate) action_about: QPtr<QAction>,
     pub(crate) action_reload: QPtr<QAction>,
+    pub(crate) action_audit: QPtr<QAction>,
     pub(crate) auto_reload: QPtr<QDoubleSpinBox>,
     pub(crate) action_add_resource: QPtr<QAction>,
+    pub(crate) action_add_svc_template: QPtr<QAction>,
+    pub(crate) action_generate_items: QPtr<QAction>,
+    pub(crate) action_undo: QPtr<QAction>,
     pub(crate) action_edit_resource: QPtr<QAction>,
     pub(crate) action_delete_resource: QPtr<QAction>,
     pub(crate) action_import_resource: QPtr<QAction>,
@@ -206,6 +231,17 @@ impl Main {
     pub unsafe fn set_nit_status(&self, status: &str) {
         self.nit_status.set_text(&qs(status));
     }
+    /// Marks the status bar with the colour tag of the active connection profile
+    pub unsafe fn set_color_tag(&self, color: Option<&str>) {
+        if let Some(color) = color {
+            self.status_bar.set_style_sheet(&qs(format!(
+                "QStatusBar {{ border-top: 3px solid {}; }}",
+                color
+            )));
+        } else {
+            self.status_bar.set_style_sheet(&qs(""));
+        }
+    }
 }
 
 #[ui_form("../ui/d_connect.ui")]
@@ -216,6 +252,12 @@ pub struct DialogConnect {
     login: QPtr<QLineEdit>,
     password: QPtr<QLineEdit>,
     timeout: QPtr<QSpinBox>,
+    pub(crate) profile: QPtr<QComboBox>,
+    pub(crate) btn_profile_save: QPtr<QPushButton>,
+    pub(crate) btn_profile_delete: QPtr<QPushButton>,
+    auto_reload: QPtr<QDoubleSpinBox>,
+    color: QPtr<QComboBox>,
+    read_only: QPtr<QCheckBox>,
     pub(crate) button_box: QPtr<QDialogButtonBox>,
 }
 
@@ -227,7 +269,53 @@ impl DialogConnect {
 }
 
 impl DialogConnect {
-    //pub unsafe fn init(&self) {}
+    pub unsafe fn init(&self) {
+        self.color.add_item_q_string(&qs(""));
+        for color in common::PROFILE_COLORS {
+            self.color.add_item_q_string(&qs(color));
+        }
+    }
+    pub unsafe fn set_profiles(&self, profiles: &[ConnectionProfile]) {
+        let current = self.profile.gs();
+        self.profile.clear();
+        for profile in profiles {
+            self.profile.add_item_q_string(&qs(&profile.name));
+        }
+        self.profile.set_current_text(&qs(current));
+    }
+    pub unsafe fn profile_name(&self) -> String {
+        self.profile.gs().trim().to_owned()
+    }
+    pub unsafe fn set_profile(&self, profile: &ConnectionProfile) {
+        self.profile.set_current_text(&qs(&profile.name));
+        self.set_data(profile.connection_options());
+        self.password.clear();
+        self.auto_reload.set_value(profile.auto_reload);
+        self.color
+            .set_current_text(&qs(profile.color.as_deref().unwrap_or_default()));
+        self.read_only.set_checked(profile.read_only);
+    }
+    /// Returns None if no profile name is specified
+    pub unsafe fn generate_profile(&self) -> Option<ConnectionProfile> {
+        let name = self.profile_name();
+        if name.is_empty() {
+            None
+        } else {
+            Some(ConnectionProfile::new(
+                &name,
+                &self.generate_options(),
+                self.auto_reload.value(),
+                self.color.gso(),
+                self.read_only.is_checked(),
+            ))
+        }
+    }
+    pub unsafe fn set_auto_reload(&self, value: f64) {
+        self.auto_reload.set_value(value);
+    }
+    pub unsafe fn read_only(&self) -> bool {
+        self.read_only.is_checked()
+    }
     pub unsafe fn handle_proto(&self, proto: &str) {
         if proto == "rt://" {
             self.login.set_disabled(true);
@@ -317,16 +405,18 @@ pub struct Busy {
     status: QPtr<QLabel>,
     gears: QPtr<QLabel>,
     btn_close: QPtr<QPushButton>,
+    btn_cancel: QPtr<QPushButton>,
 }
 
 impl Busy {
-    pub unsafe fn init(self: &Rc<Self>) {
+    pub unsafe fn init(self: &Rc<Self>, on_cancel: &QBox<SlotNoArgs>) {
         let this: Rc<Self> = self.clone();
         self.btn_close
             .clicked()
             .connect(&SlotNoArgs::new(&self.widget, move || {
                 this.widget.close();
             }));
+        self.btn_cancel.clicked().connect(on_cancel);
     }
     pub unsafe fn show(&self) {
         let pixmap = QPixmap::new();
@@ -335,14 +425,19 @@ impl Busy {
         self.status.set_text(&qs("Working..."));
         self.widget.set_window_title(&qs("Operation in progress"));
         self.btn_close.hide();
+        self.btn_cancel.show();
         self.widget.show();
     }
+    pub unsafe fn close(&self) {
+        self.widget.close();
+    }
     pub unsafe fn mark_completed(&self, memo: &str) {
         let pixmap = QPixmap::new();
         pixmap.load_1a(&qs(":/i/icons/completed.png"));
         self.gears.set_pixmap(&pixmap);
         self.status.set_text(&qs(memo));
         self.widget.set_window_title(&qs("Completed"));
+        self.btn_cancel.hide();
         self.btn_close.show();
     }
     pub unsafe fn mark_failed(&self, memo: &str) {
@@ -351,6 +446,8 @@ impl Busy {
         self.gears.set_pixmap(&pixmap);
         self.status.set_text(&qs(memo));
         self.widget.set_window_title(&qs("Failed"));
+        self.btn_cancel.hide();
+        self.btn_close.show();
     }
 }
 
@@ -368,6 +465,7 @@ pub trait NonModalDialog {
 
 struct InfoDialogInstance<T: NonModalInfoDialog + 'static> {
     dialog: Rc<T>,
+    conn: ConnId,
     _slot_close: QBox<SlotNoArgs>,
 }
 
@@ -379,7 +477,7 @@ impl<T> InfoDialogFactory<T>
 where
     T: NonModalInfoDialog + 'static,
 {
-    pub unsafe fn register(&self, dialog: Rc<T>) -> uuid::Uuid {
+    pub unsafe fn register(&self, dialog: Rc<T>, conn: ConnId) -> uuid::Uuid {
         let uuid = uuid::Uuid::new_v4();
         let reg = self.object_registry.clone();
         let dialog_c = dialog.clone();
@@ -392,6 +490,7 @@ where
             uuid,
             InfoDialogInstance {
                 dialog,
+                conn,
                 _slot_close: slot_close,
             },
         );
@@ -418,6 +517,17 @@ where
         }
         objs.clear();
     }
+    /// Closes dialogs of the particular connection
+    pub unsafe fn close_conn(&self, conn: ConnId) {
+        self.object_registry.lock().unwrap().retain(|_, obj| {
+            if obj.conn == conn {
+                obj.dialog.close();
+                false
+            } else {
+                true
+            }
+        });
+    }
     pub unsafe fn cleanup(&self) {
         self.object_registry
             .lock()
@@ -446,7 +556,7 @@ pub enum DialogKind {
 
 struct DialogInstance<T: NonModalDialog + 'static> {
     dialog: Rc<T>,
-    node: String,
+    node: NodeRef,
 }
 
 pub struct DialogFactory<T: NonModalDialog + 'static> {
@@ -468,9 +578,9 @@ impl<T> DialogFactory<T>
 where
     T: NonModalDialog + 'static,
 {
-    pub unsafe fn register<F>(&self, dialog: Rc<T>, node: &str, mut process: F)
+    pub unsafe fn register<F>(&self, dialog: Rc<T>, node: &NodeRef, mut process: F)
     where
-        F: FnMut(Rc<T>, &str) -> bool + 'static,
+        F: FnMut(Rc<T>, &NodeRef) -> bool + 'static,
     {
         let uuid = uuid::Uuid::new_v4();
         let reg = self.object_registry.clone();
@@ -493,7 +603,7 @@ where
             uuid,
             DialogInstance {
                 dialog,
-                node: node.to_owned(),
+                node: node.clone(),
             },
         );
     }
@@ -504,6 +614,17 @@ where
         }
         objs.clear();
     }
+    /// Closes dialogs of the particular connection
+    pub unsafe fn close_conn(&self, conn: ConnId) {
+        self.object_registry.lock().unwrap().retain(|_, obj| {
+            if obj.node.conn() == conn {
+                obj.dialog.widget().close();
+                false
+            } else {
+                true
+            }
+        });
+    }
     pub unsafe fn cleanup(&self) {
         self.object_registry
             .lock()
@@ -514,40 +635,57 @@ where
 
 pub struct ExportConfig {
     pub file: Option<String>,
+    pub format: FileFormat,
     pub kind: ExportKind,
     pub cloud_node: Option<String>,
     pub merge: bool,
+    pub secrets: Option<String>,
 }
 
 #[derive(Eq, PartialEq)]
 pub enum ExportKind {
     Resource,
     CloudDeploy,
+    Csv,
 }
 
 #[ui_form("../ui/d_export.ui")]
 pub struct DialogExport {
     pub(crate) widget: QBox<QWidget>,
     i_file: QPtr<QLineEdit>,
+    i_format: QPtr<QComboBox>,
     rb_cloud: QPtr<QRadioButton>,
     rb_res: QPtr<QRadioButton>,
+    rb_csv: QPtr<QRadioButton>,
     i_node: QPtr<QLineEdit>,
     cb_merge: QPtr<QCheckBox>,
+    i_secrets: QPtr<QLineEdit>,
     btn_select_file: QPtr<QToolButton>,
     btnbox: QPtr<QDialogButtonBox>,
 }
 
 impl DialogExport {
+    unsafe fn format(&self) -> FileFormat {
+        match self.i_format.current_index() {
+            1 => FileFormat::Json,
+            2 => FileFormat::Toml,
+            _ => FileFormat::Yaml,
+        }
+    }
     pub unsafe fn export_config(self: &Rc<Self>) -> ExportConfig {
         ExportConfig {
             file: self.i_file.gso(),
+            format: self.format(),
             kind: if self.rb_res.is_checked() {
                 ExportKind::Resource
-            } else {
+            } else if self.rb_cloud.is_checked() {
                 ExportKind::CloudDeploy
+            } else {
+                ExportKind::Csv
             },
             cloud_node: self.i_node.gso(),
             merge: self.cb_merge.is_checked(),
+            secrets: self.i_secrets.gso(),
         }
     }
     pub unsafe fn init(self: &Rc<Self>, on_submit: &QBox<SlotNoArgs>) {
@@ -556,12 +694,37 @@ impl DialogExport {
             .clicked()
             .connect(&SlotOfBool::new(&self.widget, move |checked| {
                 this.i_node.set_enabled(!checked);
+                this.cb_merge.set_enabled(true);
+                this.i_format.set_enabled(true);
             }));
         let this: Rc<Self> = self.clone();
         self.rb_cloud
             .clicked()
             .connect(&SlotOfBool::new(&self.widget, move |checked| {
                 this.i_node.set_enabled(checked);
+                this.cb_merge.set_enabled(true);
+                this.i_format.set_enabled(true);
+            }));
+        let this: Rc<Self> = self.clone();
+        self.rb_csv
+            .clicked()
+            .connect(&SlotOfBool::new(&self.widget, move |checked| {
+                this.i_node.set_enabled(false);
+                // CSV files are always overwritten
+                this.cb_merge.set_enabled(!checked);
+                this.i_format.set_enabled(!checked);
+            }));
+        let this: Rc<Self> = self.clone();
+        self.i_format
+            .current_index_changed()
+            .connect(&SlotOfInt::new(&self.widget, move |_| {
+                // keep the file extension in sync if it is a known one
+                if let Some(fname) = this.i_file.gso() {
+                    if FileFormat::from_extension(&fname).is_some() {
+                        let path = Path::new(&fname).with_extension(this.format().extension());
+                        this.i_file.set_text(&qs(path.to_string_lossy()));
+                    }
+                }
             }));
         let this: Rc<Self> = self.clone();
         self.btn_select_file
@@ -579,7 +742,15 @@ impl DialogExport {
                         &this.widget,
                         &qs(OUT_FILE),
                         &qs(get_last_dir()),
-                        &qs(YAML_FILTER),
+                        &qs(if this.rb_csv.is_checked() {
+                            CSV_FILTER
+                        } else {
+                            match this.format() {
+                                FileFormat::Yaml => YAML_FILTER,
+                                FileFormat::Json => JSON_FILTER,
+                                FileFormat::Toml => TOML_FILTER,
+                            }
+                        }),
                     )
                 //}
                 .to_std_string();
@@ -593,13 +764,164 @@ impl DialogExport {
     pub unsafe fn show0(&self) {
         self.widget.show();
     }
-    pub unsafe fn show(&self, node: &str, res: &str) {
+    pub unsafe fn show(&self, node: &NodeRef, res: &str) {
         self.widget.set_window_title(&qs(format!("Export {res}")));
-        self.i_node.set_text(&qs(node));
+        self.i_node.set_text(&qs(node.name()));
         self.widget.show();
     }
 }
 
+/// YAML editor parse error, the line and column are 1-based
+struct YamlError {
+    line: Option<usize>,
+    message: String,
+}
+
+impl From<YamlError> for Error {
+    fn from(e: YamlError) -> Self {
+        Error::invalid_data(e.message)
+    }
+}
+
+fn parse_yaml<T: DeserializeOwned>(text: &str) -> Result<T, YamlError> {
+    serde_yaml::from_str(text).map_err(|e| {
+        let message = e.to_string();
+        if let Some(loc) = e.location() {
+            let suffix = format!(" at line {} column {}", loc.line(), loc.column());
+            YamlError {
+                line: Some(loc.line()),
+                message: format!(
+                    "line {}, column {}: {}",
+                    loc.line(),
+                    loc.column(),
+                    message.strip_suffix(&suffix).unwrap_or(&message)
+                ),
+            }
+        } else {
+            YamlError {
+                line: None,
+                message,
+            }
+        }
+    })
+}
+
+#[derive(Copy, Clone)]
+enum YamlSpan {
+    Key,
+    Str,
+    Comment,
+}
+
+/// Splits a YAML line into highlighted spans, positions are in UTF-16 units as Qt uses them
+fn yaml_spans(line: &str) -> Vec<(c_int, c_int, YamlSpan)> {
+    let u16pos = |b: usize| c_int::try_from(line[..b].encode_utf16().count()).unwrap_or_default();
+    let mut spans = Vec::new();
+    let mut push = |start: usize, end: usize, kind: YamlSpan| {
+        spans.push((u16pos(start), u16pos(end) - u16pos(start), kind));
+    };
+    let mut key_start = line.len() - line.trim_start().len();
+    while line[key_start..].starts_with("- ") {
+        key_start += 2;
+        key_start += line[key_start..].len() - line[key_start..].trim_start().len();
+    }
+    let rest = &line[key_start..];
+    let key_end = rest
+        .find(": ")
+        .or_else(|| rest.strip_suffix(':').map(str::len))
+        .filter(|&pos| {
+            !rest[..pos].contains('#') && !rest.starts_with(|c: char| "\"'{[".contains(c))
+        });
+    let value_start = if let Some(pos) = key_end {
+        push(key_start, key_start + pos, YamlSpan::Key);
+        key_start + pos + 1
+    } else {
+        key_start
+    };
+    let mut quote: Option<(char, usize)> = None;
+    let mut prev = if value_start > 0 {
+        line[..value_start].chars().last()
+    } else {
+        None
+    };
+    for (i, c) in line[value_start..].char_indices() {
+        let i = i + value_start;
+        match quote {
+            Some((q, start)) if c == q => {
+                push(start, i + c.len_utf8(), YamlSpan::Str);
+                quote = None;
+            }
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some((c, i)),
+            None if c == '#' && prev.map_or(true, char::is_whitespace) => {
+                push(i, line.len(), YamlSpan::Comment);
+                return spans;
+            }
+            None => {}
+        }
+        prev = Some(c);
+    }
+    if let Some((_, start)) = quote {
+        push(start, line.len(), YamlSpan::Str);
+    }
+    spans
+}
+
+unsafe fn yaml_span_format(kind: YamlSpan) -> CppBox<QTextCharFormat> {
+    let fmt = QTextCharFormat::new();
+    let (r, g, b) = match kind {
+        YamlSpan::Key => {
+            fmt.set_font_weight(75);
+            (0, 0, 160)
+        }
+        YamlSpan::Str => (0, 128, 0),
+        YamlSpan::Comment => {
+            fmt.set_font_italic(true);
+            (128, 128, 128)
+        }
+    };
+    fmt.set_foreground(&QBrush::from_q_color(&QColor::from_rgb_3a(r, g, b)));
+    fmt
+}
+
+/// Colors YAML keys, strings and comments and marks the error line. The formats are merged into
+/// the previous edit block, so the undo history of the editor is not affected
+unsafe fn highlight_yaml(editor: &QPlainTextEdit, error_line: Option<usize>) {
+    let doc = editor.document();
+    let blocked = editor.block_signals(true);
+    let cursor = QTextCursor::from_q_text_document(&doc);
+    cursor.join_previous_edit_block();
+    cursor.select(SelectionType::Document);
+    cursor.set_char_format(&QTextCharFormat::new());
+    let mut block = doc.first_block();
+    let mut line = 1;
+    while block.is_valid() {
+        let pos = block.position();
+        for (start, len, kind) in yaml_spans(&block.text().to_std_string()) {
+            cursor.set_position_1a(pos + start);
+            cursor.set_position_2a(pos + start + len, MoveMode::KeepAnchor);
+            cursor.set_char_format(&yaml_span_format(kind));
+        }
+        if error_line == Some(line) {
+            let fmt = QTextCharFormat::new();
+            fmt.set_background(&QBrush::from_q_color(&QColor::from_rgb_3a(255, 200, 200)));
+            cursor.set_position_1a(pos);
+            cursor.set_position_2a(pos + block.length() - 1, MoveMode::KeepAnchor);
+            cursor.merge_char_format(&fmt);
+        }
+        block = block.next();
+        line += 1;
+    }
+    cursor.end_edit_block();
+    editor.block_signals(blocked);
+}
+
+unsafe fn set_yaml_status(label: &QLabel, error: Option<&str>) {
+    label.set_text(&qs(error.map_or_else(String::new, |e| {
+        format!("<span style=\"color: red; font-weight: bold\">{e}</span>")
+    })));
+}
+
 #[ui_form("../ui/svc_edit.ui")]
 pub struct DialogSvcEdit {
     pub(crate) widget: QBox<QWidget>,
@@ -625,6 +947,10 @@ pub struct DialogSvcEdit {
     i_bus_queue_size: QPtr<QSpinBox>,
     i_bus_ping_interval: QPtr<QDoubleSpinBox>,
     label_load_tpl: QPtr<QLabel>,
+    tabs: QPtr<QTabWidget>,
+    tab_yaml: QPtr<QWidget>,
+    i_yaml: QPtr<QPlainTextEdit>,
+    label_yaml_status: QPtr<QLabel>,
     pub(crate) btn_load_tpl: QPtr<QToolButton>,
     pub(crate) btnbox: QPtr<QDialogButtonBox>,
 }
@@ -638,7 +964,7 @@ impl NonModalDialog for DialogSvcEdit {
     }
 }
 
-pub unsafe fn on_svc_btn_load_clicked(dialog: &DialogSvcEdit, node: &str, ui_obj: &Rc<ui::Ui>) {
+pub unsafe fn on_svc_btn_load_clicked(dialog: &DialogSvcEdit, node: &NodeRef, ui_obj: &Rc<ui::Ui>) {
     let path = Path::new("/opt/eva4/share/svc-tpl");
     let mut need_set = false;
     let dir = if path.exists() {
@@ -673,9 +999,62 @@ pub unsafe fn on_svc_btn_load_clicked(dialog: &DialogSvcEdit, node: &str, ui_obj
 }
 
 impl DialogSvcEdit {
+    pub unsafe fn init(self: &Rc<Self>) {
+        let this = self.clone();
+        self.tabs
+            .current_changed()
+            .connect(&SlotOfInt::new(&self.widget, move |_| {
+                this.handle_tab_changed();
+            }));
+        let this = self.clone();
+        self.i_yaml
+            .text_changed()
+            .connect(&SlotNoArgs::new(&self.widget, move || {
+                this.handle_yaml_changed();
+            }));
+    }
+    unsafe fn is_yaml_mode(&self) -> bool {
+        self.tabs.current_index() == self.tabs.index_of(&self.tab_yaml)
+    }
+    /// The form is converted to YAML when the YAML tab is opened. Other tabs can not be opened
+    /// until the YAML errors are fixed
+    unsafe fn handle_tab_changed(&self) {
+        if self.is_yaml_mode() {
+            match self
+                .parse_form()
+                .and_then(|params| serde_yaml::to_string(&params).map_err(Error::invalid_data))
+            {
+                Ok(v) => self.i_yaml.set_plain_text(&qs(v)),
+                Err(e) => set_yaml_status(&self.label_yaml_status, Some(&e.to_string())),
+            }
+        } else if !self.i_yaml.gs().trim().is_empty()
+            && parse_yaml::<ServiceParams>(&self.i_yaml.gs()).is_err()
+        {
+            self.tabs.block_signals(true);
+            self.tabs.set_current_widget(&self.tab_yaml);
+            self.tabs.block_signals(false);
+        }
+    }
+    /// Valid YAML is applied to the form immediately
+    unsafe fn handle_yaml_changed(&self) {
+        match parse_yaml::<ServiceParams>(&self.i_yaml.gs()) {
+            Ok(mut params) => {
+                if !self.i_id.is_enabled() {
+                    params.id = self.i_id.gso();
+                }
+                self.set_params(params);
+                set_yaml_status(&self.label_yaml_status, None);
+                highlight_yaml(&self.i_yaml, None);
+            }
+            Err(e) => {
+                set_yaml_status(&self.label_yaml_status, Some(&e.message));
+                highlight_yaml(&self.i_yaml, e.line);
+            }
+        }
+    }
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_possible_wrap)]
-    pub unsafe fn show_add(&self, node: &str, spoints: Vec<SPointInfo>) {
+    pub unsafe fn show_add(&self, node: &NodeRef, spoints: Vec<SPointInfo>) {
         self.label_load_tpl.show();
         self.btn_load_tpl.show();
         self.i_id.set_text(&qs("eva."));
@@ -721,7 +1100,29 @@ impl DialogSvcEdit {
             .set_window_title(&qs(format!("Add service to {}", node)));
         self.widget.show();
     }
+    /// Opens the add dialog filled with the params, e.g. created from a template
+    pub unsafe fn show_add_params(
+        &self,
+        node: &NodeRef,
+        params: ServiceParams,
+        spoints: Vec<SPointInfo>,
+    ) {
+        self.show_add(node, spoints);
+        self.set_params(params);
+    }
     pub unsafe fn apply_params(&self, params: ServiceParams, spoints: Vec<SPointInfo>) {
+        self.i_launcher.clear();
+        self.i_launcher
+            .add_item_q_string(&qs(common::LAUNCHER_MAIN));
+        for spoint in spoints {
+            if let Some(name) = spoint.short_name() {
+                self.i_launcher
+                    .add_item_q_string(&qs(format!("{}{}", common::LAUNCHER_PFX, name)));
+            }
+        }
+        self.set_params(params);
+    }
+    unsafe fn set_params(&self, params: ServiceParams) {
         if let Some(id) = params.id {
             self.i_id.set_text(&qs(id));
         }
@@ -754,15 +1155,6 @@ impl DialogSvcEdit {
         self.i_workers.set_value(params.workers);
         self.i_mem_warn
             .set_value(i32::try_from(params.mem_warn / 1048576).unwrap_or_default());
-        self.i_launcher.clear();
-        self.i_launcher
-            .add_item_q_string(&qs(common::LAUNCHER_MAIN));
-        for spoint in spoints {
-            if let Some(name) = spoint.short_name() {
-                self.i_launcher
-                    .add_item_q_string(&qs(format!("{}{}", common::LAUNCHER_PFX, name)));
-            }
-        }
         self.i_launcher.set_current_text(&qs(params.launcher));
         let defaut_timeout = params
             .timeout
@@ -785,7 +1177,12 @@ impl DialogSvcEdit {
         self.i_bus_queue_size.set_value(params.bus.queue_size);
         self.i_bus_ping_interval.set_value(params.bus.ping_interval);
     }
-    pub unsafe fn show_edit(&self, node: &str, params: ServiceParams, spoints: Vec<SPointInfo>) {
+    pub unsafe fn show_edit(
+        &self,
+        node: &NodeRef,
+        params: ServiceParams,
+        spoints: Vec<SPointInfo>,
+    ) {
         if let Some(ref id) = params.id {
             self.label_load_tpl.hide();
             self.btn_load_tpl.hide();
@@ -796,8 +1193,18 @@ impl DialogSvcEdit {
             self.widget.show();
         }
     }
-    #[allow(clippy::float_cmp)]
+    /// In the YAML mode the params are parsed from the editor
     pub unsafe fn parse_params(&self) -> EResult<ServiceParams> {
+        if self.is_yaml_mode() {
+            let mut params: ServiceParams = parse_yaml(&self.i_yaml.gs())?;
+            params.id = self.i_id.gso();
+            Ok(params)
+        } else {
+            self.parse_form()
+        }
+    }
+    #[allow(clippy::float_cmp)]
+    unsafe fn parse_form(&self) -> EResult<ServiceParams> {
         let config_str = self.i_config.to_plain_text().to_std_string();
         let config: Value = if config_str.is_empty() {
             Value::Map(BTreeMap::new())
@@ -852,6 +1259,115 @@ impl DialogSvcEdit {
     }
 }
 
+#[ui_form("../ui/svc_template.ui")]
+struct QDialogSvcTemplate {
+    pub(crate) widget: QBox<QWidget>,
+    i_template: QPtr<QComboBox>,
+    label_description: QPtr<QLabel>,
+    i_id: QPtr<QLineEdit>,
+    form_vars: QPtr<QFormLayout>,
+    btnbox: QPtr<QDialogButtonBox>,
+}
+
+/// Creates service params from a template, the placeholders are filled in the form
+pub struct DialogSvcTemplate {
+    qdialog: QDialogSvcTemplate,
+    templates: Vec<templates::Template>,
+    inputs: RefCell<Vec<(String, QBox<QLineEdit>)>>,
+}
+
+impl NonModalDialog for DialogSvcTemplate {
+    unsafe fn widget(&self) -> Ptr<QWidget> {
+        self.qdialog.widget.as_ptr()
+    }
+    fn btn_box(&self) -> &QDialogButtonBox {
+        &self.qdialog.btnbox
+    }
+}
+
+impl DialogSvcTemplate {
+    pub unsafe fn new(node: &NodeRef, templates: Vec<templates::Template>) -> Self {
+        let qdialog = QDialogSvcTemplate::load();
+        qdialog
+            .widget
+            .set_window_title(&qs(format!("Add service to {} from template", node)));
+        for tpl in &templates {
+            qdialog.i_template.add_item_q_string(&qs(tpl.info()));
+        }
+        Self {
+            qdialog,
+            templates,
+            inputs: <_>::default(),
+        }
+    }
+    pub unsafe fn init(self: &Rc<Self>) {
+        let this = self.clone();
+        self.qdialog
+            .i_template
+            .current_index_changed()
+            .connect(&SlotOfInt::new(&self.qdialog.widget, move |_| {
+                this.handle_template_changed();
+            }));
+        self.handle_template_changed();
+    }
+    unsafe fn current(&self) -> Option<&templates::Template> {
+        usize::try_from(self.qdialog.i_template.current_index())
+            .ok()
+            .and_then(|i| self.templates.get(i))
+    }
+    unsafe fn handle_template_changed(&self) {
+        let qdialog = &self.qdialog;
+        let mut inputs = self.inputs.borrow_mut();
+        inputs.clear();
+        while qdialog.form_vars.row_count() > 0 {
+            qdialog.form_vars.remove_row_int(0);
+        }
+        let tpl = if let Some(tpl) = self.current() {
+            tpl
+        } else {
+            return;
+        };
+        qdialog.label_description.set_text(&qs(&tpl.description));
+        qdialog
+            .i_id
+            .set_text(&qs(tpl.id.as_deref().unwrap_or("eva.")));
+        for name in tpl.placeholders() {
+            let input = QLineEdit::new();
+            if let Some(default) = tpl.defaults.get(&name) {
+                input.set_text(&qs(default));
+            }
+            qdialog
+                .form_vars
+                .add_row_q_string_q_widget(&qs(&name), input.as_ptr());
+            inputs.push((name, input));
+        }
+    }
+    pub unsafe fn show(&self) {
+        self.qdialog.widget.show();
+    }
+    /// Service params with the placeholders filled in
+    pub unsafe fn params(&self) -> EResult<ServiceParams> {
+        let tpl = self
+            .current()
+            .ok_or_else(|| Error::invalid_params("no template selected"))?;
+        let id = self
+            .qdialog
+            .i_id
+            .gso()
+            .ok_or_else(|| Error::invalid_params("service id not specified"))?;
+        let mut vars = vars::Vars::new();
+        for (name, input) in self.inputs.borrow().iter() {
+            let value = input
+                .gso()
+                .ok_or_else(|| Error::invalid_params(format!("{} is not set", name)))?;
+            vars.insert(name.clone(), value);
+        }
+        let mut params = tpl.fill(&vars)?;
+        params.id = Some(id);
+        Ok(params)
+    }
+}
+
 #[ui_form("../ui/item_edit.ui")]
 pub struct DialogItemEdit {
     pub(crate) widget: QBox<QWidget>,
@@ -866,6 +1382,10 @@ pub struct DialogItemEdit {
     i_enabled: QPtr<QCheckBox>,
     i_action_config: QPtr<QPlainTextEdit>,
     i_meta: QPtr<QPlainTextEdit>,
+    tabs: QPtr<QTabWidget>,
+    tab_yaml: QPtr<QWidget>,
+    i_yaml: QPtr<QPlainTextEdit>,
+    label_yaml_status: QPtr<QLabel>,
     pub(crate) btnbox: QPtr<QDialogButtonBox>,
 }
 
@@ -886,6 +1406,71 @@ impl DialogItemEdit {
             .connect(&SlotOfQString::new(&self.widget, move |val| {
                 this.handle_oid_kind(&val.to_std_string());
             }));
+        let this = self.clone();
+        self.tabs
+            .current_changed()
+            .connect(&SlotOfInt::new(&self.widget, move |_| {
+                this.handle_tab_changed();
+            }));
+        let this = self.clone();
+        self.i_yaml
+            .text_changed()
+            .connect(&SlotNoArgs::new(&self.widget, move || {
+                this.handle_yaml_changed();
+            }));
+    }
+    unsafe fn is_yaml_mode(&self) -> bool {
+        self.tabs.current_index() == self.tabs.index_of(&self.tab_yaml)
+    }
+    /// The form is converted to YAML when the YAML tab is opened. Other tabs can not be opened
+    /// until the YAML errors are fixed
+    unsafe fn handle_tab_changed(self: &Rc<Self>) {
+        if self.is_yaml_mode() {
+            match self
+                .parse_form()
+                .and_then(|config| serde_yaml::to_string(&config).map_err(Error::invalid_data))
+            {
+                Ok(v) => self.i_yaml.set_plain_text(&qs(v)),
+                Err(e) => set_yaml_status(&self.label_yaml_status, Some(&e.to_string())),
+            }
+        } else if !self.i_yaml.gs().trim().is_empty()
+            && parse_yaml::<ItemConfig>(&self.i_yaml.gs()).is_err()
+        {
+            self.tabs.block_signals(true);
+            self.tabs.set_current_widget(&self.tab_yaml);
+            self.tabs.block_signals(false);
+        }
+    }
+    /// Valid YAML is applied to the form immediately
+    unsafe fn handle_yaml_changed(self: &Rc<Self>) {
+        match parse_yaml::<ItemConfig>(&self.i_yaml.gs()) {
+            Ok(config) => match self.check_oid(&config.oid) {
+                Ok(()) => {
+                    self.apply_config(config);
+                    set_yaml_status(&self.label_yaml_status, None);
+                    highlight_yaml(&self.i_yaml, None);
+                }
+                Err(e) => {
+                    set_yaml_status(&self.label_yaml_status, Some(&e.to_string()));
+                    highlight_yaml(&self.i_yaml, None);
+                }
+            },
+            Err(e) => {
+                set_yaml_status(&self.label_yaml_status, Some(&e.message));
+                highlight_yaml(&self.i_yaml, e.line);
+            }
+        }
+    }
+    unsafe fn form_oid(&self) -> EResult<OID> {
+        format!("{}{}", self.i_kind.gs(), self.i_full_id.gs()).parse()
+    }
+    /// OID of an existing item can not be changed in the YAML editor
+    unsafe fn check_oid(&self, oid: &OID) -> EResult<()> {
+        if self.i_full_id.is_enabled() || self.form_oid()? == *oid {
+            Ok(())
+        } else {
+            Err(Error::invalid_params("OID can not be changed"))
+        }
     }
     unsafe fn handle_oid_kind(self: &Rc<Self>, kind: &str) {
         // set state-related fields fields
@@ -902,7 +1487,7 @@ impl DialogItemEdit {
     }
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_possible_wrap)]
-    pub unsafe fn show_add(self: &Rc<Self>, node: &str, services: Vec<SvcData>) {
+    pub unsafe fn show_add(self: &Rc<Self>, node: &NodeRef, services: Vec<SvcData>) {
         let kind = "sensor:";
         self.handle_oid_kind(kind);
         self.i_kind.set_current_text(&qs(kind));
@@ -930,16 +1515,29 @@ impl DialogItemEdit {
     }
     pub unsafe fn show_edit(
         self: &Rc<Self>,
-        node: &str,
+        node: &NodeRef,
         config: ItemConfig,
         services: Vec<SvcData>,
     ) {
+        self.i_kind.set_enabled(false);
+        self.i_full_id.set_enabled(false);
+        self.i_action_svc.clear();
+        self.i_action_svc.add_item_q_string(&qs(""));
+        for svc in services {
+            if svc.id.starts_with(CONTROLLER_SVC_PFX) {
+                self.i_action_svc.add_item_q_string(&qs(svc.id));
+            }
+        }
+        self.widget
+            .set_window_title(&qs(format!("Edit item {}/{}", node, config.oid)));
+        self.apply_config(config);
+        self.widget.show();
+    }
+    unsafe fn apply_config(self: &Rc<Self>, config: ItemConfig) {
         let kind = format!("{}:", config.oid.kind());
         self.handle_oid_kind(&kind);
         self.i_kind.set_current_text(&qs(kind));
         self.i_full_id.set_text(&qs(config.oid.full_id()));
-        self.i_kind.set_enabled(false);
-        self.i_full_id.set_enabled(false);
         self.i_range_min.set_text(&qs(""));
         self.i_range_max.set_text(&qs(""));
         self.i_range_min_eq.set_current_text(&qs("≤"));
@@ -964,14 +1562,9 @@ impl DialogItemEdit {
                 }
             }
         }
-        self.i_action_svc.clear();
-        self.i_action_svc.add_item_q_string(&qs(""));
-        for svc in services {
-            if svc.id.starts_with(CONTROLLER_SVC_PFX) {
-                self.i_action_svc.add_item_q_string(&qs(svc.id));
-            }
-        }
+        self.i_action_svc.set_current_text(&qs(""));
         self.i_action_timeout.set_value(0.0);
+        self.i_action_config.set_plain_text(&qs(""));
         if let Some(action) = config.action {
             if let Some(timeout) = action.timeout {
                 self.i_action_timeout.set_value(timeout);
@@ -1001,13 +1594,20 @@ impl DialogItemEdit {
             self.i_meta.set_plain_text(&qs(""));
         }
         self.i_enabled.set_checked(config.enabled);
-        self.widget
-            .set_window_title(&qs(format!("Edit item {}/{}", node, config.oid)));
-        self.widget.show();
     }
-    //#[allow(clippy::float_cmp)]
+    /// In the YAML mode the config is parsed from the editor
     pub unsafe fn parse_config(&self) -> EResult<ItemConfig> {
-        let oid: OID = format!("{}{}", self.i_kind.gs(), self.i_full_id.gs()).parse()?;
+        if self.is_yaml_mode() {
+            let config: ItemConfig = parse_yaml(&self.i_yaml.gs())?;
+            self.check_oid(&config.oid)?;
+            Ok(config)
+        } else {
+            self.parse_form()
+        }
+    }
+    //#[allow(clippy::float_cmp)]
+    unsafe fn parse_form(&self) -> EResult<ItemConfig> {
+        let oid = self.form_oid()?;
         let meta_str = self.i_meta.to_plain_text().to_std_string();
         let meta: Option<Value> = if meta_str.is_empty() {
             None
@@ -1068,17 +1668,270 @@ impl DialogItemEdit {
     }
 }
 
-#[ui_form("../ui/lvar_set.ui")]
-pub struct DialogLvarSet {
+#[ui_form("../ui/item_bulk_edit.ui")]
+struct QDialogItemBulkEdit {
     pub(crate) widget: QBox<QWidget>,
-    c_status: QPtr<QCheckBox>,
-    c_value: QPtr<QCheckBox>,
-    i_status: QPtr<QSpinBox>,
-    i_value: QPtr<QPlainTextEdit>,
-    pub(crate) btn_box: QPtr<QDialogButtonBox>,
+    label_summary: QPtr<QLabel>,
+    c_enabled: QPtr<QCheckBox>,
+    i_enabled: QPtr<QCheckBox>,
+    c_action_svc: QPtr<QCheckBox>,
+    i_action_svc: QPtr<QComboBox>,
+    c_action_timeout: QPtr<QCheckBox>,
+    i_action_timeout: QPtr<QDoubleSpinBox>,
+    c_meta: QPtr<QCheckBox>,
+    i_meta_key: QPtr<QLineEdit>,
+    i_meta_value: QPtr<QPlainTextEdit>,
+    btnbox: QPtr<QDialogButtonBox>,
 }
 
-impl DialogLvarSet {
+/// Changes the same config fields of multiple items. The current field values are displayed only
+/// if they are the same for all the items, checked fields are applied
+pub struct DialogItemBulkEdit {
+    qdialog: QDialogItemBulkEdit,
+    configs: Vec<Value>,
+}
+
+impl NonModalDialog for DialogItemBulkEdit {
+    unsafe fn widget(&self) -> Ptr<QWidget> {
+        self.qdialog.widget.as_ptr()
+    }
+    fn btn_box(&self) -> &QDialogButtonBox {
+        &self.qdialog.btnbox
+    }
+}
+
+const MULTIPLE_VALUES: &str = "(multiple values)";
+
+impl DialogItemBulkEdit {
+    pub unsafe fn new(node: &NodeRef, configs: Vec<Value>, services: Vec<SvcData>) -> Self {
+        let q = QDialogItemBulkEdit::load();
+        q.widget
+            .set_window_title(&qs(format!("Bulk edit items on {}", node)));
+        q.label_summary
+            .set_text(&qs(format!("{} items selected", configs.len())));
+        match uniform_field(&configs, &["enabled"]) {
+            Some(Value::Bool(enabled)) => q.i_enabled.set_checked(enabled),
+            _ => {
+                q.i_enabled.set_tristate_1a(true);
+                q.i_enabled.set_check_state(CheckState::PartiallyChecked);
+            }
+        }
+        q.i_action_svc.add_item_q_string(&qs(""));
+        for svc in services {
+            if svc.id.starts_with(CONTROLLER_SVC_PFX) {
+                q.i_action_svc.add_item_q_string(&qs(svc.id));
+            }
+        }
+        match uniform_field(&configs, &["action", "svc"]) {
+            Some(Value::String(svc)) => q.i_action_svc.set_current_text(&qs(svc)),
+            Some(_) => q.i_action_svc.set_current_text(&qs("")),
+            None => {
+                q.i_action_svc.set_current_text(&qs(""));
+                q.i_action_svc
+                    .line_edit()
+                    .set_placeholder_text(&qs(MULTIPLE_VALUES));
+            }
+        }
+        match uniform_field(&configs, &["action", "timeout"]) {
+            Some(v) => q
+                .i_action_timeout
+                .set_value(f64::deserialize(v).unwrap_or_default()),
+            None => q
+                .i_action_timeout
+                .set_special_value_text(&qs(MULTIPLE_VALUES)),
+        }
+        Self {
+            qdialog: q,
+            configs,
+        }
+    }
+    pub unsafe fn init(self: &Rc<Self>) {
+        let q = &self.qdialog;
+        let this = self.clone();
+        q.c_enabled
+            .clicked()
+            .connect(&SlotOfBool::new(&q.widget, move |checked| {
+                let i_enabled = &this.qdialog.i_enabled;
+                i_enabled.set_enabled(checked);
+                if checked && i_enabled.check_state() == CheckState::PartiallyChecked {
+                    i_enabled.set_tristate_1a(false);
+                    i_enabled.set_checked(true);
+                }
+            }));
+        let this = self.clone();
+        q.c_action_svc
+            .clicked()
+            .connect(&SlotOfBool::new(&q.widget, move |checked| {
+                this.qdialog.i_action_svc.set_enabled(checked);
+            }));
+        let this = self.clone();
+        q.c_action_timeout
+            .clicked()
+            .connect(&SlotOfBool::new(&q.widget, move |checked| {
+                let i_action_timeout = &this.qdialog.i_action_timeout;
+                i_action_timeout.set_enabled(checked);
+                if checked {
+                    i_action_timeout.set_special_value_text(&qs(""));
+                }
+            }));
+        let this = self.clone();
+        q.c_meta
+            .clicked()
+            .connect(&SlotOfBool::new(&q.widget, move |checked| {
+                this.qdialog.i_meta_key.set_enabled(checked);
+                this.qdialog.i_meta_value.set_enabled(checked);
+            }));
+        let this = self.clone();
+        q.i_meta_key
+            .text_changed()
+            .connect(&SlotOfQString::new(&q.widget, move |_| {
+                this.show_meta_value();
+            }));
+    }
+    pub unsafe fn show(&self) {
+        self.qdialog.widget.show();
+    }
+    pub fn configs(&self) -> &[Value] {
+        &self.configs
+    }
+    unsafe fn show_meta_value(&self) {
+        let key = self.qdialog.i_meta_key.gs();
+        let i_meta_value = &self.qdialog.i_meta_value;
+        i_meta_value.set_placeholder_text(&qs(""));
+        i_meta_value.set_plain_text(&qs(""));
+        if key.is_empty() {
+            return;
+        }
+        match uniform_field(&self.configs, &["meta", key.as_str()]) {
+            Some(Value::Unit) => {}
+            Some(v) => {
+                if let Ok(s) = serde_yaml::to_string(&v) {
+                    i_meta_value.set_plain_text(&qs(s));
+                }
+            }
+            None => i_meta_value.set_placeholder_text(&qs(MULTIPLE_VALUES)),
+        }
+    }
+    pub unsafe fn parse_update(&self) -> EResult<ItemFieldsUpdate> {
+        let q = &self.qdialog;
+        let mut update = ItemFieldsUpdate::default();
+        if q.c_enabled.is_checked() {
+            update.enabled = Some(q.i_enabled.is_checked());
+        }
+        if q.c_action_svc.is_checked() {
+            update.action_svc = Some(q.i_action_svc.gs());
+        }
+        if q.c_action_timeout.is_checked() {
+            update.action_timeout = Some(q.i_action_timeout.value());
+        }
+        if q.c_meta.is_checked() {
+            let key = q.i_meta_key.gs();
+            if key.is_empty() {
+                return Err(Error::invalid_params("meta key not specified"));
+            }
+            let value = if let Some(value_str) = q.i_meta_value.gso() {
+                serde_yaml::from_str(&value_str).map_err(Error::invalid_data)?
+            } else {
+                Value::Unit
+            };
+            update.meta = Some((key, value));
+        }
+        Ok(update)
+    }
+}
+
+#[ui_form("../ui/item_generator.ui")]
+struct QDialogItemGenerator {
+    pub(crate) widget: QBox<QWidget>,
+    i_pattern: QPtr<QLineEdit>,
+    i_template: QPtr<QPlainTextEdit>,
+    label_summary: QPtr<QLabel>,
+    preview: QPtr<QPlainTextEdit>,
+    btnbox: QPtr<QDialogButtonBox>,
+}
+
+/// Generates items from an OID pattern and a config template
+pub struct DialogItemGenerator {
+    qdialog: QDialogItemGenerator,
+}
+
+impl NonModalDialog for DialogItemGenerator {
+    unsafe fn widget(&self) -> Ptr<QWidget> {
+        self.qdialog.widget.as_ptr()
+    }
+    fn btn_box(&self) -> &QDialogButtonBox {
+        &self.qdialog.btnbox
+    }
+}
+
+impl DialogItemGenerator {
+    pub unsafe fn new(node: &NodeRef) -> Self {
+        let qdialog = QDialogItemGenerator::load();
+        qdialog
+            .widget
+            .set_window_title(&qs(format!("Generate items on {}", node)));
+        qdialog.i_template.set_plain_text(&qs("enabled: true\n"));
+        Self { qdialog }
+    }
+    pub unsafe fn init(self: &Rc<Self>) {
+        let this: Rc<Self> = self.clone();
+        self.qdialog
+            .i_pattern
+            .text_changed()
+            .connect(&SlotOfQString::new(&self.qdialog.widget, move |_| {
+                this.update_preview();
+            }));
+        let this: Rc<Self> = self.clone();
+        self.qdialog
+            .i_template
+            .text_changed()
+            .connect(&SlotNoArgs::new(&self.qdialog.widget, move || {
+                this.update_preview();
+            }));
+    }
+    pub unsafe fn show(&self) {
+        self.qdialog.widget.show();
+    }
+    pub unsafe fn generate(&self) -> EResult<Vec<Value>> {
+        let pattern = self.qdialog.i_pattern.gs();
+        if pattern.is_empty() {
+            return Err(Error::invalid_params("OID pattern not specified"));
+        }
+        generate_items(&pattern, &self.qdialog.i_template.gs())
+    }
+    unsafe fn update_preview(&self) {
+        let q = &self.qdialog;
+        match self.generate() {
+            Ok(configs) => {
+                q.label_summary
+                    .set_text(&qs(format!("{} items", configs.len())));
+                let lines: Vec<String> = configs
+                    .iter()
+                    .map(|c| serde_json::to_string(c).unwrap_or_default())
+                    .collect();
+                q.preview.set_plain_text(&qs(lines.join("\n")));
+            }
+            Err(e) => {
+                q.label_summary.set_text(&qs(format!(
+                    "<span style=\"color: red; font-weight: bold\">{e}</span>"
+                )));
+                q.preview.set_plain_text(&qs(""));
+            }
+        }
+    }
+}
+
+#[ui_form("../ui/lvar_set.ui")]
+pub struct DialogLvarSet {
+    pub(crate) widget: QBox<QWidget>,
+    c_status: QPtr<QCheckBox>,
+    c_value: QPtr<QCheckBox>,
+    i_status: QPtr<QSpinBox>,
+    i_value: QPtr<QPlainTextEdit>,
+    pub(crate) btn_box: QPtr<QDialogButtonBox>,
+}
+
+impl DialogLvarSet {
     pub unsafe fn init(self: &Rc<Self>) {
         let this = self.clone();
         self.c_status
@@ -1301,6 +2154,7 @@ impl DialogUnitAction {
 
 pub struct DialogActionWatch {
     qdialog: QDialogActionWatch,
+    conn: ConnId,
     u: Mutex<Option<uuid::Uuid>>,
 }
 
@@ -1328,7 +2182,7 @@ struct QDialogActionWatch {
 }
 
 impl DialogActionWatch {
-    pub unsafe fn new(node: &str, u: uuid::Uuid) -> Self {
+    pub unsafe fn new(node: &NodeRef, u: uuid::Uuid) -> Self {
         let dialog = QDialogActionWatch::load();
         dialog
             .widget
@@ -1344,6 +2198,7 @@ impl DialogActionWatch {
         dialog.i_err.set_plain_text(&qs(""));
         Self {
             qdialog: dialog,
+            conn: node.conn(),
             u: <_>::default(),
         }
     }
@@ -1355,7 +2210,7 @@ impl DialogActionWatch {
     }
     unsafe fn stop_watcher(&self) {
         if let Some(u) = self.u.lock().unwrap().as_ref() {
-            let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(*u)));
+            let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(self.conn, *u)));
         }
     }
     unsafe fn process_data(&self, data: Value) -> EResult<()> {
@@ -1449,7 +2304,7 @@ impl NonModalInfoDialog for DialogActionWatch {
 
 pub struct DialogItemWatch {
     qdialog: QDialogItemWatch,
-    node: String,
+    node: NodeRef,
     oid: OID,
     arch: Mutex<Vec<ItemWatchData>>,
     _chart_view: QBox<QChartView>,
@@ -1478,7 +2333,7 @@ struct ItemWatchData {
 }
 
 impl DialogItemWatch {
-    pub unsafe fn new(node: &str, oid: &OID) -> Self {
+    pub unsafe fn new(node: &NodeRef, oid: &OID) -> Self {
         let qdialog = QDialogItemWatch::load();
         let title = qs(format!("{}/{}", node, oid));
         qdialog.widget.set_window_title(&title);
@@ -1504,7 +2359,7 @@ impl DialogItemWatch {
         qdialog.i_prop.set_focus_0a();
         Self {
             qdialog,
-            node: node.to_owned(),
+            node: node.clone(),
             oid: oid.clone(),
             arch: <_>::default(),
             _chart_view: chart_view,
@@ -1518,7 +2373,7 @@ impl DialogItemWatch {
             .i_interval
             .value_changed()
             .connect(&SlotOfDouble::new(&self.qdialog.widget, move |val| {
-                let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(u)));
+                let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(this.node.conn(), u)));
                 let _r = bus::call::<()>(Arc::new(NitData::start_item_watcher(
                     u,
                     &this.node,
@@ -1652,7 +2507,7 @@ impl QDialogSvcCall {
         u: &Mutex<Option<uuid::Uuid>>,
         items: &Mutex<Option<Vec<crate::smart_table::Item>>>,
         id: &str,
-        node: &str,
+        node: &NodeRef,
         params: &Mutex<Vec<SvcCallParam>>,
     ) {
         macro_rules! err {
@@ -1770,7 +2625,7 @@ impl QDialogSvcCall {
 pub struct DialogSvcCall {
     qdialog: Rc<QDialogSvcCall>,
     _id: String,
-    _node: String,
+    _node: NodeRef,
     _info: Rc<SvcInfo>,
     _params: Rc<Mutex<Vec<SvcCallParam>>>,
     op: Rc<Mutex<Option<Instant>>>,
@@ -1795,7 +2650,7 @@ impl DialogSvcCall {
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_possible_wrap)]
     #[allow(clippy::too_many_lines)]
-    pub unsafe fn new(id: &str, node: &str, info: SvcInfo) -> Rc<Self> {
+    pub unsafe fn new(id: &str, node: &NodeRef, info: SvcInfo) -> Rc<Self> {
         let dialog = Rc::new(QDialogSvcCall::load());
         let op: Rc<Mutex<Option<Instant>>> = <_>::default();
         //dialog.tbl_result.hide();
@@ -1813,7 +2668,7 @@ impl DialogSvcCall {
         let d = dialog.clone();
         let p = params.clone();
         let svc_id = id.to_owned();
-        let svc_node = node.to_owned();
+        let svc_node = node.clone();
         let op_c = op.clone();
         let u_c = u.clone();
         let items_c = items.clone();
@@ -1900,7 +2755,7 @@ impl DialogSvcCall {
         let this = Self {
             qdialog: dialog,
             _id: id.to_owned(),
-            _node: node.to_owned(),
+            _node: node.clone(),
             _info: info,
             _params: params,
             op,
@@ -2061,3 +2916,992 @@ impl NonModalInfoDialog for DialogSvcCall {
         self.qdialog.widget.close();
     }
 }
+
+#[ui_form("../ui/bulk_report.ui")]
+struct QDialogBulkReport {
+    pub(crate) widget: QBox<QWidget>,
+    label_summary: QPtr<QLabel>,
+    tbl_result: QPtr<QTableWidget>,
+    btn_copy: QPtr<QPushButton>,
+    btn_save: QPtr<QPushButton>,
+    btn_retry: QPtr<QPushButton>,
+    btn_close: QPtr<QPushButton>,
+}
+
+pub struct DialogBulkReport {
+    qdialog: QDialogBulkReport,
+    nit: Option<Nit>,
+    report: Mutex<Vec<BulkResult>>,
+    items: Mutex<Option<Vec<crate::smart_table::Item>>>,
+}
+
+impl DialogBulkReport {
+    pub unsafe fn new(nit: Nit) -> Self {
+        let dialog = Self::with_title(&format!("{} {}", nit.node(), nit.kind()));
+        Self {
+            nit: Some(nit),
+            ..dialog
+        }
+    }
+    /// A report of a multi-node operation, which can not be retried
+    pub unsafe fn with_title(title: &str) -> Self {
+        let qdialog = QDialogBulkReport::load();
+        qdialog.widget.set_window_title(&qs(title));
+        qdialog.btn_retry.set_enabled(false);
+        Self {
+            qdialog,
+            nit: None,
+            report: <_>::default(),
+            items: <_>::default(),
+        }
+    }
+    pub unsafe fn init(self: &Rc<Self>, on_retry: &QBox<SlotNoArgs>) {
+        let this: Rc<Self> = self.clone();
+        self.qdialog.btn_copy.clicked().connect(&SlotNoArgs::new(
+            &self.qdialog.widget,
+            move || {
+                let result = this
+                    .report
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|r| format!("{}\t{}", r.id, r.error.as_deref().unwrap_or("OK")))
+                    .collect::<Vec<String>>()
+                    .join(common::CRLF);
+                let mut clipboard = Clipboard::new().unwrap();
+                clipboard.set_text(result).unwrap();
+            },
+        ));
+        let this: Rc<Self> = self.clone();
+        self.qdialog.btn_save.clicked().connect(&SlotNoArgs::new(
+            &self.qdialog.widget,
+            move || {
+                let fname = QFileDialog::get_save_file_name_4a(
+                    &this.qdialog.widget,
+                    &qs(OUT_FILE),
+                    &qs(get_last_dir()),
+                    &qs(YAML_FILTER),
+                )
+                .to_std_string();
+                if !fname.is_empty() {
+                    set_last_dir(&fname);
+                    if let Err(e) = common::save_yaml(&fname, &*this.report.lock().unwrap()) {
+                        QMessageBox::warning_q_widget2_q_string(
+                            &this.qdialog.widget,
+                            &qs("Failed"),
+                            &qs(e.to_string()),
+                        );
+                    }
+                }
+            },
+        ));
+        self.qdialog.btn_retry.clicked().connect(on_retry);
+    }
+    pub unsafe fn show(&self) {
+        self.qdialog.widget.show();
+    }
+    /// The same operation for the failed resources only
+    pub fn retry_nit(&self) -> Option<NitData> {
+        let failed: Vec<String> = self
+            .report
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| !r.is_ok())
+            .map(|r| r.id.clone())
+            .collect();
+        if failed.is_empty() {
+            None
+        } else {
+            self.nit.as_ref().and_then(|nit| nit.retry(failed))
+        }
+    }
+    pub unsafe fn set_report(&self, report: Vec<BulkResult>) {
+        let failed = report.iter().filter(|r| !r.is_ok()).count();
+        self.qdialog.label_summary.set_text(&qs(format!(
+            "{} succeeded, {} failed",
+            report.len() - failed,
+            failed
+        )));
+        self.qdialog
+            .btn_retry
+            .set_enabled(failed > 0 && self.nit.is_some());
+        self.qdialog.tbl_result.set_row_count(0);
+        self.qdialog.tbl_result.set_column_count(0);
+        let results: Vec<(Value, Value, FormattedValueColor)> = report
+            .iter()
+            .map(|r| {
+                if let Some(ref e) = r.error {
+                    (
+                        Value::String(r.id.clone()),
+                        Value::String(e.clone()),
+                        FormattedValueColor::Red,
+                    )
+                } else {
+                    (
+                        Value::String(r.id.clone()),
+                        Value::String("OK".to_owned()),
+                        FormattedValueColor::Green,
+                    )
+                }
+            })
+            .collect();
+        let mut t = Table::new(&["id", "result"]);
+        for (id, result, color) in &results {
+            t.append_row(vec![
+                FormattedValue::new(id),
+                FormattedValue {
+                    color: *color,
+                    value: result,
+                },
+            ]);
+        }
+        self.items
+            .lock()
+            .unwrap()
+            .replace(t.fill_qt(&self.qdialog.tbl_result));
+        *self.report.lock().unwrap() = report;
+    }
+}
+
+impl NonModalInfoDialog for DialogBulkReport {
+    unsafe fn widget(&self) -> Ptr<QWidget> {
+        self.qdialog.widget.as_ptr()
+    }
+    fn btn_close(&self) -> &QPushButton {
+        &self.qdialog.btn_close
+    }
+    unsafe fn push(&self, data: EResult<Value>) {
+        match data.and_then(|v| Vec::<BulkResult>::deserialize(v).map_err(Into::into)) {
+            Ok(report) => self.set_report(report),
+            Err(e) => self.qdialog.label_summary.set_text(&qs(e.to_string())),
+        }
+    }
+    unsafe fn close(&self) {
+        self.qdialog.widget.close();
+    }
+}
+
+#[ui_form("../ui/import_preview.ui")]
+struct QDialogImportPreview {
+    pub(crate) widget: QBox<QWidget>,
+    label_summary: QPtr<QLabel>,
+    tbl_resources: QPtr<QTableWidget>,
+    diff: QPtr<QPlainTextEdit>,
+    btn_select_all: QPtr<QPushButton>,
+    btn_select_none: QPtr<QPushButton>,
+    btn_select_modified: QPtr<QPushButton>,
+    btnbox: QPtr<QDialogButtonBox>,
+}
+
+/// Shows resources of an import file compared with the deployed ones, only the ticked resources
+/// are deployed
+pub struct DialogImportPreview {
+    qdialog: QDialogImportPreview,
+    kind: ResourceKind,
+    /// Node name, resource kind and the entry. Node and kind columns are shown for cloud deploys
+    entries: Vec<(String, ResourceKind, ImportEntry)>,
+    cloud: bool,
+    items: Mutex<Option<Vec<crate::smart_table::Item>>>,
+}
+
+impl NonModalDialog for DialogImportPreview {
+    unsafe fn widget(&self) -> Ptr<QWidget> {
+        self.qdialog.widget.as_ptr()
+    }
+    fn btn_box(&self) -> &QDialogButtonBox {
+        &self.qdialog.btnbox
+    }
+}
+
+impl DialogImportPreview {
+    pub unsafe fn new(node: &NodeRef, kind: ResourceKind, entries: Vec<ImportEntry>) -> Self {
+        let qdialog = QDialogImportPreview::load();
+        qdialog
+            .widget
+            .set_window_title(&qs(format!("Import {} to {}", kind.key(), node)));
+        Self {
+            qdialog,
+            kind,
+            entries: entries
+                .into_iter()
+                .map(|e| (node.name().to_owned(), kind, e))
+                .collect(),
+            cloud: false,
+            items: <_>::default(),
+        }
+    }
+    pub unsafe fn new_cloud(
+        fname: &str,
+        entries: Vec<(String, ResourceKind, ImportEntry)>,
+    ) -> Self {
+        let qdialog = QDialogImportPreview::load();
+        qdialog
+            .widget
+            .set_window_title(&qs(format!("Cloud deploy {}", fname)));
+        Self {
+            qdialog,
+            kind: ResourceKind::Svcs,
+            entries,
+            cloud: true,
+            items: <_>::default(),
+        }
+    }
+    pub unsafe fn init(self: &Rc<Self>) {
+        let this: Rc<Self> = self.clone();
+        self.qdialog
+            .btn_select_all
+            .clicked()
+            .connect(&SlotNoArgs::new(&self.qdialog.widget, move || {
+                this.select(|_| true);
+            }));
+        let this: Rc<Self> = self.clone();
+        self.qdialog
+            .btn_select_none
+            .clicked()
+            .connect(&SlotNoArgs::new(&self.qdialog.widget, move || {
+                this.select(|_| false);
+            }));
+        let this: Rc<Self> = self.clone();
+        self.qdialog
+            .btn_select_modified
+            .clicked()
+            .connect(&SlotNoArgs::new(&self.qdialog.widget, move || {
+                this.select(|e| e.status != ImportStatus::Identical);
+            }));
+        let this: Rc<Self> = self.clone();
+        self.qdialog
+            .tbl_resources
+            .item_selection_changed()
+            .connect(&SlotNoArgs::new(&self.qdialog.widget, move || {
+                this.show_diff();
+            }));
+        let mut cnt: BTreeMap<String, usize> = BTreeMap::new();
+        let rows: Vec<(Value, Value, Value, Value, Value, FormattedValueColor)> = self
+            .entries
+            .iter()
+            .map(|(node, kind, e)| {
+                *cnt.entry(e.status.to_string()).or_default() += 1;
+                let color = match e.status {
+                    ImportStatus::New => FormattedValueColor::Green,
+                    ImportStatus::Changed => FormattedValueColor::Orange,
+                    ImportStatus::Identical => FormattedValueColor::Gray,
+                };
+                (
+                    Value::String(node.clone()),
+                    Value::String(kind.key().to_owned()),
+                    Value::String(e.id.clone()),
+                    Value::String(e.status.to_string()),
+                    Value::String(if e.diff.is_empty() {
+                        String::new()
+                    } else {
+                        e.diff.len().to_string()
+                    }),
+                    color,
+                )
+            })
+            .collect();
+        let mut t = if self.cloud {
+            Table::new(&["node", "kind", "id", "status", "changed fields"])
+        } else {
+            Table::new(&["id", "status", "changed fields"])
+        };
+        for (node, kind, id, status, changes, color) in &rows {
+            let mut row = Vec::with_capacity(5);
+            if self.cloud {
+                row.push(FormattedValue::new(node));
+                row.push(FormattedValue::new(kind));
+            }
+            row.push(FormattedValue::new(id));
+            row.push(FormattedValue {
+                color: *color,
+                value: status,
+            });
+            row.push(FormattedValue::new(changes));
+            t.append_row(row);
+        }
+        self.items
+            .lock()
+            .unwrap()
+            .replace(t.fill_qt(&self.qdialog.tbl_resources));
+        for row in 0..self.entries.len() {
+            let item = self.qdialog.tbl_resources.item(row as c_int, 0);
+            item.set_flags(item.flags() | ItemFlag::ItemIsUserCheckable);
+        }
+        self.select(|e| e.status != ImportStatus::Identical);
+        self.qdialog.label_summary.set_text(&qs(cnt
+            .iter()
+            .map(|(status, n)| format!("{}: {}", status, n))
+            .collect::<Vec<String>>()
+            .join(", ")));
+    }
+    pub unsafe fn show(&self) {
+        self.qdialog.widget.show();
+    }
+    pub fn kind(&self) -> ResourceKind {
+        self.kind
+    }
+    unsafe fn is_checked(&self, row: usize) -> bool {
+        self.qdialog
+            .tbl_resources
+            .item(row as c_int, 0)
+            .check_state()
+            == CheckState::Checked
+    }
+    /// Configs of the ticked resources
+    pub unsafe fn selected(&self) -> Vec<Value> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(row, _)| self.is_checked(*row))
+            .map(|(_, (_, _, e))| e.config.clone())
+            .collect()
+    }
+    /// Ticked resources of a cloud deploy, grouped by nodes in the file order
+    pub unsafe fn selected_deploy(&self) -> Vec<NodeDeploy> {
+        let mut deploy: Vec<NodeDeploy> = Vec::new();
+        for (row, (node, kind, e)) in self.entries.iter().enumerate() {
+            if !self.is_checked(row) {
+                continue;
+            }
+            let pos = if let Some(pos) = deploy.iter().position(|d| d.node == *node) {
+                pos
+            } else {
+                deploy.push(NodeDeploy {
+                    node: node.clone(),
+                    svcs: Vec::new(),
+                    items: Vec::new(),
+                });
+                deploy.len() - 1
+            };
+            match kind {
+                ResourceKind::Svcs => deploy[pos].svcs.push(e.config.clone()),
+                ResourceKind::Items => deploy[pos].items.push(e.config.clone()),
+            }
+        }
+        deploy
+    }
+    unsafe fn select<F>(&self, f: F)
+    where
+        F: Fn(&ImportEntry) -> bool,
+    {
+        for (row, (_, _, entry)) in self.entries.iter().enumerate() {
+            self.qdialog
+                .tbl_resources
+                .item(row as c_int, 0)
+                .set_check_state(if f(entry) {
+                    CheckState::Checked
+                } else {
+                    CheckState::Unchecked
+                });
+        }
+    }
+    unsafe fn show_diff(&self) {
+        let row = self.qdialog.tbl_resources.current_row();
+        let text = usize::try_from(row)
+            .ok()
+            .and_then(|row| self.entries.get(row))
+            .map_or_else(String::new, |(_, _, entry)| match entry.status {
+                ImportStatus::New => "new resource".to_owned(),
+                ImportStatus::Identical => "no changes".to_owned(),
+                ImportStatus::Changed => format_diff(&entry.diff),
+            });
+        self.qdialog.diff.set_plain_text(&qs(text));
+    }
+}
+
+#[ui_form("../ui/copy_resources.ui")]
+struct QDialogCopyResources {
+    pub(crate) widget: QBox<QWidget>,
+    label_resources: QPtr<QLabel>,
+    i_node: QPtr<QComboBox>,
+    i_prefix_from: QPtr<QLineEdit>,
+    i_prefix_to: QPtr<QLineEdit>,
+    btnbox: QPtr<QDialogButtonBox>,
+}
+
+/// Copies services or items to another node, optionally replacing the id prefix
+pub struct DialogCopyResources {
+    qdialog: QDialogCopyResources,
+    kind: ResourceKind,
+    resources: Vec<String>,
+    targets: Vec<NodeRef>,
+}
+
+impl NonModalDialog for DialogCopyResources {
+    unsafe fn widget(&self) -> Ptr<QWidget> {
+        self.qdialog.widget.as_ptr()
+    }
+    fn btn_box(&self) -> &QDialogButtonBox {
+        &self.qdialog.btnbox
+    }
+}
+
+impl DialogCopyResources {
+    /// Targets are (label, node) pairs
+    pub unsafe fn new(
+        source: &NodeRef,
+        kind: ResourceKind,
+        resources: Vec<String>,
+        targets: Vec<(String, NodeRef)>,
+        target: Option<&NodeRef>,
+    ) -> Self {
+        let qdialog = QDialogCopyResources::load();
+        qdialog
+            .widget
+            .set_window_title(&qs(format!("Copy {} from {}", kind.key(), source)));
+        qdialog
+            .label_resources
+            .set_text(&qs(if resources.len() == 1 {
+                resources[0].clone()
+            } else {
+                format!("{} {}", resources.len(), kind.key())
+            }));
+        for (label, _) in &targets {
+            qdialog.i_node.add_item_q_string(&qs(label));
+        }
+        if let Some(pos) = target.and_then(|t| targets.iter().position(|(_, n)| n == t)) {
+            qdialog.i_node.set_current_index(pos as c_int);
+        }
+        if resources.len() == 1 {
+            qdialog.i_prefix_from.set_text(&qs(&resources[0]));
+            qdialog.i_prefix_to.set_text(&qs(&resources[0]));
+        }
+        Self {
+            qdialog,
+            kind,
+            resources,
+            targets: targets.into_iter().map(|(_, n)| n).collect(),
+        }
+    }
+    pub unsafe fn show(&self) {
+        self.qdialog.widget.show();
+    }
+    pub fn kind(&self) -> ResourceKind {
+        self.kind
+    }
+    pub fn resources(&self) -> &[String] {
+        &self.resources
+    }
+    pub unsafe fn target(&self) -> Option<NodeRef> {
+        usize::try_from(self.qdialog.i_node.current_index())
+            .ok()
+            .and_then(|n| self.targets.get(n))
+            .cloned()
+    }
+    /// The id prefix and its replacement, None if the ids are not changed
+    pub unsafe fn prefix(&self) -> Option<(String, String)> {
+        let from = self.qdialog.i_prefix_from.gs();
+        let to = self.qdialog.i_prefix_to.gs();
+        if from == to {
+            None
+        } else {
+            Some((from, to))
+        }
+    }
+}
+
+#[ui_form("../ui/rename_items.ui")]
+struct QDialogRenameItems {
+    pub(crate) widget: QBox<QWidget>,
+    i_from: QPtr<QLineEdit>,
+    i_to: QPtr<QLineEdit>,
+    preview: QPtr<QPlainTextEdit>,
+    btnbox: QPtr<QDialogButtonBox>,
+}
+
+/// Renames a single item or a group of items by replacing the OID prefix
+pub struct DialogRenameItems {
+    qdialog: QDialogRenameItems,
+    oids: Vec<String>,
+}
+
+impl NonModalDialog for DialogRenameItems {
+    unsafe fn widget(&self) -> Ptr<QWidget> {
+        self.qdialog.widget.as_ptr()
+    }
+    fn btn_box(&self) -> &QDialogButtonBox {
+        &self.qdialog.btnbox
+    }
+}
+
+impl DialogRenameItems {
+    pub unsafe fn new(node: &NodeRef, oids: Vec<String>) -> Self {
+        let qdialog = QDialogRenameItems::load();
+        qdialog
+            .widget
+            .set_window_title(&qs(format!("Rename items on {}", node)));
+        let mask = if oids.len() == 1 {
+            oids[0].clone()
+        } else {
+            group_mask(&oids)
+        };
+        qdialog.i_from.set_text(&qs(&mask));
+        qdialog.i_to.set_text(&qs(&mask));
+        Self { qdialog, oids }
+    }
+    pub unsafe fn init(self: &Rc<Self>) {
+        for input in [&self.qdialog.i_from, &self.qdialog.i_to] {
+            let this: Rc<Self> = self.clone();
+            input
+                .text_changed()
+                .connect(&SlotOfQString::new(&self.qdialog.widget, move |_| {
+                    this.update_preview();
+                }));
+        }
+        self.update_preview();
+    }
+    pub unsafe fn show(&self) {
+        self.qdialog.widget.show();
+    }
+    pub unsafe fn rename(&self) -> EResult<Rename> {
+        Rename::new(
+            &self.oids,
+            &self.qdialog.i_from.gs(),
+            &self.qdialog.i_to.gs(),
+        )
+    }
+    unsafe fn update_preview(&self) {
+        let text = match self.rename() {
+            Ok(rename) => {
+                let mut lines: Vec<String> = rename
+                    .pairs()
+                    .iter()
+                    .map(|(oid, new_oid)| format!("{} -> {}", oid, new_oid))
+                    .collect();
+                lines.extend(
+                    rename
+                        .skipped()
+                        .iter()
+                        .map(|oid| format!("{} (skipped)", oid)),
+                );
+                lines.join("\n")
+            }
+            Err(e) => e.to_string(),
+        };
+        self.qdialog.preview.set_plain_text(&qs(text));
+    }
+}
+
+/// The common OID group of the items as a mask, e.g. "sensor:plant1/#"
+fn group_mask(oids: &[String]) -> String {
+    let mut prefix: &str = oids.first().map_or("", String::as_str);
+    for oid in oids.iter().skip(1) {
+        let len = prefix
+            .char_indices()
+            .zip(oid.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or_else(|| prefix.len().min(oid.len()), |((pos, _), _)| pos);
+        prefix = &prefix[..len];
+    }
+    match prefix.rfind(['/', ':']) {
+        Some(pos) => format!("{}#", &prefix[..=pos]),
+        None => String::new(),
+    }
+}
+
+#[ui_form("../ui/node_compare.ui")]
+struct QDialogNodeCompare {
+    pub(crate) widget: QBox<QWidget>,
+    label_summary: QPtr<QLabel>,
+    tbl_resources: QPtr<QTableWidget>,
+    diff: QPtr<QPlainTextEdit>,
+    btn_copy_right: QPtr<QPushButton>,
+    btn_copy_left: QPtr<QPushButton>,
+    cb_identical: QPtr<QCheckBox>,
+    btn_reload: QPtr<QPushButton>,
+    btn_close: QPtr<QPushButton>,
+}
+
+/// Services and items of two nodes, side by side
+pub struct DialogNodeCompare {
+    qdialog: QDialogNodeCompare,
+    left: NodeRef,
+    right: NodeRef,
+    entries: Mutex<Vec<CompareEntry>>,
+    // table row to entry mapping
+    rows: Mutex<Vec<usize>>,
+    items: Mutex<Option<Vec<crate::smart_table::Item>>>,
+}
+
+impl DialogNodeCompare {
+    pub unsafe fn new(left: &NodeRef, right: &NodeRef) -> Self {
+        let qdialog = QDialogNodeCompare::load();
+        qdialog
+            .widget
+            .set_window_title(&qs(format!("{} / {}", left, right)));
+        qdialog
+            .btn_copy_right
+            .set_text(&qs(format!("Copy to {}", right)));
+        qdialog
+            .btn_copy_left
+            .set_text(&qs(format!("Copy to {}", left)));
+        qdialog
+            .btn_copy_right
+            .set_enabled(!bus::is_read_only(right.conn()));
+        qdialog
+            .btn_copy_left
+            .set_enabled(!bus::is_read_only(left.conn()));
+        Self {
+            qdialog,
+            left: left.clone(),
+            right: right.clone(),
+            entries: <_>::default(),
+            rows: <_>::default(),
+            items: <_>::default(),
+        }
+    }
+    pub unsafe fn init(
+        self: &Rc<Self>,
+        on_reload: &QBox<SlotNoArgs>,
+        on_copy_right: &QBox<SlotNoArgs>,
+        on_copy_left: &QBox<SlotNoArgs>,
+    ) {
+        let this: Rc<Self> = self.clone();
+        self.qdialog
+            .cb_identical
+            .clicked()
+            .connect(&SlotOfBool::new(&self.qdialog.widget, move |_| {
+                this.fill();
+            }));
+        let this: Rc<Self> = self.clone();
+        self.qdialog
+            .tbl_resources
+            .item_selection_changed()
+            .connect(&SlotNoArgs::new(&self.qdialog.widget, move || {
+                this.show_diff();
+            }));
+        self.qdialog.btn_reload.clicked().connect(on_reload);
+        self.qdialog.btn_copy_right.clicked().connect(on_copy_right);
+        self.qdialog.btn_copy_left.clicked().connect(on_copy_left);
+    }
+    pub unsafe fn show(&self) {
+        self.qdialog.widget.show();
+    }
+    pub fn left(&self) -> &NodeRef {
+        &self.left
+    }
+    pub fn right(&self) -> &NodeRef {
+        &self.right
+    }
+    pub unsafe fn mark_loading(&self) {
+        self.qdialog.label_summary.set_text(&qs("Loading..."));
+    }
+    /// Configs (svcs, items) of the selected resources which exist on the source side
+    pub unsafe fn selected(&self, to_right: bool) -> (Vec<Value>, Vec<Value>) {
+        let selected = self.qdialog.tbl_resources.selected_items();
+        let mut selected_rows: Vec<c_int> = Vec::new();
+        while !selected.is_empty() {
+            let row = selected.take_first().row();
+            if !selected_rows.contains(&row) {
+                selected_rows.push(row);
+            }
+        }
+        let rows = self.rows.lock().unwrap();
+        let entries = self.entries.lock().unwrap();
+        let mut svcs = Vec::new();
+        let mut items = Vec::new();
+        for row in selected_rows {
+            if let Some(entry) = usize::try_from(row)
+                .ok()
+                .and_then(|row| rows.get(row))
+                .and_then(|n| entries.get(*n))
+            {
+                let config = if to_right { &entry.left } else { &entry.right };
+                if let Some(config) = config {
+                    match entry.kind {
+                        ResourceKind::Svcs => svcs.push(config.clone()),
+                        ResourceKind::Items => items.push(config.clone()),
+                    }
+                }
+            }
+        }
+        (svcs, items)
+    }
+    unsafe fn set_entries(&self, entries: Vec<CompareEntry>) {
+        let mut cnt: BTreeMap<String, usize> = BTreeMap::new();
+        for entry in &entries {
+            *cnt.entry(entry.status.to_string()).or_default() += 1;
+        }
+        self.qdialog.label_summary.set_text(&qs(cnt
+            .iter()
+            .map(|(status, n)| format!("{}: {}", status, n))
+            .collect::<Vec<String>>()
+            .join(", ")));
+        *self.entries.lock().unwrap() = entries;
+        self.fill();
+    }
+    unsafe fn fill(&self) {
+        let show_identical = self.qdialog.cb_identical.is_checked();
+        let mut rows = Vec::new();
+        let data: Vec<(Value, Value, Value, FormattedValueColor)> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| show_identical || e.status != CompareStatus::Identical)
+            .map(|(n, e)| {
+                rows.push(n);
+                let color = match e.status {
+                    CompareStatus::LeftOnly | CompareStatus::RightOnly => FormattedValueColor::Red,
+                    CompareStatus::Different => FormattedValueColor::Orange,
+                    CompareStatus::Identical => FormattedValueColor::Gray,
+                };
+                (
+                    Value::String(e.kind.key().to_owned()),
+                    Value::String(e.id.clone()),
+                    Value::String(match e.status {
+                        CompareStatus::LeftOnly => format!("{} only", self.left),
+                        CompareStatus::RightOnly => format!("{} only", self.right),
+                        _ => e.status.to_string(),
+                    }),
+                    color,
+                )
+            })
+            .collect();
+        // the table is filled outside of the locks as Qt fires selection signals
+        *self.rows.lock().unwrap() = rows;
+        let mut t = Table::new(&["kind", "id", "status"]);
+        for (kind, id, status, color) in &data {
+            t.append_row(vec![
+                FormattedValue::new(kind),
+                FormattedValue::new(id),
+                FormattedValue {
+                    color: *color,
+                    value: status,
+                },
+            ]);
+        }
+        self.qdialog.tbl_resources.set_row_count(0);
+        self.qdialog.tbl_resources.set_column_count(0);
+        self.qdialog.diff.set_plain_text(&qs(""));
+        self.items
+            .lock()
+            .unwrap()
+            .replace(t.fill_qt(&self.qdialog.tbl_resources));
+    }
+    unsafe fn show_diff(&self) {
+        let row = self.qdialog.tbl_resources.current_row();
+        let rows = self.rows.lock().unwrap();
+        let entries = self.entries.lock().unwrap();
+        let text = usize::try_from(row)
+            .ok()
+            .and_then(|row| rows.get(row))
+            .and_then(|n| entries.get(*n))
+            .map_or_else(String::new, |entry| match entry.status {
+                CompareStatus::LeftOnly => format!("exists on {} only", self.left),
+                CompareStatus::RightOnly => format!("exists on {} only", self.right),
+                CompareStatus::Identical => "no differences".to_owned(),
+                CompareStatus::Different => format!(
+                    "- {}\n+ {}\n\n{}",
+                    self.left,
+                    self.right,
+                    format_diff(&entry.diff)
+                ),
+            });
+        self.qdialog.diff.set_plain_text(&qs(text));
+    }
+}
+
+impl NonModalInfoDialog for DialogNodeCompare {
+    unsafe fn widget(&self) -> Ptr<QWidget> {
+        self.qdialog.widget.as_ptr()
+    }
+    fn btn_close(&self) -> &QPushButton {
+        &self.qdialog.btn_close
+    }
+    unsafe fn push(&self, data: EResult<Value>) {
+        match data.and_then(|v| Vec::<CompareEntry>::deserialize(v).map_err(Into::into)) {
+            Ok(entries) => self.set_entries(entries),
+            Err(e) => self.qdialog.label_summary.set_text(&qs(e.to_string())),
+        }
+    }
+    unsafe fn close(&self) {
+        self.qdialog.widget.close();
+    }
+}
+
+#[ui_form("../ui/audit.ui")]
+struct QDialogAudit {
+    pub(crate) widget: QBox<QWidget>,
+    i_filter: QPtr<QLineEdit>,
+    i_node: QPtr<QLineEdit>,
+    i_op: QPtr<QComboBox>,
+    cb_failed: QPtr<QCheckBox>,
+    btn_reload: QPtr<QPushButton>,
+    tbl_audit: QPtr<QTableWidget>,
+    label_summary: QPtr<QLabel>,
+    btn_export: QPtr<QPushButton>,
+    btn_close: QPtr<QPushButton>,
+}
+
+pub struct DialogAudit {
+    qdialog: QDialogAudit,
+    records: Mutex<Vec<AuditRecord>>,
+    items: Mutex<Option<Vec<crate::smart_table::Item>>>,
+}
+
+impl DialogAudit {
+    pub unsafe fn new() -> Self {
+        Self {
+            qdialog: QDialogAudit::load(),
+            records: <_>::default(),
+            items: <_>::default(),
+        }
+    }
+    pub unsafe fn init(self: &Rc<Self>) {
+        let q = &self.qdialog;
+        let this: Rc<Self> = self.clone();
+        q.i_filter
+            .text_changed()
+            .connect(&SlotOfQString::new(&q.widget, move |_| this.fill()));
+        let this: Rc<Self> = self.clone();
+        q.i_node
+            .text_changed()
+            .connect(&SlotOfQString::new(&q.widget, move |_| this.fill()));
+        let this: Rc<Self> = self.clone();
+        q.i_op
+            .activated2()
+            .connect(&SlotOfQString::new(&q.widget, move |_| this.fill()));
+        let this: Rc<Self> = self.clone();
+        q.cb_failed
+            .clicked()
+            .connect(&SlotOfBool::new(&q.widget, move |_| this.fill()));
+        let this: Rc<Self> = self.clone();
+        q.btn_reload
+            .clicked()
+            .connect(&SlotNoArgs::new(&q.widget, move || this.load()));
+        let this: Rc<Self> = self.clone();
+        q.btn_export
+            .clicked()
+            .connect(&SlotNoArgs::new(&q.widget, move || {
+                let fname = QFileDialog::get_save_file_name_4a(
+                    &this.qdialog.widget,
+                    &qs(OUT_FILE),
+                    &qs(get_last_dir()),
+                    &qs(JSONL_FILTER),
+                )
+                .to_std_string();
+                if !fname.is_empty() {
+                    set_last_dir(&fname);
+                    if let Err(e) = audit::save(&fname, &this.filtered()) {
+                        QMessageBox::warning_q_widget2_q_string(
+                            &this.qdialog.widget,
+                            &qs("Failed"),
+                            &qs(e.to_string()),
+                        );
+                    }
+                }
+            }));
+        let this: Rc<Self> = self.clone();
+        q.btn_close
+            .clicked()
+            .connect(&SlotNoArgs::new(&q.widget, move || {
+                this.qdialog.widget.close();
+            }));
+    }
+    pub unsafe fn show(&self) {
+        self.load();
+        self.qdialog.widget.show();
+    }
+    unsafe fn load(&self) {
+        match audit::load() {
+            Ok(records) => *self.records.lock().unwrap() = records,
+            Err(e) => {
+                QMessageBox::warning_q_widget2_q_string(
+                    &self.qdialog.widget,
+                    &qs("Unable to load audit log"),
+                    &qs(e.to_string()),
+                );
+            }
+        }
+        let i_op = &self.qdialog.i_op;
+        let current_op = i_op.current_text();
+        let mut ops: Vec<String> = self
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.op.clone())
+            .collect();
+        ops.sort_unstable();
+        ops.dedup();
+        i_op.clear();
+        i_op.add_item_q_string(&qs(""));
+        for op in ops {
+            i_op.add_item_q_string(&qs(op));
+        }
+        i_op.set_current_text(&current_op);
+        self.fill();
+    }
+    unsafe fn filtered(&self) -> Vec<AuditRecord> {
+        let q = &self.qdialog;
+        let filter = q.i_filter.gso();
+        let node = q.i_node.gso();
+        let op = q.i_op.gso();
+        let failed_only = q.cb_failed.is_checked();
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| {
+                filter.as_ref().map_or(true, |f| r.matches(f))
+                    && node.as_ref().map_or(true, |n| r.node.contains(n.as_str()))
+                    && op.as_ref().map_or(true, |o| &r.op == o)
+                    && (!failed_only || !r.is_ok())
+            })
+            .cloned()
+            .collect()
+    }
+    unsafe fn fill(&self) {
+        let records = self.filtered();
+        self.qdialog.label_summary.set_text(&qs(format!(
+            "{} of {} record(s)",
+            records.len(),
+            self.records.lock().unwrap().len()
+        )));
+        // the most recent first
+        let rows: Vec<Vec<(Value, FormattedValueColor)>> = records
+            .into_iter()
+            .rev()
+            .map(|r| {
+                let color = if r.is_ok() {
+                    FormattedValueColor::Normal
+                } else {
+                    FormattedValueColor::Red
+                };
+                vec![
+                    (Value::String(output::time_str(r.t).0), color),
+                    (Value::String(r.path), color),
+                    (r.login.map_or(Value::Unit, Value::String), color),
+                    (Value::String(r.node), color),
+                    (Value::String(r.op), color),
+                    (Value::String(r.targets.join(", ")), color),
+                    (r.payload.map_or(Value::Unit, Value::String), color),
+                    (
+                        Value::String(r.error.unwrap_or_else(|| "OK".to_owned())),
+                        color,
+                    ),
+                ]
+            })
+            .collect();
+        let mut t = Table::new(&[
+            "time", "path", "login", "node", "op", "targets", "payload", "result",
+        ]);
+        for row in &rows {
+            t.append_row(
+                row.iter()
+                    .map(|(value, color)| FormattedValue {
+                        color: *color,
+                        value,
+                    })
+                    .collect(),
+            );
+        }
+        let tbl = &self.qdialog.tbl_audit;
+        tbl.set_row_count(0);
+        tbl.set_column_count(0);
+        self.items.lock().unwrap().replace(t.fill_qt(tbl));
+    }
+}
@@ -0,0 +1,279 @@
+use crate::common::ItemConfig;
+use eva_common::prelude::*;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+pub const MAX_ITEMS: usize = 10_000;
+
+enum Part {
+    Literal(String),
+    Values(Vec<String>),
+}
+
+/// Parses a placeholder body: "1..64", "01..64" (zero-padded) or "a,b,c"
+fn parse_placeholder(s: &str) -> EResult<Vec<String>> {
+    if let Some((start_s, end_s)) = s.split_once("..") {
+        let start: i64 = start_s
+            .trim()
+            .parse()
+            .map_err(|_| Error::invalid_params(format!("invalid range start: {}", start_s)))?;
+        let end: i64 = end_s
+            .trim()
+            .parse()
+            .map_err(|_| Error::invalid_params(format!("invalid range end: {}", end_s)))?;
+        let width = if start_s.trim().len() > 1 && start_s.trim().starts_with('0') {
+            start_s.trim().len().max(end_s.trim().len())
+        } else {
+            0
+        };
+        if start.abs_diff(end) >= MAX_ITEMS as u64 {
+            return Err(Error::invalid_params(format!(
+                "the range is too large: {}",
+                s
+            )));
+        }
+        let values: Vec<i64> = if start <= end {
+            (start..=end).collect()
+        } else {
+            (end..=start).rev().collect()
+        };
+        Ok(values
+            .into_iter()
+            .map(|v| format!("{:0width$}", v, width = width))
+            .collect())
+    } else {
+        let values: Vec<String> = s
+            .split(',')
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty())
+            .collect();
+        if values.is_empty() {
+            Err(Error::invalid_params("empty placeholder"))
+        } else {
+            Ok(values)
+        }
+    }
+}
+
+fn parse_pattern(pattern: &str) -> EResult<Vec<Part>> {
+    let mut parts = Vec::new();
+    let mut rest = pattern;
+    while let Some(pos) = rest.find('{') {
+        if pos > 0 {
+            parts.push(Part::Literal(rest[..pos].to_owned()));
+        }
+        let end = rest[pos..]
+            .find('}')
+            .ok_or_else(|| Error::invalid_params("unclosed placeholder"))?;
+        parts.push(Part::Values(parse_placeholder(&rest[pos + 1..pos + end])?));
+        rest = &rest[pos + end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest.to_owned()));
+    }
+    Ok(parts)
+}
+
+/// Expands the OID pattern into (oid, placeholder values) pairs, the first placeholder is the
+/// outermost one
+pub fn expand_pattern(pattern: &str) -> EResult<Vec<(String, Vec<String>)>> {
+    let mut result: Vec<(String, Vec<String>)> = vec![(String::new(), Vec::new())];
+    for part in parse_pattern(pattern)? {
+        match part {
+            Part::Literal(s) => {
+                for (oid, _) in &mut result {
+                    oid.push_str(&s);
+                }
+            }
+            Part::Values(values) => {
+                if result.len() * values.len() > MAX_ITEMS {
+                    return Err(Error::invalid_params(format!(
+                        "too many items, max: {}",
+                        MAX_ITEMS
+                    )));
+                }
+                let mut expanded = Vec::with_capacity(result.len() * values.len());
+                for (oid, vars) in result {
+                    for v in &values {
+                        let mut vars = vars.clone();
+                        vars.push(v.clone());
+                        expanded.push((format!("{}{}", oid, v), vars));
+                    }
+                }
+                result = expanded;
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn var_value(vars: &[String], expr: &str) -> EResult<String> {
+    let (n_s, offset) = if let Some(pos) = expr.find(['+', '-']) {
+        let offset: i64 = expr[pos + 1..]
+            .trim()
+            .parse()
+            .map_err(|_| Error::invalid_params(format!("invalid variable offset: {}", expr)))?;
+        let offset = if &expr[pos..=pos] == "-" {
+            -offset
+        } else {
+            offset
+        };
+        (expr[..pos].trim(), Some(offset))
+    } else {
+        (expr.trim(), None)
+    };
+    let value = n_s
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|n| vars.get(n))
+        .ok_or_else(|| Error::invalid_params(format!("invalid variable: ${}", expr)))?;
+    if let Some(offset) = offset {
+        let v: i64 = value
+            .parse()
+            .map_err(|_| Error::invalid_params(format!("{} is not a number", value)))?;
+        Ok((v + offset).to_string())
+    } else {
+        Ok(value.clone())
+    }
+}
+
+/// Substitutes placeholder values: $1 is the value of the first placeholder, ${1+100} adds an
+/// offset to a numeric value, $$ is the dollar sign
+pub fn substitute(template: &str, vars: &[String]) -> EResult<String> {
+    let mut result = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('$') => {
+                chars.next();
+                result.push('$');
+            }
+            Some('{') => {
+                chars.next();
+                let mut expr = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => expr.push(ch),
+                        None => return Err(Error::invalid_params("unclosed variable")),
+                    }
+                }
+                result.push_str(&var_value(vars, &expr)?);
+            }
+            Some(ch) if ch.is_ascii_digit() => {
+                let mut expr = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !ch.is_ascii_digit() {
+                        break;
+                    }
+                    expr.push(ch);
+                    chars.next();
+                }
+                result.push_str(&var_value(vars, &expr)?);
+            }
+            _ => result.push('$'),
+        }
+    }
+    Ok(result)
+}
+
+/// Generates item configs from the OID pattern and the YAML config template (without oid)
+pub fn generate_items(pattern: &str, template: &str) -> EResult<Vec<Value>> {
+    let mut oids = HashSet::new();
+    let mut configs = Vec::new();
+    for (oid, vars) in expand_pattern(pattern)? {
+        let mut config: Value =
+            serde_yaml::from_str(&substitute(template, &vars)?).map_err(Error::invalid_data)?;
+        if config == Value::Unit {
+            config = Value::Map(<_>::default());
+        }
+        if let Value::Map(ref mut m) = config {
+            m.insert("oid".into(), Value::String(oid.clone()));
+        } else {
+            return Err(Error::invalid_data("the template must be a map"));
+        }
+        ItemConfig::deserialize(config.clone())
+            .map_err(|e| Error::invalid_data(format!("{}: invalid config: {}", oid, e)))?;
+        if !oids.insert(oid.clone()) {
+            return Err(Error::invalid_params(format!("duplicate OID: {}", oid)));
+        }
+        configs.push(config);
+    }
+    Ok(configs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn oids(pattern: &str) -> Vec<String> {
+        expand_pattern(pattern)
+            .unwrap()
+            .into_iter()
+            .map(|(oid, _)| oid)
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_pattern() {
+        assert_eq!(
+            oids("sensor:t{1..3}"),
+            ["sensor:t1", "sensor:t2", "sensor:t3"]
+        );
+        assert_eq!(
+            oids("sensor:t{08..10}"),
+            ["sensor:t08", "sensor:t09", "sensor:t10"]
+        );
+        assert_eq!(
+            oids("sensor:t{3..1}"),
+            ["sensor:t3", "sensor:t2", "sensor:t1"]
+        );
+        assert_eq!(
+            oids("unit:{a, b}/m{1..2}"),
+            ["unit:a/m1", "unit:a/m2", "unit:b/m1", "unit:b/m2"]
+        );
+        assert_eq!(oids("sensor:t"), ["sensor:t"]);
+        let expanded = expand_pattern("sensor:{x,y}/{5..6}").unwrap();
+        assert_eq!(expanded[1].1, ["x", "6"]);
+        assert_eq!(
+            expand_pattern("sensor:t{1..10000}").unwrap().len(),
+            MAX_ITEMS
+        );
+        assert!(expand_pattern("sensor:t{0..10000}").is_err());
+        assert!(expand_pattern("sensor:{1..100}/{1..101}").is_err());
+        assert!(expand_pattern("sensor:t{1..3").is_err());
+        assert!(expand_pattern("sensor:t{a..3}").is_err());
+        assert!(expand_pattern("sensor:t{,}").is_err());
+    }
+
+    #[test]
+    fn test_substitute() {
+        let vars = ["x".to_owned(), "5".to_owned()];
+        assert_eq!(substitute("$1/$2", &vars).unwrap(), "x/5");
+        assert_eq!(
+            substitute("${2+100} ${2-10} $$1 $", &vars).unwrap(),
+            "105 -5 $1 $"
+        );
+        assert!(substitute("$3", &vars).is_err());
+        assert!(substitute("${1+1}", &vars).is_err());
+        assert!(substitute("${2", &vars).is_err());
+    }
+
+    #[test]
+    fn test_generate_items() {
+        let configs =
+            generate_items("sensor:t{1..2}", "enabled: true\nmeta:\n  reg: ${1+100}").unwrap();
+        assert_eq!(configs.len(), 2);
+        let config: ItemConfig = ItemConfig::deserialize(configs[1].clone()).unwrap();
+        assert_eq!(config.oid.to_string(), "sensor:t2");
+        let expected: Value = serde_yaml::from_str("reg: 102").unwrap();
+        assert_eq!(config.meta, Some(expected));
+        assert!(generate_items("sensor:t{1..2}", "- 1").is_err());
+        assert!(generate_items("sensor:{a,a}", "").is_err());
+    }
+}
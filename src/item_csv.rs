@@ -0,0 +1,316 @@
+use crate::common::{BulkResult, ItemConfig};
+use eva_common::prelude::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+const COL_OID: &str = "oid";
+const COL_ENABLED: &str = "enabled";
+const COL_ACTION_SVC: &str = "action.svc";
+const COL_ACTION_TIMEOUT: &str = "action.timeout";
+const COL_RANGE_MIN: &str = "logic.range.min";
+const COL_RANGE_MAX: &str = "logic.range.max";
+const COL_RANGE_MIN_EQ: &str = "logic.range.min_eq";
+const COL_RANGE_MAX_EQ: &str = "logic.range.max_eq";
+const PFX_META: &str = "meta.";
+const PFX_ACTION_CONFIG: &str = "action.config.";
+
+const FIXED_COLS: &[&str] = &[
+    COL_OID,
+    COL_ENABLED,
+    COL_ACTION_SVC,
+    COL_ACTION_TIMEOUT,
+    COL_RANGE_MIN,
+    COL_RANGE_MAX,
+    COL_RANGE_MIN_EQ,
+    COL_RANGE_MAX_EQ,
+];
+
+/// A parsed CSV row, the row number is 1-based and does not include the header
+pub struct CsvRow {
+    pub row: usize,
+    pub oid: String,
+    pub config: EResult<Value>,
+}
+
+impl CsvRow {
+    pub fn report(rows: &[CsvRow]) -> Vec<BulkResult> {
+        rows.iter()
+            .map(|r| BulkResult {
+                id: format!("row {}: {}", r.row, r.oid),
+                error: r.config.as_ref().err().map(ToString::to_string),
+            })
+            .collect()
+    }
+}
+
+fn field<'a>(config: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    let mut value = config;
+    for f in path {
+        if let Value::Map(m) = value {
+            value = m.get(&Value::String((*f).to_owned()))?;
+        } else {
+            return None;
+        }
+    }
+    Some(value)
+}
+
+/// Flattens nested maps into dot-separated keys
+fn flatten(prefix: &str, value: &Value, result: &mut BTreeMap<String, Value>) {
+    if let Value::Map(m) = value {
+        for (k, v) in m {
+            flatten(&format!("{}{}.", prefix, k), v, result);
+        }
+    } else if let Some(key) = prefix.strip_suffix('.') {
+        result.insert(key.to_owned(), value.clone());
+    }
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Unit) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(v @ (Value::Seq(_) | Value::Map(_))) => serde_json::to_string(v).unwrap_or_default(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Meta and action config cells are parsed as YAML on import, so strings which would be read as
+/// another type (e.g. "01" or "true") are written as quoted JSON strings
+fn typed_cell(value: Option<&Value>) -> String {
+    if let Some(Value::String(s)) = value {
+        if serde_yaml::from_str::<Value>(s).ok().as_ref() != value {
+            return serde_json::to_string(s).unwrap_or_default();
+        }
+    }
+    cell(value)
+}
+
+/// Writes item configs as CSV. The meta and action config fields are flattened into columns
+/// with "meta." and "action.config." prefixes
+pub fn save(fname: &str, configs: &[Value]) -> EResult<()> {
+    let mut rows = Vec::with_capacity(configs.len());
+    let mut extra_cols = BTreeSet::new();
+    for config in configs {
+        let mut flat = BTreeMap::new();
+        if let Some(meta) = field(config, &["meta"]) {
+            flatten(PFX_META, meta, &mut flat);
+        }
+        if let Some(action_config) = field(config, &["action", "config"]) {
+            flatten(PFX_ACTION_CONFIG, action_config, &mut flat);
+        }
+        extra_cols.extend(flat.keys().cloned());
+        rows.push((config, flat));
+    }
+    let mut writer = csv::Writer::from_path(fname).map_err(Error::io)?;
+    let header: Vec<&str> = FIXED_COLS
+        .iter()
+        .copied()
+        .chain(extra_cols.iter().map(String::as_str))
+        .collect();
+    writer.write_record(&header).map_err(Error::io)?;
+    for (config, flat) in rows {
+        let mut record: Vec<String> = FIXED_COLS
+            .iter()
+            .map(|col| cell(field(config, &col.split('.').collect::<Vec<&str>>())))
+            .collect();
+        record.extend(extra_cols.iter().map(|col| typed_cell(flat.get(col))));
+        writer.write_record(&record).map_err(Error::io)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Sets a value by a dot-separated path, creating missing maps
+fn set_path(config: &mut BTreeMap<Value, Value>, path: &str, value: Value) -> EResult<()> {
+    let mut parts = path.split('.').peekable();
+    let mut map = config;
+    while let Some(part) = parts.next() {
+        if part.is_empty() {
+            return Err(Error::invalid_data(format!("invalid column: {}", path)));
+        }
+        if parts.peek().is_none() {
+            map.insert(part.into(), value);
+            return Ok(());
+        }
+        let entry = map
+            .entry(part.into())
+            .or_insert_with(|| Value::Map(BTreeMap::new()));
+        map = if let Value::Map(m) = entry {
+            m
+        } else {
+            return Err(Error::invalid_data(format!("column conflict: {}", path)));
+        };
+    }
+    Ok(())
+}
+
+fn parse_bool(s: &str) -> EResult<bool> {
+    match s.to_lowercase().as_str() {
+        "true" | "1" | "yes" | "y" => Ok(true),
+        "false" | "0" | "no" | "n" => Ok(false),
+        _ => Err(Error::invalid_data(format!("invalid boolean: {}", s))),
+    }
+}
+
+fn parse_f64(col: &str, s: &str) -> EResult<Value> {
+    s.parse::<f64>()
+        .map(Value::F64)
+        .map_err(|_| Error::invalid_data(format!("{}: invalid number: {}", col, s)))
+}
+
+fn parse_row(header: &[String], record: &csv::StringRecord) -> EResult<Value> {
+    let mut config = BTreeMap::new();
+    for (col, s) in header.iter().zip(record.iter()) {
+        let s = s.trim();
+        if s.is_empty() {
+            continue;
+        }
+        let value = match col.as_str() {
+            COL_OID => Value::String(s.parse::<OID>()?.to_string()),
+            COL_ENABLED | COL_RANGE_MIN_EQ | COL_RANGE_MAX_EQ => Value::Bool(parse_bool(s)?),
+            COL_ACTION_SVC => Value::String(s.to_owned()),
+            COL_ACTION_TIMEOUT | COL_RANGE_MIN | COL_RANGE_MAX => parse_f64(col, s)?,
+            c if c.starts_with(PFX_META) || c.starts_with(PFX_ACTION_CONFIG) => {
+                serde_yaml::from_str(s).unwrap_or_else(|_| Value::String(s.to_owned()))
+            }
+            _ => return Err(Error::invalid_data(format!("unknown column: {}", col))),
+        };
+        set_path(&mut config, col, value)?;
+    }
+    if !config.contains_key(&Value::String(COL_OID.to_owned())) {
+        return Err(Error::invalid_data("OID not specified"));
+    }
+    config
+        .entry(COL_ENABLED.into())
+        .or_insert(Value::Bool(true));
+    let config = Value::Map(config);
+    ItemConfig::deserialize(config.clone()).map_err(Error::invalid_data)?;
+    Ok(config)
+}
+
+/// Reads item configs from a CSV file. Each row is validated separately, so the file errors are
+/// reported per row. Meta and action config cells are parsed as YAML values
+pub fn load(fname: &str) -> EResult<Vec<CsvRow>> {
+    let mut reader = csv::Reader::from_path(fname).map_err(Error::io)?;
+    let header: Vec<String> = reader
+        .headers()
+        .map_err(Error::invalid_data)?
+        .iter()
+        .map(|h| h.trim().to_owned())
+        .collect();
+    let oid_col = header
+        .iter()
+        .position(|h| h == COL_OID)
+        .ok_or_else(|| Error::invalid_data("no oid column"))?;
+    let mut oids = BTreeSet::new();
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let row = match record {
+            Ok(record) => {
+                let oid = record.get(oid_col).unwrap_or_default().trim().to_owned();
+                let config = if oid.is_empty() || oids.insert(oid.clone()) {
+                    parse_row(&header, &record)
+                } else {
+                    Err(Error::invalid_data("duplicate OID"))
+                };
+                CsvRow {
+                    row: i + 1,
+                    oid,
+                    config,
+                }
+            }
+            Err(e) => CsvRow {
+                row: i + 1,
+                oid: String::new(),
+                config: Err(Error::invalid_data(e)),
+            },
+        };
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let configs = vec![
+            yaml(
+                r#"
+oid: sensor:t1
+enabled: true
+meta:
+  reg: "01"
+  flag: "true"
+  empty: ""
+  spaced: " a "
+  text: hello
+  num: 5
+  list: [1, "2"]
+  nested:
+    on: false
+action:
+  svc: eva.controller.x
+  timeout: 5.5
+  config:
+    port: "502"
+    unit: 1
+logic:
+  range:
+    min: 0
+    max: 100.5
+    min_eq: true
+    max_eq: false
+"#,
+            ),
+            yaml("oid: sensor:t2\nenabled: false"),
+        ];
+        let fname = std::env::temp_dir().join(format!("ecmui-test-{}.csv", std::process::id()));
+        let fname = fname.to_str().unwrap();
+        save(fname, &configs).unwrap();
+        let rows = load(fname).unwrap();
+        std::fs::remove_file(fname).unwrap();
+        assert_eq!(rows.len(), 2);
+        let loaded: Vec<Value> = rows.into_iter().map(|r| r.config.unwrap()).collect();
+        assert_eq!(field(&loaded[0], &["meta"]), field(&configs[0], &["meta"]));
+        assert_eq!(
+            field(&loaded[0], &["action", "config"]),
+            field(&configs[0], &["action", "config"])
+        );
+        let item = ItemConfig::deserialize(loaded[0].clone()).unwrap();
+        assert!(item.enabled);
+        let action = item.action.unwrap();
+        assert_eq!(action.svc.as_deref(), Some("eva.controller.x"));
+        assert_eq!(action.timeout, Some(5.5));
+        let range = item.logic.unwrap().range.unwrap();
+        assert_eq!(range.min, Some(0.0));
+        assert_eq!(range.max, Some(100.5));
+        assert!(range.min_eq);
+        assert!(!range.max_eq);
+        assert_eq!(loaded[1], configs[1]);
+    }
+
+    #[test]
+    fn test_row_errors() {
+        let fname = std::env::temp_dir().join(format!("ecmui-test-err-{}.csv", std::process::id()));
+        let fname = fname.to_str().unwrap();
+        std::fs::write(
+            fname,
+            "oid,enabled,logic.range.min\nsensor:t1,yes,\nsensor:t1,,\nsensor:t2,maybe,\nsensor:t3,,x\n",
+        )
+        .unwrap();
+        let rows = load(fname).unwrap();
+        std::fs::remove_file(fname).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0].config.is_ok());
+        assert!(rows.iter().skip(1).all(|r| r.config.is_err()));
+        assert_eq!(rows[3].row, 4);
+    }
+}
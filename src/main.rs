@@ -12,15 +12,24 @@ use std::env;
 use std::path::PathBuf;
 use std::sync::Mutex;
 use std::time::Duration;
-use tokio::task::JoinHandle;
 
+mod audit;
+mod backup;
 mod bus;
+mod cli;
 mod com_channel;
 mod common;
 mod forms;
+mod generator;
+mod item_csv;
 mod output;
+mod rename;
+mod resources;
 mod smart_table;
+mod templates;
 mod ui;
+mod undo;
+mod vars;
 
 use common::Nit;
 
@@ -29,10 +38,6 @@ const BUS_CLIENT_NAME: &str = "ecmui";
 const CONTROLLER_SVC_PFX: &str = "eva.controller.";
 
 lazy_static! {
-    static ref CLIENT_CHANNEL: Mutex<Option<bus::CommandTx>> = <_>::default();
-    static ref CLIENT_NAME: Mutex<Option<String>> = <_>::default();
-    static ref CONNECTION: Mutex<Option<JoinHandle<()>>> = <_>::default();
-    static ref NIT_HANDLER: Mutex<Option<JoinHandle<()>>> = <_>::default();
     static ref LAST_NIT: Mutex<Option<Nit>> = <_>::default();
     static ref UI_TX: OnceCell<Mutex<com_channel::ComChannel<ui::Command>>> = <_>::default();
     static ref CONFIG_FILE: Option<PathBuf> =
@@ -43,6 +48,30 @@ lazy_static! {
         } else {
             None
         };
+    static ref AUDIT_FILE: Option<PathBuf> =
+        if let Some(dirs) = ProjectDirs::from("com", "bohemia-automation", "ecmui") {
+            let mut p = dirs.data_local_dir().to_owned();
+            p.push("audit.jsonl");
+            Some(p)
+        } else {
+            None
+        };
+    static ref UNDO_FILE: Option<PathBuf> =
+        if let Some(dirs) = ProjectDirs::from("com", "bohemia-automation", "ecmui") {
+            let mut p = dirs.data_local_dir().to_owned();
+            p.push("undo.json");
+            Some(p)
+        } else {
+            None
+        };
+    static ref TEMPLATES_DIR: Option<PathBuf> =
+        if let Some(dirs) = ProjectDirs::from("com", "bohemia-automation", "ecmui") {
+            let mut p = dirs.data_local_dir().to_owned();
+            p.push("templates");
+            Some(p)
+        } else {
+            None
+        };
 }
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -62,7 +91,10 @@ unsafe fn main_icon() -> CppBox<QIcon> {
 
 fn main() {
     eva_common::self_test();
-    let args = common::Args::parse();
+    let mut args = common::Args::parse();
+    if let Some(command) = args.take_command() {
+        std::process::exit(cli::run(&args, command));
+    }
     std::env::set_var("QT_AUTO_SCREEN_SCALE_FACTOR", "1");
     unsafe {
         qt_core::QCoreApplication::set_attribute_2a(
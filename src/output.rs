@@ -1,6 +1,6 @@
 use crate::common::{
-    spent_time, ActionRecord, BrokerInfo, ItemInfo, LogRecord, Nit, NitKind, NodeInfo, SPointInfo,
-    SvcData,
+    spent_time, ActionRecord, BrokerInfo, ConnId, ItemInfo, LogRecord, Nit, NitKind, NodeInfo,
+    SPointInfo, SvcData,
 };
 use crate::smart_table::{self, FormattedValue, FormattedValueColor};
 use crate::ui::Ui;
@@ -8,6 +8,7 @@ use chrono::{DateTime, Local, NaiveDateTime, SecondsFormat, Utc};
 use eva_common::prelude::*;
 use serde::Deserialize;
 use std::collections::{btree_map, BTreeMap};
+use std::os::raw::c_int;
 use std::rc::Rc;
 
 #[allow(clippy::cast_possible_truncation)]
@@ -66,6 +67,9 @@ pub unsafe fn result(ui: &Rc<Ui>, nit: Nit, value: Value) -> EResult<()> {
             return Err(Error::invalid_data("invalid value received"));
         };
     }
+    if !matches!(nit.kind(), NitKind::Items(_, _)) {
+        crate::bus::set_item_view(None, Vec::new());
+    }
     match nit.kind() {
         NitKind::State => {
             if let Value::Seq(seq) = value {
@@ -101,15 +105,22 @@ pub unsafe fn result(ui: &Rc<Ui>, nit: Nit, value: Value) -> EResult<()> {
             Ok(())
         }
         NitKind::SPoints => {
-            list_spoints(ui, Vec::deserialize(value)?);
+            list_spoints(ui, Vec::deserialize(value)?, nit.conn());
             Ok(())
         }
         NitKind::Items(_, _) => {
-            list_items(ui, Vec::deserialize(value)?);
+            let data: Vec<ItemInfo> = Vec::deserialize(value)?;
+            crate::bus::set_item_view(
+                Some(nit.conn()),
+                data.iter()
+                    .map(|d| (d.node.clone(), d.oid.clone()))
+                    .collect(),
+            );
+            list_items(ui, data);
             Ok(())
         }
         NitKind::Broker => {
-            list_broker_clients(ui, BrokerInfo::deserialize(value)?);
+            list_broker_clients(ui, BrokerInfo::deserialize(value)?, nit.conn());
             Ok(())
         }
         NitKind::Log(_) => {
@@ -193,7 +204,7 @@ unsafe fn state_node_list(ui: &Rc<Ui>, data: Vec<NodeInfo>) {
     ui.secondary_table_items.lock().unwrap().append(&mut items);
 }
 
-unsafe fn list_broker_clients(ui: &Rc<Ui>, data: BrokerInfo) {
+unsafe fn list_broker_clients(ui: &Rc<Ui>, data: BrokerInfo, conn: ConnId) {
     ui.clear_tables();
     ui.window.secondary_table.hide();
     let qt_table = &ui.window.primary_table;
@@ -211,7 +222,7 @@ unsafe fn list_broker_clients(ui: &Rc<Ui>, data: BrokerInfo) {
     ]);
     let mut rows: Vec<Vec<Value>> = Vec::new();
     {
-        let client_name = crate::CLIENT_NAME.lock().unwrap();
+        let client_name = crate::bus::client_name(conn);
         for d in data.clients {
             if let Some(n) = client_name.as_ref() {
                 if n == &d.name {
@@ -281,6 +292,50 @@ unsafe fn list_services(ui: &Rc<Ui>, data: Vec<SvcData>) {
     ui.primary_table_items.lock().unwrap().append(&mut items);
 }
 
+const ITEM_COL_OID: c_int = 0;
+const ITEM_COL_CONNECTED: c_int = 2;
+const ITEM_COL_STATUS: c_int = 3;
+const ITEM_COL_VALUE: c_int = 4;
+const ITEM_COL_SET_TIME: c_int = 5;
+const ITEM_COL_NODE: c_int = 6;
+
+/// Updates the item row of the item list in-place
+pub unsafe fn update_item_state(ui: &Ui, state: &ItemInfo) {
+    let qt_table = &ui.window.primary_table;
+    let oid = state.oid.as_str();
+    for row in 0..qt_table.row_count() {
+        let item_oid = qt_table.item(row, ITEM_COL_OID);
+        let item_node = qt_table.item(row, ITEM_COL_NODE);
+        if item_oid.is_null()
+            || item_node.is_null()
+            || item_oid.text().to_std_string() != oid
+            || item_node.text().to_std_string() != state.node
+        {
+            continue;
+        }
+        let values = [
+            (ITEM_COL_CONNECTED, Value::Bool(state.connected)),
+            (
+                ITEM_COL_STATUS,
+                state.status.map_or(Value::Unit, Value::I16),
+            ),
+            (ITEM_COL_VALUE, format_value(state.value.clone())),
+            (
+                ITEM_COL_SET_TIME,
+                state
+                    .t
+                    .map_or(Value::Unit, |t| Value::String(time_str(t).0)),
+            ),
+        ];
+        for (col, value) in &values {
+            if let Some(item) = qt_table.item(row, *col).as_ref() {
+                smart_table::set_item_value(&item, &FormattedValue::new(value));
+            }
+        }
+        break;
+    }
+}
+
 unsafe fn list_items(ui: &Rc<Ui>, data: Vec<ItemInfo>) {
     ui.clear_tables();
     ui.window.secondary_table.hide();
@@ -340,14 +395,14 @@ unsafe fn list_items(ui: &Rc<Ui>, data: Vec<ItemInfo>) {
     ui.primary_table_items.lock().unwrap().append(&mut items);
 }
 
-unsafe fn list_spoints(ui: &Rc<Ui>, data: Vec<SPointInfo>) {
+unsafe fn list_spoints(ui: &Rc<Ui>, data: Vec<SPointInfo>, conn: ConnId) {
     ui.clear_tables();
     ui.window.secondary_table.hide();
     let qt_table = &ui.window.primary_table;
     let mut smart_table = smart_table::Table::new(&["name", "source", "port", "version", "build"]);
     let mut rows: Vec<Vec<Value>> = Vec::new();
     {
-        let client_name = crate::CLIENT_NAME.lock().unwrap();
+        let client_name = crate::bus::client_name(conn);
         for d in data {
             if let Some(n) = client_name.as_ref() {
                 if n == &d.name {
@@ -0,0 +1,224 @@
+use crate::bus;
+use crate::common::{NitData, NodeRef, PayloadLvarSet};
+use eva_common::prelude::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Strips the trailing wildcard of a group mask, e.g. "sensor:plant1/#" becomes "sensor:plant1/"
+pub fn mask_prefix(mask: &str) -> &str {
+    mask.strip_suffix('#').unwrap_or(mask)
+}
+
+/// Item OID rename plan. The items which do not match the prefix are skipped
+pub struct Rename {
+    pairs: Vec<(OID, OID)>,
+    skipped: Vec<String>,
+}
+
+struct SavedItem {
+    oid: OID,
+    config: Value,
+    state: Option<PayloadLvarSet>,
+}
+
+impl Rename {
+    pub fn new(oids: &[String], from: &str, to: &str) -> EResult<Self> {
+        let from = mask_prefix(from);
+        let to = mask_prefix(to);
+        if from.is_empty() {
+            return Err(Error::invalid_params("the source prefix is empty"));
+        }
+        let mut pairs = Vec::new();
+        let mut skipped = Vec::new();
+        for s in oids {
+            if let Some(rest) = s.strip_prefix(from) {
+                let oid: OID = s.parse()?;
+                let new_oid: OID = format!("{}{}", to, rest).parse()?;
+                if oid.kind() != new_oid.kind() {
+                    return Err(Error::invalid_params(format!(
+                        "{}: item kind can not be changed",
+                        oid
+                    )));
+                }
+                if oid != new_oid {
+                    pairs.push((oid, new_oid));
+                }
+            } else {
+                skipped.push(s.clone());
+            }
+        }
+        let olds: HashSet<&OID> = pairs.iter().map(|(o, _)| o).collect();
+        let mut news: HashSet<&OID> = HashSet::new();
+        for (_, new_oid) in &pairs {
+            if olds.contains(new_oid) || !news.insert(new_oid) {
+                return Err(Error::invalid_params(format!(
+                    "{}: the target OID is used more than once",
+                    new_oid
+                )));
+            }
+        }
+        Ok(Self { pairs, skipped })
+    }
+    pub fn pairs(&self) -> &[(OID, OID)] {
+        &self.pairs
+    }
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+    /// Deploys the items under the new OIDs, copies lvar states and destroys the old items after
+    /// all the new ones are confirmed. On failure the node is rolled back: the new items are
+    /// destroyed and the old ones are re-deployed if already destroyed
+    pub fn run(&self, node: &NodeRef) -> EResult<()> {
+        let mut saved = Vec::with_capacity(self.pairs.len());
+        let mut configs = Vec::with_capacity(self.pairs.len());
+        for (oid, new_oid) in &self.pairs {
+            match bus::call::<Value>(Arc::new(NitData::new_item_get_config(
+                node,
+                new_oid.to_string(),
+            ))) {
+                Ok(_) => {
+                    return Err(Error::failed(format!("{} already exists", new_oid)));
+                }
+                Err(e) if e.kind() == ErrorKind::ResourceNotFound => {}
+                Err(e) => return Err(e),
+            }
+            let mut config: Value = bus::call(Arc::new(NitData::new_item_get_config(
+                node,
+                oid.to_string(),
+            )))?;
+            let state = if oid.kind() == ItemKind::Lvar {
+                let state = bus::item_state(node, oid.clone())?;
+                Some(PayloadLvarSet {
+                    i: None,
+                    status: Some(state.status),
+                    value: state.value,
+                })
+            } else {
+                None
+            };
+            saved.push(SavedItem {
+                oid: oid.clone(),
+                config: config.clone(),
+                state,
+            });
+            if let Value::Map(ref mut m) = config {
+                m.insert("oid".into(), Value::String(new_oid.to_string()));
+            }
+            configs.push(config);
+        }
+        self.deploy_new(node, configs, &saved)
+            .map_err(|e| self.rollback(node, &saved, false, e))?;
+        bus::call::<()>(Arc::new(NitData::new_item_destroy(
+            node,
+            saved.iter().map(|s| s.oid.to_string()).collect(),
+        )))
+        .map_err(|e| self.rollback(node, &saved, true, e))
+    }
+    fn deploy_new(&self, node: &NodeRef, configs: Vec<Value>, saved: &[SavedItem]) -> EResult<()> {
+        bus::call::<()>(Arc::new(NitData::new_item_deploy_multi(node, configs)))?;
+        for ((_, new_oid), item) in self.pairs.iter().zip(saved) {
+            if let Some(ref state) = item.state {
+                bus::call::<()>(Arc::new(NitData::new_lvar_set(
+                    node,
+                    vec![new_oid.to_string()],
+                    state.clone(),
+                )))?;
+            }
+        }
+        for (_, new_oid) in &self.pairs {
+            bus::call::<Value>(Arc::new(NitData::new_item_get_config(
+                node,
+                new_oid.to_string(),
+            )))
+            .map_err(|e| Error::failed(format!("{} not confirmed: {}", new_oid, e)))?;
+        }
+        Ok(())
+    }
+    /// The rollback is performed even if the operation has been cancelled
+    fn rollback(&self, node: &NodeRef, saved: &[SavedItem], restore: bool, err: Error) -> Error {
+        let result = bus::detached(|| {
+            if restore {
+                bus::call::<()>(Arc::new(NitData::new_item_deploy_multi(
+                    node,
+                    saved.iter().map(|s| s.config.clone()).collect(),
+                )))?;
+                for item in saved {
+                    if let Some(ref state) = item.state {
+                        bus::call::<()>(Arc::new(NitData::new_lvar_set(
+                            node,
+                            vec![item.oid.to_string()],
+                            state.clone(),
+                        )))?;
+                    }
+                }
+            }
+            // the new items may be not deployed at all
+            for (_, new_oid) in &self.pairs {
+                match bus::call::<()>(Arc::new(NitData::new_item_destroy(
+                    node,
+                    vec![new_oid.to_string()],
+                ))) {
+                    Err(e) if e.kind() != ErrorKind::ResourceNotFound => return Err(e),
+                    _ => {}
+                }
+            }
+            Ok(())
+        });
+        match result {
+            Ok(()) => Error::failed(format!("{} (rolled back)", err)),
+            Err(e) => Error::failed(format!("{} (rollback failed: {})", err, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn oids(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| (*s).to_owned()).collect()
+    }
+
+    #[test]
+    fn test_rename_plan() {
+        let rename = Rename::new(
+            &oids(&["sensor:p1/t1", "sensor:p1/t2", "sensor:p2/t1"]),
+            "sensor:p1/#",
+            "sensor:p3/#",
+        )
+        .unwrap();
+        let pairs: Vec<(String, String)> = rename
+            .pairs()
+            .iter()
+            .map(|(o, n)| (o.to_string(), n.to_string()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("sensor:p1/t1".to_owned(), "sensor:p3/t1".to_owned()),
+                ("sensor:p1/t2".to_owned(), "sensor:p3/t2".to_owned())
+            ]
+        );
+        assert_eq!(rename.skipped(), ["sensor:p2/t1"]);
+        assert!(Rename::new(&oids(&["sensor:t1"]), "sensor:t1", "sensor:t1")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rename_errors() {
+        assert!(Rename::new(&oids(&["sensor:t1"]), "#", "sensor:x").is_err());
+        assert!(Rename::new(&oids(&["sensor:t1"]), "sensor:", "unit:").is_err());
+        // the target is one of the renamed items
+        assert!(Rename::new(
+            &oids(&["sensor:t1", "sensor:t11"]),
+            "sensor:t1",
+            "sensor:t11"
+        )
+        .is_err());
+        assert!(Rename::new(&oids(&["sensor:a/t", "sensor:b/t"]), "sensor:", "sensor:x").is_ok());
+    }
+}
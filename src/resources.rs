@@ -0,0 +1,698 @@
+use crate::common::{load_data, ConnId, NitData, NodeRef, ServiceParams};
+use crate::vars;
+use eva_common::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+#[derive(clap::ValueEnum, Serialize, Deserialize, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceKind {
+    Svcs,
+    Items,
+}
+
+impl ResourceKind {
+    /// Resource file section name
+    pub fn key(self) -> &'static str {
+        match self {
+            ResourceKind::Svcs => "svcs",
+            ResourceKind::Items => "items",
+        }
+    }
+    pub fn id_field(self) -> &'static str {
+        match self {
+            ResourceKind::Svcs => "id",
+            ResourceKind::Items => "oid",
+        }
+    }
+}
+
+/// Loads a resource file section
+pub fn load_import(kind: ResourceKind, fname: &str) -> EResult<Vec<Value>> {
+    let mut data: HashMap<String, Value> =
+        load_data(fname)?.ok_or_else(|| Error::invalid_data("No input"))?;
+    if data.contains_key("version") {
+        return Err(Error::invalid_data(
+            "cloud deploy files can not be imported as resources",
+        ));
+    }
+    if let Some(section) = data.remove(kind.key()) {
+        if let Value::Seq(seq) = section {
+            Ok(seq)
+        } else {
+            Err(Error::invalid_data("section is not a seq"))
+        }
+    } else {
+        Err(Error::invalid_data(format!("no {} section", kind.key())))
+    }
+}
+
+pub fn deploy_nit(node: &NodeRef, kind: ResourceKind, configs: Vec<Value>) -> NitData {
+    match kind {
+        ResourceKind::Svcs => NitData::new_svc_deploy_multi(node, configs),
+        ResourceKind::Items => NitData::new_item_deploy_multi(node, configs),
+    }
+}
+
+/// Loads a resource file and creates a deploy command, returns the command and the number of
+/// resources. Placeholders are resolved from the environment files and the process environment
+pub fn import_nit(
+    node: &NodeRef,
+    kind: ResourceKind,
+    fname: &str,
+    env_files: &[String],
+) -> EResult<(NitData, usize)> {
+    let mut configs = load_import(kind, fname)?;
+    vars::resolve_env(configs.iter_mut().collect(), env_files)?;
+    let cnt = configs.len();
+    Ok((deploy_nit(node, kind, configs), cnt))
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum ImportStatus {
+    New,
+    Changed,
+    Identical,
+}
+
+impl fmt::Display for ImportStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ImportStatus::New => "new",
+                ImportStatus::Changed => "changed",
+                ImportStatus::Identical => "identical",
+            }
+        )
+    }
+}
+
+/// A field which differs between two configs
+#[derive(Serialize, Deserialize)]
+pub struct FieldDiff {
+    pub path: String,
+    pub current: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A resource of an import file, compared with the deployed one
+pub struct ImportEntry {
+    pub id: String,
+    pub config: Value,
+    pub status: ImportStatus,
+    pub diff: Vec<FieldDiff>,
+}
+
+impl ImportEntry {
+    /// Services are compared by params, items by the full config
+    pub fn new(kind: ResourceKind, config: Value, current: Option<Value>) -> EResult<Self> {
+        let id = resource_id(kind, &config)?;
+        let (status, diff) = if let Some(current) = current {
+            let diff = match kind {
+                ResourceKind::Svcs => diff_values(
+                    &current,
+                    config_field(&config, "params").unwrap_or(&Value::Unit),
+                ),
+                ResourceKind::Items => diff_values(&current, &config),
+            };
+            if diff.is_empty() {
+                (ImportStatus::Identical, diff)
+            } else {
+                (ImportStatus::Changed, diff)
+            }
+        } else {
+            (ImportStatus::New, Vec::new())
+        };
+        Ok(Self {
+            id,
+            config,
+            status,
+            diff,
+        })
+    }
+}
+
+fn config_field<'a>(config: &'a Value, field: &str) -> Option<&'a Value> {
+    if let Value::Map(m) = config {
+        m.get(&Value::String(field.to_owned()))
+    } else {
+        None
+    }
+}
+
+/// Service id or item oid of a resource config
+pub fn resource_id(kind: ResourceKind, config: &Value) -> EResult<String> {
+    let field = kind.id_field();
+    config_field(config, field)
+        .map(ToString::to_string)
+        .ok_or_else(|| Error::invalid_data(format!("{} entry with no {}", kind.key(), field)))
+}
+
+/// Replaces the service id or item oid prefix. The resource config is not modified if the id does
+/// not start with the prefix
+pub fn replace_id_prefix(
+    kind: ResourceKind,
+    config: &mut Value,
+    from: &str,
+    to: &str,
+) -> EResult<()> {
+    let id = resource_id(kind, config)?;
+    if let Some(rest) = id.strip_prefix(from) {
+        let new_id = format!("{}{}", to, rest);
+        if kind == ResourceKind::Items {
+            new_id.parse::<OID>()?;
+        } else if new_id.is_empty() {
+            return Err(Error::invalid_data(format!("{}: empty service id", id)));
+        }
+        if let Value::Map(m) = config {
+            m.insert(kind.id_field().into(), Value::String(new_id));
+        }
+    }
+    Ok(())
+}
+
+/// Item config fields changed by the bulk editor, None fields are not modified. An empty action
+/// service, a zero action timeout and a unit meta value remove the field
+#[derive(Default)]
+pub struct ItemFieldsUpdate {
+    pub enabled: Option<bool>,
+    pub action_svc: Option<String>,
+    pub action_timeout: Option<f64>,
+    pub meta: Option<(String, Value)>,
+}
+
+impl ItemFieldsUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.enabled.is_none()
+            && self.action_svc.is_none()
+            && self.action_timeout.is_none()
+            && self.meta.is_none()
+    }
+    /// Action fields are applied to units and lmacros only
+    pub fn apply(&self, config: &mut Value) {
+        let has_action = config_field(config, "oid")
+            .and_then(|v| v.to_string().parse::<OID>().ok())
+            .map_or(false, |oid| {
+                oid.kind() == ItemKind::Unit || oid.kind() == ItemKind::Lmacro
+            });
+        let m = if let Value::Map(m) = config {
+            m
+        } else {
+            return;
+        };
+        if let Some(enabled) = self.enabled {
+            m.insert("enabled".into(), Value::Bool(enabled));
+        }
+        if has_action {
+            if let Some(ref svc) = self.action_svc {
+                let value = if svc.is_empty() {
+                    Value::Unit
+                } else {
+                    Value::String(svc.clone())
+                };
+                set_map_field(m, "action", "svc", value);
+            }
+            if let Some(timeout) = self.action_timeout {
+                let value = if timeout > 0.0 {
+                    Value::F64(timeout)
+                } else {
+                    Value::Unit
+                };
+                set_map_field(m, "action", "timeout", value);
+            }
+        }
+        if let Some((ref key, ref value)) = self.meta {
+            set_map_field(m, "meta", key, value.clone());
+        }
+    }
+}
+
+/// Sets a field of a nested map, the map is created if missing. Unit values remove the field
+fn set_map_field(m: &mut BTreeMap<Value, Value>, section: &str, field: &str, value: Value) {
+    let entry = m
+        .entry(section.into())
+        .or_insert_with(|| Value::Map(BTreeMap::new()));
+    if !matches!(entry, Value::Map(_)) {
+        *entry = Value::Map(BTreeMap::new());
+    }
+    if let Value::Map(s) = entry {
+        if value == Value::Unit {
+            s.remove(&Value::String(field.to_owned()));
+        } else {
+            s.insert(field.into(), value);
+        }
+    }
+}
+
+/// Returns the field value if it is the same in all configs, missing fields are units
+pub fn uniform_field(configs: &[Value], path: &[&str]) -> Option<Value> {
+    let mut result: Option<Value> = None;
+    for config in configs {
+        let mut value = Some(config);
+        for field in path {
+            value = value.and_then(|v| config_field(v, field));
+        }
+        let value = value.cloned().unwrap_or(Value::Unit);
+        match result {
+            Some(ref r) if *r != value => return None,
+            Some(_) => {}
+            None => result = Some(value),
+        }
+    }
+    result
+}
+
+/// Compares two values field by field, nested maps are compared recursively. The values are
+/// compared in JSON representation, numbers are compared as floats, so 5 and 5.0 are equal.
+/// Missing fields are equal to nulls
+pub fn diff_values(current: &Value, new: &Value) -> Vec<FieldDiff> {
+    let mut result = Vec::new();
+    if let (Ok(c), Ok(n)) = (serde_json::to_value(current), serde_json::to_value(new)) {
+        diff_at("", &c, &n, &mut result);
+    }
+    result
+}
+
+fn diff_at(
+    path: &str,
+    current: &serde_json::Value,
+    new: &serde_json::Value,
+    result: &mut Vec<FieldDiff>,
+) {
+    if let (serde_json::Value::Object(c), serde_json::Value::Object(n)) = (current, new) {
+        let keys: BTreeSet<&String> = c.keys().chain(n.keys()).collect();
+        for key in keys {
+            let p = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            diff_at(
+                &p,
+                c.get(key).unwrap_or(&serde_json::Value::Null),
+                n.get(key).unwrap_or(&serde_json::Value::Null),
+                result,
+            );
+        }
+    } else if !leaf_eq(current, new) {
+        let value = |v: &serde_json::Value| {
+            if v.is_null() {
+                None
+            } else {
+                Some(v.to_string())
+            }
+        };
+        result.push(FieldDiff {
+            path: path.to_owned(),
+            current: value(current),
+            new: value(new),
+        });
+    }
+}
+
+fn leaf_eq(current: &serde_json::Value, new: &serde_json::Value) -> bool {
+    match (current, new) {
+        (serde_json::Value::Number(c), serde_json::Value::Number(n)) => c.as_f64() == n.as_f64(),
+        (serde_json::Value::Array(c), serde_json::Value::Array(n)) => {
+            c.len() == n.len() && c.iter().zip(n).all(|(c, n)| leaf_eq(c, n))
+        }
+        (serde_json::Value::Object(c), serde_json::Value::Object(n)) => {
+            c.len() == n.len()
+                && c.iter()
+                    .all(|(k, c)| n.get(k).map_or(false, |n| leaf_eq(c, n)))
+        }
+        _ => current == new,
+    }
+}
+
+pub fn format_diff(diff: &[FieldDiff]) -> String {
+    diff.iter()
+        .map(|d| {
+            format!(
+                "{}\n  - {}\n  + {}",
+                d.path,
+                d.current.as_deref().unwrap_or("null"),
+                d.new.as_deref().unwrap_or("null")
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareStatus {
+    LeftOnly,
+    RightOnly,
+    Different,
+    Identical,
+}
+
+impl fmt::Display for CompareStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CompareStatus::LeftOnly => "left only",
+                CompareStatus::RightOnly => "right only",
+                CompareStatus::Different => "different",
+                CompareStatus::Identical => "identical",
+            }
+        )
+    }
+}
+
+/// A resource of two compared nodes
+#[derive(Serialize, Deserialize)]
+pub struct CompareEntry {
+    pub kind: ResourceKind,
+    pub id: String,
+    pub status: CompareStatus,
+    pub left: Option<Value>,
+    pub right: Option<Value>,
+    pub diff: Vec<FieldDiff>,
+}
+
+/// Compares resource configs of two nodes, the result is sorted by id
+pub fn compare(
+    kind: ResourceKind,
+    left: Vec<Value>,
+    right: Vec<Value>,
+) -> EResult<Vec<CompareEntry>> {
+    let mut entries: BTreeMap<String, (Option<Value>, Option<Value>)> = BTreeMap::new();
+    for config in left {
+        let id = resource_id(kind, &config)?;
+        entries.entry(id).or_default().0 = Some(config);
+    }
+    for config in right {
+        let id = resource_id(kind, &config)?;
+        entries.entry(id).or_default().1 = Some(config);
+    }
+    Ok(entries
+        .into_iter()
+        .map(|(id, (left, right))| {
+            let (status, diff) = match (&left, &right) {
+                (Some(l), Some(r)) => {
+                    let diff = diff_values(l, r);
+                    if diff.is_empty() {
+                        (CompareStatus::Identical, diff)
+                    } else {
+                        (CompareStatus::Different, diff)
+                    }
+                }
+                (Some(_), None) => (CompareStatus::LeftOnly, Vec::new()),
+                _ => (CompareStatus::RightOnly, Vec::new()),
+            };
+            CompareEntry {
+                kind,
+                id,
+                status,
+                left,
+                right,
+                diff,
+            }
+        })
+        .collect())
+}
+
+/// Resources of a single node section of a cloud deploy file
+pub struct NodeDeploy {
+    pub node: String,
+    pub svcs: Vec<Value>,
+    pub items: Vec<Value>,
+}
+
+impl NodeDeploy {
+    /// Deploy commands, services go first as items may refer to them
+    pub fn nits(&self, conn: ConnId) -> Vec<NitData> {
+        let node = NodeRef::new(conn, &self.node);
+        let mut nits = Vec::new();
+        if !self.svcs.is_empty() {
+            nits.push(NitData::new_svc_deploy_multi(&node, self.svcs.clone()));
+        }
+        if !self.items.is_empty() {
+            nits.push(NitData::new_item_deploy_multi(&node, self.items.clone()));
+        }
+        nits
+    }
+    pub fn summary(&self) -> String {
+        format!("{} svcs, {} items", self.svcs.len(), self.items.len())
+    }
+}
+
+/// Loads a cloud deploy file, returns None if the file is a regular resource file. Only svcs and
+/// items sections are processed, other deploy instructions are ignored
+pub fn load_cloud_deploy(fname: &str) -> EResult<Option<Vec<NodeDeploy>>> {
+    let mut data: HashMap<String, Value> =
+        load_data(fname)?.ok_or_else(|| Error::invalid_data("No input"))?;
+    if !data.contains_key("version") {
+        return Ok(None);
+    }
+    let content = match data.remove("content") {
+        Some(Value::Seq(content)) => content,
+        Some(_) => return Err(Error::invalid_data("Section content is not a seq")),
+        None => return Ok(Some(Vec::new())),
+    };
+    let mut result: Vec<NodeDeploy> = Vec::new();
+    for entry in content {
+        let mut entry: HashMap<String, Value> = HashMap::deserialize(entry)
+            .map_err(|_| Error::invalid_data("content entry is not a map"))?;
+        let node = entry
+            .remove("node")
+            .ok_or_else(|| Error::invalid_data("content entry has no node"))?
+            .to_string();
+        let mut section = |key: &str| -> EResult<Vec<Value>> {
+            match entry.remove(key) {
+                Some(Value::Seq(seq)) => Ok(seq),
+                Some(_) => Err(Error::invalid_data(format!(
+                    "{}: {} entry is not a seq",
+                    node, key
+                ))),
+                None => Ok(Vec::new()),
+            }
+        };
+        let svcs = section("svcs")?;
+        let items = section("items")?;
+        if let Some(d) = result.iter_mut().find(|d| d.node == node) {
+            d.svcs.extend(svcs);
+            d.items.extend(items);
+        } else {
+            result.push(NodeDeploy { node, svcs, items });
+        }
+    }
+    Ok(Some(result))
+}
+
+/// Loads the service params from a file: plain service params, a resource file or a cloud deploy
+/// file. The cloud deploy section of the given node is preferred, other sections are searched if
+/// the service is not found there
+pub fn load_svc_params(fname: &str, id: &str, node: &str) -> EResult<Value> {
+    let find = |svcs: &[Value]| {
+        svcs.iter()
+            .find(|svc| resource_id(ResourceKind::Svcs, svc).ok().as_deref() == Some(id))
+            .and_then(|svc| config_field(svc, "params"))
+            .cloned()
+    };
+    let not_found = || Error::not_found(format!("service {} not found in {}", id, fname));
+    if let Some(deploy) = load_cloud_deploy(fname)? {
+        let (own, other): (Vec<&NodeDeploy>, Vec<&NodeDeploy>) =
+            deploy.iter().partition(|d| d.node == node);
+        return own
+            .into_iter()
+            .chain(other)
+            .find_map(|d| find(&d.svcs))
+            .ok_or_else(not_found);
+    }
+    let data: Value = load_data(fname)?.ok_or_else(|| Error::invalid_data("No input"))?;
+    if let Some(svcs) = config_field(&data, "svcs") {
+        return if let Value::Seq(svcs) = svcs {
+            find(svcs).ok_or_else(not_found)
+        } else {
+            Err(Error::invalid_data("Section svcs is not a seq"))
+        };
+    }
+    ServiceParams::deserialize(data.clone()).map_err(|e| {
+        Error::invalid_data(format!("neither service params nor a resource file: {}", e))
+    })?;
+    Ok(data)
+}
+
+/// Prepares the export data. If merge is requested and the file exists, its content is loaded
+pub fn export_data(
+    fname: &str,
+    cloud_deploy: bool,
+    merge: bool,
+) -> EResult<BTreeMap<Value, Value>> {
+    let create_map = || {
+        let mut map = BTreeMap::new();
+        if cloud_deploy {
+            map.insert("version".into(), Value::U8(4));
+        }
+        map
+    };
+    if !merge {
+        return Ok(create_map());
+    }
+    match load_data::<BTreeMap<Value, Value>>(fname) {
+        Ok(Some(v)) => {
+            let version_key: Value = "version".into();
+            if cloud_deploy && !v.contains_key(&version_key) {
+                Err(Error::invalid_data(
+                    "Attempt to export as cloud deploy into resource file",
+                ))
+            } else if !cloud_deploy && v.contains_key(&version_key) {
+                Err(Error::invalid_data(
+                    "Attempt to export as resources into cloud deploy file",
+                ))
+            } else {
+                Ok(v)
+            }
+        }
+        Ok(None) => Ok(create_map()),
+        Err(e) => Err(Error::invalid_data(format!("Output file parse error: {e}"))),
+    }
+}
+
+pub fn find_export_entry<'a>(
+    data: &'a mut BTreeMap<Value, Value>,
+    key: &str,
+    cloud_deploy: bool,
+    cloud_node: Option<&str>,
+) -> EResult<&'a mut Vec<Value>> {
+    macro_rules! process_entry {
+        ($map: expr) => {{
+            let entry = $map
+                .entry(Value::String(key.to_owned()))
+                .or_insert_with(|| Value::Seq(Vec::new()));
+            if let Value::Seq(ref mut v) = entry {
+                return Ok(v);
+            }
+            return Err(Error::invalid_data(format!("{} entry is not a seq", key)));
+        }};
+    }
+    if cloud_deploy {
+        let cloud_node = cloud_node.ok_or_else(|| Error::invalid_params("node not specified"))?;
+        let content: &mut Value = data
+            .entry(Value::String("content".to_owned()))
+            .or_insert_with(|| Value::Seq(Vec::new()));
+        if let Value::Seq(ref mut c) = content {
+            let node_key = Value::String("node".to_owned());
+            let pos = if let Some(pos) = c.iter().position(|el| {
+                if let Value::Map(ref map) = el {
+                    map.get(&node_key)
+                        .map_or(false, |node| node.to_string() == cloud_node)
+                } else {
+                    false
+                }
+            }) {
+                pos
+            } else {
+                let mut deploy_map = BTreeMap::new();
+                deploy_map.insert(node_key, Value::String(cloud_node.to_owned()));
+                c.push(Value::Map(deploy_map));
+                c.len() - 1
+            };
+            if let Value::Map(ref mut map) = c[pos] {
+                process_entry!(map);
+            }
+            Err(Error::invalid_data("content entry is not a map"))
+        } else {
+            Err(Error::invalid_data("Section content is not a seq"))
+        }
+    } else {
+        process_entry!(data);
+    }
+}
+
+/// Puts the service params into the export section, replacing the existing entry if found
+pub fn export_svc(svcs: &mut Vec<Value>, id: &str, svc_config: Value) {
+    let id_key = Value::String("id".to_owned());
+    let mut svc_map = None;
+    for svc in &mut *svcs {
+        if let Value::Map(m) = svc {
+            if let Some(svc_id) = m.get(&id_key) {
+                if svc_id.to_string() == id {
+                    svc_map = Some(m);
+                    break;
+                }
+            }
+        }
+    }
+    if let Some(map) = svc_map {
+        map.insert("params".into(), svc_config);
+    } else {
+        svcs.push(svc_entry(id, svc_config));
+    }
+}
+
+/// Service entry in the resource file format
+pub fn svc_entry(id: &str, svc_config: Value) -> Value {
+    let mut map: BTreeMap<Value, Value> = BTreeMap::new();
+    map.insert("id".into(), Value::String(id.to_owned()));
+    map.insert("params".into(), svc_config);
+    Value::Map(map)
+}
+
+/// Puts the item config into the export section, replacing the existing entry if found
+pub fn export_item(items: &mut Vec<Value>, oid: &str, item_config: Value) {
+    let oid_key = Value::String("oid".to_owned());
+    items.retain(|item| {
+        if let Value::Map(m) = item {
+            if let Some(item_oid) = m.get(&oid_key) {
+                item_oid.to_string() != oid
+            } else {
+                true
+            }
+        } else {
+            true
+        }
+    });
+    items.push(item_config);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_diff_numbers() {
+        let current = yaml("oid: sensor:t\nlogic:\n  range:\n    min: 5.0\nvalues: [1.0, 2]");
+        let new = yaml("oid: sensor:t\nlogic:\n  range:\n    min: 5\nvalues: [1, 2.0]");
+        assert!(diff_values(&current, &new).is_empty());
+        let new = yaml("oid: sensor:t\nlogic:\n  range:\n    min: 5.5\nvalues: [1, 2]");
+        let diff = diff_values(&current, &new);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].path, "logic.range.min");
+        assert_eq!(diff[0].new.as_deref(), Some("5.5"));
+    }
+
+    #[test]
+    fn test_import_status() {
+        let current = yaml("oid: sensor:t\nenabled: true\ntimeout: 5.0");
+        let entry = ImportEntry::new(
+            ResourceKind::Items,
+            yaml("oid: sensor:t\nenabled: true\ntimeout: 5"),
+            Some(current.clone()),
+        )
+        .unwrap();
+        assert!(entry.status == ImportStatus::Identical);
+        let entry = ImportEntry::new(
+            ResourceKind::Items,
+            yaml("oid: sensor:t\nenabled: false\ntimeout: 5"),
+            Some(current),
+        )
+        .unwrap();
+        assert!(entry.status == ImportStatus::Changed);
+        assert_eq!(entry.diff[0].path, "enabled");
+        let entry = ImportEntry::new(ResourceKind::Items, yaml("oid: sensor:t"), None).unwrap();
+        assert!(entry.status == ImportStatus::New);
+    }
+}
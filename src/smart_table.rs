@@ -150,6 +150,34 @@ impl<'a> From<&'a Value> for FormattedValue<'a> {
     }
 }
 
+// sets value of an existing table item
+pub unsafe fn set_item_value(item: &QTableWidgetItem, val: &FormattedValue) {
+    macro_rules! set_data {
+        ($data: expr) => {
+            item.set_data(0, &$data)
+        };
+    }
+    match val.value {
+        Value::Bool(v) => {
+            set_data!(QVariant::from_bool(*v));
+        }
+        Value::U8(v) => set_data!(QVariant::from_uint(u32::from(*v))),
+        Value::I8(v) => set_data!(QVariant::from_int(i32::from(*v))),
+        Value::U16(v) => set_data!(QVariant::from_uint(u32::from(*v))),
+        Value::I16(v) => set_data!(QVariant::from_int(i32::from(*v))),
+        Value::U32(v) => set_data!(QVariant::from_uint(*v)),
+        Value::I32(v) => set_data!(QVariant::from_int(*v)),
+        Value::U64(v) => set_data!(QVariant::from_u64(*v)),
+        Value::I64(v) => set_data!(QVariant::from_i64(*v)),
+        Value::F32(v) => set_data!(QVariant::from_float(*v)),
+        Value::F64(v) => set_data!(QVariant::from_double(*v)),
+        s => item.set_text(&qs(s.to_string())),
+    };
+    if let Some(brush) = val.color.brush() {
+        item.set_foreground(&brush);
+    }
+}
+
 pub struct Table<'a> {
     cols: Vec<SmartCol<'a>>,
     data: Vec<Vec<FormattedValue<'a>>>,
@@ -190,30 +218,7 @@ impl<'a> Table<'a> {
                     if col_n <= max {
                         let item = QTableWidgetItem::new();
                         if let Some(val) = row.get(col_n) {
-                            macro_rules! set_data {
-                                ($data: expr) => {
-                                    item.set_data(0, &$data)
-                                };
-                            }
-                            match val.value {
-                                Value::Bool(v) => {
-                                    set_data!(QVariant::from_bool(*v));
-                                }
-                                Value::U8(v) => set_data!(QVariant::from_uint(u32::from(*v))),
-                                Value::I8(v) => set_data!(QVariant::from_int(i32::from(*v))),
-                                Value::U16(v) => set_data!(QVariant::from_uint(u32::from(*v))),
-                                Value::I16(v) => set_data!(QVariant::from_int(i32::from(*v))),
-                                Value::U32(v) => set_data!(QVariant::from_uint(*v)),
-                                Value::I32(v) => set_data!(QVariant::from_int(*v)),
-                                Value::U64(v) => set_data!(QVariant::from_u64(*v)),
-                                Value::I64(v) => set_data!(QVariant::from_i64(*v)),
-                                Value::F32(v) => set_data!(QVariant::from_float(*v)),
-                                Value::F64(v) => set_data!(QVariant::from_double(*v)),
-                                s => item.set_text(&qs(s.to_string())),
-                            };
-                            if let Some(brush) = val.color.brush() {
-                                item.set_foreground(&brush);
-                            }
+                            set_item_value(&item, val);
                         }
                         table.set_item(row_n as c_int, col_n as c_int, &item);
                         items.push(Item { _item: item });
@@ -0,0 +1,188 @@
+use crate::common::ServiceParams;
+use crate::vars::{self, SecretPath, Vars};
+use eva_common::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+const TEMPLATE_EXT: &str = "yml";
+
+/// Templates shipped with the application, user templates with the same names override them
+const BUILTIN: &[(&str, &str)] = &[
+    ("db-sql", include_str!("../templates/db-sql.yml")),
+    ("modbus", include_str!("../templates/modbus.yml")),
+    ("opcua", include_str!("../templates/opcua.yml")),
+    ("virtual", include_str!("../templates/virtual.yml")),
+];
+
+/// Service params preset, ${VAR} placeholders are filled in when a service is created
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Template {
+    #[serde(skip)]
+    pub name: String,
+    #[serde(skip)]
+    pub builtin: bool,
+    #[serde(default)]
+    pub description: String,
+    /// Suggested service id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Default placeholder values
+    #[serde(default, skip_serializing_if = "Vars::is_empty")]
+    pub defaults: Vars,
+    pub params: Value,
+}
+
+impl Template {
+    fn parse(name: &str, buf: &str, builtin: bool) -> EResult<Self> {
+        let mut tpl: Template = serde_yaml::from_str(buf)
+            .map_err(|e| Error::invalid_data(format!("template {}: {}", name, e)))?;
+        tpl.name = name.to_owned();
+        tpl.builtin = builtin;
+        Ok(tpl)
+    }
+    pub fn info(&self) -> String {
+        let mut info = self.name.clone();
+        if !self.description.is_empty() {
+            info = format!("{} - {}", info, self.description);
+        }
+        if self.builtin {
+            info += " (built-in)";
+        }
+        info
+    }
+    /// Placeholder names, sorted
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        vars::placeholders(&self.params, &mut names);
+        names.into_iter().collect()
+    }
+    /// Fills in the placeholders, all of them must be set
+    pub fn fill(&self, vars: &Vars) -> EResult<ServiceParams> {
+        let mut params = self.params.clone();
+        vars::resolve(vec![&mut params], vars)?;
+        ServiceParams::deserialize(params)
+            .map_err(|e| Error::invalid_data(format!("template {}: {}", self.name, e)))
+    }
+}
+
+fn dir() -> EResult<&'static PathBuf> {
+    crate::TEMPLATES_DIR
+        .as_ref()
+        .ok_or_else(|| Error::not_found("templates directory not available"))
+}
+
+fn path(name: &str) -> EResult<PathBuf> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return Err(Error::invalid_params(format!(
+            "invalid template name: {}",
+            name
+        )));
+    }
+    let mut p = dir()?.clone();
+    p.push(format!("{}.{}", name, TEMPLATE_EXT));
+    Ok(p)
+}
+
+/// Built-in and user templates, sorted by name
+pub fn list() -> EResult<Vec<Template>> {
+    let mut templates = Vec::new();
+    for (name, buf) in BUILTIN {
+        templates.push(Template::parse(name, buf, true)?);
+    }
+    if let Ok(dir) = dir() {
+        if dir.exists() {
+            for entry in std::fs::read_dir(dir)? {
+                let p = entry?.path();
+                if p.extension().map_or(true, |ext| ext != TEMPLATE_EXT) {
+                    continue;
+                }
+                let name = if let Some(name) = p.file_stem() {
+                    name.to_string_lossy().to_string()
+                } else {
+                    continue;
+                };
+                let mut buf = String::new();
+                std::fs::File::open(&p)?.read_to_string(&mut buf)?;
+                let tpl = Template::parse(&name, &buf, false)?;
+                templates.retain(|t| t.name != name);
+                templates.push(tpl);
+            }
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// Is there a user template with the name
+pub fn exists(name: &str) -> EResult<bool> {
+    Ok(path(name)?.exists())
+}
+
+/// Saves the service params as a user template. The values at the placeholder paths are replaced
+/// with ${VAR} placeholders. The current values become the defaults only if store_defaults is
+/// set, as the defaults are written to the template file in plain text. Returns the template file
+/// path and the placeholder names
+pub fn save(
+    name: &str,
+    description: &str,
+    params: &ServiceParams,
+    placeholder_paths: &[SecretPath],
+    store_defaults: bool,
+) -> EResult<(PathBuf, Vec<String>)> {
+    let file = path(name)?;
+    let source = to_value(params).map_err(Error::invalid_data)?;
+    let mut value = source.clone();
+    let names = vars::strip_secrets("", &mut value, placeholder_paths);
+    let mut defaults = Vars::new();
+    if store_defaults {
+        for var in &names {
+            match find_default(&value, &source, var) {
+                Some(Value::String(s)) => {
+                    defaults.insert(var.clone(), s);
+                }
+                Some(v) => {
+                    let s = serde_yaml::to_string(&v).map_err(Error::invalid_data)?;
+                    defaults.insert(var.clone(), s.trim_end().to_owned());
+                }
+                None => {}
+            }
+        }
+    }
+    let tpl = Template {
+        name: name.to_owned(),
+        builtin: false,
+        description: description.to_owned(),
+        id: params.id.clone(),
+        defaults,
+        params: value,
+    };
+    let data = serde_yaml::to_string(&tpl).map_err(Error::invalid_data)?;
+    std::fs::create_dir_all(dir()?)?;
+    let mut f = std::fs::File::create(&file)?;
+    f.write_all(data.as_bytes())?;
+    Ok((file, names))
+}
+
+/// Finds the source value at the position of the placeholder in the stripped value
+fn find_default(stripped: &Value, source: &Value, var: &str) -> Option<Value> {
+    if vars::placeholder_name(stripped) == Some(var) {
+        return Some(source.clone());
+    }
+    if let (Value::Map(s), Value::Map(src)) = (stripped, source) {
+        for (k, v) in s {
+            if let Some(src_v) = src.get(k) {
+                if let Some(found) = find_default(v, src_v, var) {
+                    return Some(found);
+                }
+            }
+        }
+    }
+    None
+}
@@ -1,28 +1,41 @@
+use crate::backup::NodeBackup;
 use crate::common::{
-    copy_from_table, load_yaml, new_size, save_yaml, splitter_sizes, ActionFilter, ActionRecord,
-    Args, Config, ItemConfig, LogFilter, Nit, NitData, NitKind, NodeInfo, SPointInfo,
-    ServiceParams, SvcData, SvcInfo,
+    copy_from_table, new_size, save_data, splitter_sizes, ActionFilter, ActionRecord, Args,
+    BulkResult, Config, ConnId, ConnectionOptions, ConnectionProfile, ItemConfig, ItemInfo,
+    LogFilter, Nit, NitData, NitKind, NodeInfo, NodeRef, SPointInfo, ServiceParams, SvcData,
+    SvcInfo,
 };
+use crate::item_csv;
 use crate::output;
+use crate::resources::{
+    compare, deploy_nit, export_data, export_item, export_svc, find_export_entry,
+    load_cloud_deploy, load_import, load_svc_params, replace_id_prefix, resource_id, svc_entry,
+    ImportEntry, NodeDeploy, ResourceKind,
+};
 use crate::smart_table;
+use crate::templates;
+use crate::undo;
+use crate::vars;
 use crate::{
     bus,
-    forms::{self, ExportKind, QInputX},
+    forms::{self, ExportKind, NonModalInfoDialog, QInputX},
 };
 use arboard::Clipboard;
 use cpp_core::{CppBox, Ptr, Ref, StaticUpcast};
 use eva_common::prelude::*;
 use qt_core::{
-    qs, slot, QBox, QObject, QPoint, QPtr, QSortFilterProxyModel, QString, QTimer, SlotNoArgs,
-    SlotOfDouble, SlotOfQString,
+    qs, slot, ItemDataRole, QBox, QModelIndex, QObject, QPoint, QPtr, QSortFilterProxyModel,
+    QString, QStringList, QTimer, QVariant, SlotNoArgs, SlotOfDouble, SlotOfQModelIndexIntInt,
+    SlotOfQString,
 };
-use qt_gui::{QIcon, QPixmap, QStandardItemModel};
+use qt_gui::{QBrush, QColor, QIcon, QPixmap, QStandardItemModel};
+use qt_widgets::q_line_edit::EchoMode;
 use qt_widgets::{
-    QAction, QApplication, QFileDialog, QMenu, QMessageBox, QTableWidget, QTableWidgetItem,
-    QTableWidgetSelectionRange, QTreeWidget, QTreeWidgetItem,
+    QAction, QApplication, QFileDialog, QInputDialog, QMenu, QMessageBox, QTableWidget,
+    QTableWidgetItem, QTableWidgetSelectionRange, QTreeWidget, QTreeWidgetItem,
 };
 use serde::Deserialize;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::fmt::Write as _;
 use std::os::raw::c_int;
@@ -51,6 +64,50 @@ macro_rules! format_err {
     };
 }
 
+/// Fetches services and items of both nodes and compares them
+fn compare_nodes(left: &NodeRef, right: &NodeRef) -> EResult<Value> {
+    let l = NodeBackup::create(left)?;
+    let r = NodeBackup::create(right)?;
+    let mut entries = compare(ResourceKind::Svcs, l.svcs, r.svcs)?;
+    entries.extend(compare(ResourceKind::Items, l.items, r.items)?);
+    to_value(entries).map_err(Into::into)
+}
+
+/// Compares the configs with the deployed resources
+fn import_entries(
+    node: &NodeRef,
+    kind: ResourceKind,
+    configs: Vec<Value>,
+) -> EResult<Vec<ImportEntry>> {
+    configs
+        .into_iter()
+        .map(|config| {
+            let id = resource_id(kind, &config)?;
+            let nit = match kind {
+                ResourceKind::Svcs => NitData::new_svc_get_params(node, id),
+                ResourceKind::Items => NitData::new_item_get_config(node, id),
+            };
+            let current = match bus::call::<Value>(Arc::new(nit)) {
+                Ok(v) => Some(v),
+                Err(e) if e.kind() == ErrorKind::ResourceNotFound => None,
+                Err(e) => return Err(e),
+            };
+            ImportEntry::new(kind, config, current)
+        })
+        .collect()
+}
+
+fn exported_msg(count: usize, kind: &str, var_names: &[String]) -> String {
+    if var_names.is_empty() {
+        format!("{count} {kind} exported")
+    } else {
+        format!(
+            "{count} {kind} exported, secrets replaced with variables: {}",
+            var_names.join(", ")
+        )
+    }
+}
+
 pub fn set_status(status: impl std::fmt::Display, kind: StatusKind) {
     command(Command::SetStatus(status.to_string(), kind));
 }
@@ -65,14 +122,30 @@ pub fn command(c: Command) {
 
 pub enum Command {
     SetStatus(String, StatusKind),
-    MarkConnected(String, Vec<NodeInfo>),
-    MarkDisconnected,
+    MarkConnected(ConnId, String, Vec<NodeInfo>),
+    MarkDisconnected(ConnId),
     ProcessNit(Nit),
-    ProcessItemWatch(uuid::Uuid, Value),
-    ProcessActionWatch(uuid::Uuid, Value),
-    ProcessSvcCallResult(uuid::Uuid, EResult<Value>),
+    ProcessItemWatch(ConnId, uuid::Uuid, Value),
+    ProcessItemState(ConnId, ItemInfo),
+    ProcessActionWatch(ConnId, uuid::Uuid, Value),
+    ProcessSvcCallResult(ConnId, uuid::Uuid, EResult<Value>),
+    ProcessCallResult(uuid::Uuid, EResult<Value>),
+    ProcessActionResult(uuid::Uuid, EResult<String>),
+    ProcessBulkResult(uuid::Uuid, Nit, EResult<Vec<BulkResult>>),
+    ProcessCloudDeployResult(uuid::Uuid, ConnId, String, EResult<Vec<BulkResult>>),
+    ProcessImportPreview(uuid::Uuid, NodeRef, ResourceKind, EResult<Vec<ImportEntry>>),
+    ProcessCloudDeployPreview(
+        uuid::Uuid,
+        ConnId,
+        String,
+        EResult<Vec<(String, ResourceKind, ImportEntry)>>,
+    ),
+    ProcessNodeCompare(uuid::Uuid, EResult<Value>),
+    ProcessItemBulkEdit(uuid::Uuid, NodeRef, EResult<(Vec<Value>, Vec<SvcData>)>),
 }
 
+type AsyncCallback = Box<dyn FnOnce(&Rc<Ui>, EResult<Value>)>;
+
 struct NodeTreeItem {
     primary: CppBox<QTreeWidgetItem>,
     secondaries: Vec<CppBox<QTreeWidgetItem>>,
@@ -87,11 +160,12 @@ impl Drop for NodeTreeItem {
 impl NodeTreeItem {
     unsafe fn new(
         tree: &QPtr<QTreeWidget>,
+        parent: &CppBox<QTreeWidgetItem>,
         name: &str,
         auto_expand: bool,
         icon: &CppBox<QIcon>,
     ) -> Self {
-        let primary = QTreeWidgetItem::from_q_tree_widget(tree);
+        let primary = QTreeWidgetItem::from_q_tree_widget_item(parent);
         primary.set_text(0, &qs(name));
         primary.set_icon(0, icon);
         if auto_expand {
@@ -111,67 +185,43 @@ impl NodeTreeItem {
     }
 }
 
-fn find_export_entry<'a>(
-    data: &'a mut BTreeMap<Value, Value>,
-    key: &str,
-    cloud_deploy: bool,
-    cloud_node: Option<&str>,
-) -> EResult<&'a mut Vec<Value>> {
-    macro_rules! process_entry {
-        ($map: expr) => {{
-            let entry = $map
-                .entry(Value::String(key.to_owned()))
-                .or_insert_with(|| Value::Seq(Vec::new()));
-            if let Value::Seq(ref mut v) = entry {
-                return Ok(v);
-            }
-            return Err(Error::invalid_data(format!("{} entry is not a seq", key)));
-        }};
-    }
-    if cloud_deploy {
-        let content: &mut Value = data
-            .entry(Value::String("content".to_owned()))
-            .or_insert_with(|| Value::Seq(Vec::new()));
-        if let Value::Seq(ref mut c) = content {
-            let node_key = Value::String("node".to_owned());
-            if let Some(pos) = c.iter().position(|el| {
-                if let Value::Map(ref map) = el {
-                    if let Some(node) = map.get(&node_key) {
-                        &node.to_string() == cloud_node.as_ref().unwrap()
-                        //remutation is safe as the second block is not executed
-                        //process_entry!(unsafe { as_mut(map) });
-                    } else {
-                        false
-                    }
-                    //}
-                    //} else {
-                    //return Err(Error::invalid_data("content entry is not a map"));
-                } else {
-                    false
-                }
-            }) {
-                if let Value::Map(ref mut map) = c[pos] {
-                    process_entry!(map);
-                }
-                panic!();
-            }
-            let mut deploy_map = BTreeMap::new();
-            deploy_map.insert(
-                node_key,
-                Value::String((*cloud_node.as_ref().unwrap()).to_owned()),
-            );
-            // remutation is safe as the first block wasn't been executed cuz the entry hadn't been
-            // found
-            c.push(Value::Map(deploy_map));
-            if let Value::Map(ref mut map) = c.last_mut().unwrap() {
-                process_entry!(map)
-            }
-            panic!();
+/// A top-level tree item of a connection, the connection ID is stored in the item data
+struct CloudTreeItem {
+    root: CppBox<QTreeWidgetItem>,
+    nodes: Vec<NodeTreeItem>,
+    node_names: Vec<String>,
+}
+
+impl Drop for CloudTreeItem {
+    fn drop(&mut self) {
+        self.nodes.clear();
+    }
+}
+
+impl CloudTreeItem {
+    #[allow(clippy::cast_possible_truncation)]
+    unsafe fn new(tree: &QPtr<QTreeWidget>, conn: ConnId, icon: &CppBox<QIcon>) -> Self {
+        let root = QTreeWidgetItem::from_q_tree_widget(tree);
+        root.set_icon(0, icon);
+        root.set_data(
+            0,
+            ItemDataRole::UserRole.into(),
+            &QVariant::from_u64(conn as u64),
+        );
+        Self {
+            root,
+            nodes: Vec::new(),
+            node_names: Vec::new(),
+        }
+    }
+    unsafe fn set_label(&self, label: &str, color: Option<&str>) {
+        self.root.set_text(0, &qs(label));
+        if let Some(color) = color {
+            self.root
+                .set_foreground(0, &QBrush::from_q_color(&QColor::from_q_string(&qs(color))));
         } else {
-            Err(Error::invalid_data("Section content is not a seq"))
+            self.root.set_foreground(0, &QBrush::new());
         }
-    } else {
-        process_entry!(data);
     }
 }
 
@@ -220,26 +270,44 @@ pub struct Ui {
     dialog_unit_action: Rc<forms::DialogUnitAction>,
     dialog_about: Rc<forms::DialogAbout>,
     dialog_export: Rc<forms::DialogExport>,
+    dialog_audit: Rc<forms::DialogAudit>,
     busy: Rc<forms::Busy>,
     _source_model: QBox<QStandardItemModel>,
     _proxy_model: QBox<QSortFilterProxyModel>,
     cleanup_timer: QBox<QTimer>,
+    tree_drop_timer: QBox<QTimer>,
     cmd_rx: mpsc_std::Receiver<Command>,
-    tree_items: Mutex<HashMap<String, NodeTreeItem>>,
+    clouds: Mutex<BTreeMap<ConnId, CloudTreeItem>>,
+    tree_populating: atomic::AtomicBool,
+    tree_drops: Mutex<Vec<(Vec<String>, c_int, c_int)>>,
     pub(crate) primary_table_items: Mutex<Vec<smart_table::Item>>,
     pub(crate) secondary_table_items: Mutex<Vec<smart_table::Item>>,
     auto_reload_timer: Mutex<Option<QBox<QTimer>>>,
     auto_reload_auto_suspended: atomic::AtomicBool,
     title: String,
     config: Mutex<Option<Config>>,
-    action_timer: QBox<QTimer>,
+    conn_profiles: Mutex<HashMap<ConnId, ConnectionProfile>>,
+    node_filter_conn: Mutex<Option<ConnId>>,
+    recent_actions: Mutex<Vec<QPtr<QAction>>>,
+    current_op: Mutex<Option<uuid::Uuid>>,
+    async_calls: Mutex<HashMap<uuid::Uuid, AsyncCallback>>,
+    nit_pending: Mutex<Option<uuid::Uuid>>,
     svc_edit_dialogs: forms::DialogFactory<forms::DialogSvcEdit>,
     item_edit_dialogs: forms::DialogFactory<forms::DialogItemEdit>,
+    import_preview_dialogs: forms::DialogFactory<forms::DialogImportPreview>,
+    copy_dialogs: forms::DialogFactory<forms::DialogCopyResources>,
+    rename_dialogs: forms::DialogFactory<forms::DialogRenameItems>,
+    item_bulk_edit_dialogs: forms::DialogFactory<forms::DialogItemBulkEdit>,
+    item_generator_dialogs: forms::DialogFactory<forms::DialogItemGenerator>,
+    svc_template_dialogs: forms::DialogFactory<forms::DialogSvcTemplate>,
     item_watch_dialogs: forms::InfoDialogFactory<forms::DialogItemWatch>,
     action_watch_dialogs: forms::InfoDialogFactory<forms::DialogActionWatch>,
     svc_call_dialogs: forms::InfoDialogFactory<forms::DialogSvcCall>,
+    bulk_report_dialogs: forms::InfoDialogFactory<forms::DialogBulkReport>,
+    node_compare_dialogs: forms::InfoDialogFactory<forms::DialogNodeCompare>,
     icon_stop: CppBox<QIcon>,
     icon_start: CppBox<QIcon>,
+    icon_cloud: CppBox<QIcon>,
     icon_node: CppBox<QIcon>,
     icon_broker: CppBox<QIcon>,
     icon_items: CppBox<QIcon>,
@@ -271,9 +339,6 @@ impl Ui {
             let cleanup_timer: QBox<QTimer> = QTimer::new_0a();
             let window = forms::Main::load();
             let title = window.widget.window_title().to_std_string();
-            let action_timer = QTimer::new_0a();
-            action_timer.set_interval(100);
-            action_timer.set_single_shot(true);
             let dialog_lvar_set = Rc::new(forms::DialogLvarSet::load());
             dialog_lvar_set.init();
             let dialog_unit_action = Rc::new(forms::DialogUnitAction::load());
@@ -284,26 +349,44 @@ impl Ui {
                 dialog_unit_action,
                 dialog_about: Rc::new(forms::DialogAbout::load()),
                 dialog_export: Rc::new(forms::DialogExport::load()),
+                dialog_audit: Rc::new(forms::DialogAudit::new()),
                 busy: Rc::new(forms::Busy::load()),
                 _source_model: QStandardItemModel::new_0a(),
                 _proxy_model: QSortFilterProxyModel::new_0a(),
                 cleanup_timer,
+                tree_drop_timer: QTimer::new_0a(),
                 cmd_rx,
-                tree_items: <_>::default(),
+                clouds: <_>::default(),
+                tree_populating: <_>::default(),
+                tree_drops: <_>::default(),
                 primary_table_items: <_>::default(),
                 secondary_table_items: <_>::default(),
                 auto_reload_timer: <_>::default(),
                 auto_reload_auto_suspended: <_>::default(),
                 title,
                 config: <_>::default(),
-                action_timer,
+                conn_profiles: <_>::default(),
+                node_filter_conn: <_>::default(),
+                recent_actions: <_>::default(),
+                current_op: <_>::default(),
+                async_calls: <_>::default(),
+                nit_pending: <_>::default(),
                 svc_edit_dialogs: <_>::default(),
                 item_edit_dialogs: <_>::default(),
+                import_preview_dialogs: <_>::default(),
+                copy_dialogs: <_>::default(),
+                rename_dialogs: <_>::default(),
+                item_bulk_edit_dialogs: <_>::default(),
+                item_generator_dialogs: <_>::default(),
+                svc_template_dialogs: <_>::default(),
                 item_watch_dialogs: <_>::default(),
                 action_watch_dialogs: <_>::default(),
                 svc_call_dialogs: <_>::default(),
+                bulk_report_dialogs: <_>::default(),
+                node_compare_dialogs: <_>::default(),
                 icon_stop: qicon("stop"),
                 icon_start: qicon("start"),
+                icon_cloud: qicon("connect"),
                 icon_node: qicon("node"),
                 icon_broker: qicon("broker"),
                 icon_items: qicon("items"),
@@ -315,6 +398,7 @@ impl Ui {
             });
             this.init(cmd_tx);
             this.dialog_export.init(&this.slot_on_export_clicked());
+            this.dialog_audit.init();
             this.cleanup_timer.timeout().connect(&this.slot_cleanup());
             this.cleanup_timer
                 .start_1a(crate::UI_CLEANUP_INTERVAL.as_millis().try_into().unwrap());
@@ -322,27 +406,42 @@ impl Ui {
             this
         }
     }
-    unsafe fn ui_action<F>(self: &Rc<Self>, mut action: F)
+    /// Runs a blocking action in a separate thread, the busy dialog is shown until the action is
+    /// completed or cancelled
+    unsafe fn ui_action<F>(self: &Rc<Self>, action: F)
     where
-        F: FnMut() -> EResult<String> + 'static,
+        F: FnOnce() -> EResult<String> + Send + 'static,
     {
-        let this = self.clone();
-        self.action_timer.disconnect();
-        self.action_timer
-            .timeout()
-            .connect(&SlotNoArgs::new(
-                &self.window.widget,
-                move || match action() {
-                    Ok(result) => {
-                        this.busy.mark_completed(&result);
-                    }
-                    Err(e) => {
-                        this.busy.mark_failed(&e.to_string());
-                    }
-                },
-            ));
+        let u = uuid::Uuid::new_v4();
+        self.current_op.lock().unwrap().replace(u);
         self.busy();
-        self.action_timer.start_0a();
+        std::thread::spawn(move || {
+            let result = bus::run_op(u, action);
+            command(Command::ProcessActionResult(u, result));
+        });
+    }
+    #[slot(SlotNoArgs)]
+    unsafe fn on_busy_cancel(self: &Rc<Self>) {
+        if let Some(u) = self.current_op.lock().unwrap().take() {
+            bus::cancel(u);
+        }
+        self.busy.mark_failed(bus::ERR_CANCELLED);
+    }
+    /// Calls the bus without blocking the UI, the callback is executed in the UI thread when the
+    /// result is received
+    unsafe fn call_async<F>(self: &Rc<Self>, nit: Nit, f: F) -> uuid::Uuid
+    where
+        F: FnOnce(&Rc<Self>, EResult<Value>) + 'static,
+    {
+        let u = uuid::Uuid::new_v4();
+        self.async_calls.lock().unwrap().insert(u, Box::new(f));
+        if let Err(e) = bus::call_async(nit, u) {
+            let cb = self.async_calls.lock().unwrap().remove(&u);
+            if let Some(cb) = cb {
+                cb(self, Err(e));
+            }
+        }
+        u
     }
     #[slot(SlotNoArgs)]
     unsafe fn handle_cmd(self: &Rc<Self>) {
@@ -350,6 +449,7 @@ impl Ui {
         if self.window.widget.is_visible() {
             if let Some(config) = self.config.lock().unwrap().as_mut() {
                 config.set_auto_reload(self.window.auto_reload.value());
+                config.set_continue_on_error(self.window.action_continue_on_error.is_checked());
                 let size = self.window.widget.size();
                 config.set_main_window_size(size.width(), size.height());
                 if let Some((x, y)) = splitter_sizes(&self.window.splitter_workspace) {
@@ -371,23 +471,35 @@ impl Ui {
         //
         while let Ok(cmd) = self.cmd_rx.try_recv() {
             match cmd {
-                Command::ProcessItemWatch(u, data) => {
+                Command::ProcessItemWatch(conn, u, data) => {
                     if !self.window.widget.is_visible()
                         || !self.item_watch_dialogs.push(u, Ok(data))
                     {
-                        let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(u)));
+                        let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(conn, u)));
+                    }
+                }
+                Command::ProcessItemState(conn, state) => {
+                    let is_item_list = crate::LAST_NIT
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .map_or(false, |nit| {
+                            nit.conn() == conn && matches!(nit.kind(), NitKind::Items(_, _))
+                        });
+                    if is_item_list {
+                        output::update_item_state(self, &state);
                     }
                 }
-                Command::ProcessSvcCallResult(u, data) => {
+                Command::ProcessSvcCallResult(conn, u, data) => {
                     if !self.window.widget.is_visible() || !self.svc_call_dialogs.push(u, data) {
-                        let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(u)));
+                        let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(conn, u)));
                     }
                 }
-                Command::ProcessActionWatch(u, data) => {
+                Command::ProcessActionWatch(conn, u, data) => {
                     if !self.window.widget.is_visible()
                         || !self.action_watch_dialogs.push(u, Ok(data))
                     {
-                        let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(u)));
+                        let _r = bus::call::<()>(Arc::new(NitData::stop_watcher(conn, u)));
                     }
                 }
                 Command::SetStatus(v, kind) => match kind {
@@ -398,20 +510,37 @@ impl Ui {
                         self.window.set_status(&format_err!(v));
                     }
                 },
-                Command::MarkConnected(path, node_list) => {
+                Command::MarkConnected(conn, path, node_list) => {
                     self.window.set_status(&format!("Connected: {}", path));
-                    self.window
-                        .widget
-                        .set_window_title(&qs(format!("{} - {}", path, self.title)));
-                    self.clear_workspace();
-                    self.window.i_node.clear();
-                    self.window.i_node.add_item_q_string(&qs("*"));
-                    let mut tree_items = self.tree_items.lock().unwrap();
-                    let mut first = true;
+                    // keep the current view if the connection has been re-established
+                    let current_conn = self.current_conn();
+                    let prev_path = self.current_tree_path();
+                    // the tree is modified outside of the lock as Qt may fire selection signals
+                    self.tree_populating.store(true, atomic::Ordering::SeqCst);
+                    let existing = self.clouds.lock().unwrap().remove(&conn);
+                    let is_new = existing.is_none();
+                    let mut cloud = existing.unwrap_or_else(|| {
+                        CloudTreeItem::new(&self.window.main_tree, conn, &self.icon_cloud)
+                    });
+                    if let Some(profile) = self.conn_profiles.lock().unwrap().get(&conn) {
+                        cloud.set_label(
+                            &format!("{} ({})", profile.name, path),
+                            profile.color.as_deref(),
+                        );
+                    } else {
+                        cloud.set_label(&path, None);
+                    }
+                    if current_conn == Some(conn) {
+                        self.window.set_nit_status("");
+                        self.clear_tables();
+                    }
+                    cloud.nodes.clear();
+                    cloud.node_names.clear();
+                    let mut first = is_new;
                     for node in node_list {
-                        self.window.i_node.add_item_q_string(&qs(&node.name));
                         let mut item = NodeTreeItem::new(
                             &self.window.main_tree,
+                            &cloud.root,
                             &node.name,
                             first,
                             &self.icon_node,
@@ -422,23 +551,168 @@ impl Ui {
                         item.add("log", &self.icon_log);
                         item.add("services", &self.icon_services);
                         item.add("spoints", &self.icon_spoints);
-                        tree_items.insert(node.name, item);
+                        cloud.nodes.push(item);
+                        cloud.node_names.push(node.name);
                         first = false;
                     }
+                    if is_new {
+                        self.window.main_tree.set_item_expanded(&cloud.root, true);
+                    }
+                    self.clouds.lock().unwrap().insert(conn, cloud);
+                    self.tree_populating.store(false, atomic::Ordering::SeqCst);
+                    if current_conn == Some(conn) {
+                        self.select_tree_path(&prev_path);
+                    }
+                    if self.current_conn() == Some(conn) {
+                        self.node_filter_conn.lock().unwrap().take();
+                        self.set_node_filter_conn(conn);
+                    }
+                    self.update_conn_status();
                     self.refire_auto_reload();
                 }
-                Command::MarkDisconnected => {
-                    self.auto_reload_timer.lock().unwrap().take();
-                    self.clear_workspace();
-                    self.window.set_status("Disconnected");
-                    self.window.set_nit_status("");
-                    self.window.widget.set_window_title(&qs(&self.title));
-                    self.item_watch_dialogs.close_all();
-                    self.action_watch_dialogs.close_all();
+                Command::MarkDisconnected(conn) => {
+                    let current_conn = self.current_conn();
+                    let cloud = self.clouds.lock().unwrap().remove(&conn);
+                    if let Some(cloud) = cloud {
+                        self.window.set_status(&format!(
+                            "Disconnected: {}",
+                            cloud.root.text(0).to_std_string()
+                        ));
+                    }
+                    self.conn_profiles.lock().unwrap().remove(&conn);
+                    if current_conn == Some(conn) || crate::LAST_NIT.lock().unwrap().is_none() {
+                        self.nit_pending.lock().unwrap().take();
+                        self.clear_tables();
+                        self.window.set_nit_status("");
+                    }
+                    if self.clouds.lock().unwrap().is_empty() {
+                        self.auto_reload_timer.lock().unwrap().take();
+                    }
+                    self.update_conn_status();
+                    self.item_watch_dialogs.close_conn(conn);
+                    self.action_watch_dialogs.close_conn(conn);
+                    self.svc_call_dialogs.close_conn(conn);
+                    self.bulk_report_dialogs.close_conn(conn);
+                    self.node_compare_dialogs.close_conn(conn);
+                    self.import_preview_dialogs.close_conn(conn);
+                    self.copy_dialogs.close_conn(conn);
+                    self.rename_dialogs.close_conn(conn);
+                    self.item_bulk_edit_dialogs.close_conn(conn);
+                    self.item_generator_dialogs.close_conn(conn);
+                    self.svc_template_dialogs.close_conn(conn);
                 }
                 Command::ProcessNit(nit) => {
                     self.process_nit(nit);
                 }
+                Command::ProcessCallResult(u, result) => {
+                    let cb = self.async_calls.lock().unwrap().remove(&u);
+                    if let Some(cb) = cb {
+                        cb(self, result);
+                    }
+                }
+                Command::ProcessActionResult(u, result) => {
+                    let mut current_op = self.current_op.lock().unwrap();
+                    if current_op.map_or(false, |op| op == u) {
+                        current_op.take();
+                        drop(current_op);
+                        match result {
+                            Ok(v) => self.busy.mark_completed(&v),
+                            Err(e) => self.busy.mark_failed(&e.to_string()),
+                        }
+                    }
+                }
+                Command::ProcessBulkResult(u, nit, result) => {
+                    if let Some(report) = self.finish_bulk_op(u, result) {
+                        self.bulk_report(nit, report);
+                    }
+                }
+                Command::ProcessNodeCompare(u, data) => {
+                    self.node_compare_dialogs.push(u, data);
+                }
+                Command::ProcessItemBulkEdit(u, node, result) => {
+                    if self.take_op(u) {
+                        match result {
+                            Ok((configs, services)) => {
+                                self.busy.close();
+                                self.show_item_bulk_edit(&node, configs, services);
+                            }
+                            Err(e) => self.busy.mark_failed(&e.to_string()),
+                        }
+                    }
+                }
+                Command::ProcessImportPreview(u, node, kind, result) => {
+                    if self.take_op(u) {
+                        match result {
+                            Ok(entries) => {
+                                self.busy.close();
+                                self.show_import_preview(&node, kind, entries);
+                            }
+                            Err(e) => self.busy.mark_failed(&e.to_string()),
+                        }
+                    }
+                }
+                Command::ProcessCloudDeployPreview(u, conn, fname, result) => {
+                    if self.take_op(u) {
+                        match result {
+                            Ok(entries) => {
+                                self.busy.close();
+                                self.show_cloud_deploy_preview(conn, fname, entries);
+                            }
+                            Err(e) => self.busy.mark_failed(&e.to_string()),
+                        }
+                    }
+                }
+                Command::ProcessCloudDeployResult(u, conn, fname, result) => {
+                    if let Some(report) = self.finish_bulk_op(u, result) {
+                        self.show_report(&format!("cloud deploy {}", fname), conn, report);
+                    }
+                }
+            }
+        }
+    }
+    /// Clears the current operation, returns false if the operation is no longer current (e.g.
+    /// cancelled)
+    fn take_op(&self, u: uuid::Uuid) -> bool {
+        let mut current_op = self.current_op.lock().unwrap();
+        if current_op.map_or(false, |op| op == u) {
+            current_op.take();
+            true
+        } else {
+            false
+        }
+    }
+    /// Shows a bulk report which can not be retried
+    unsafe fn show_report(&self, title: &str, conn: ConnId, report: Vec<BulkResult>) {
+        let dialog = Rc::new(forms::DialogBulkReport::with_title(title));
+        dialog.init(&SlotNoArgs::new(dialog.widget(), || {}));
+        dialog.set_report(report);
+        self.bulk_report_dialogs.register(dialog.clone(), conn);
+        dialog.show();
+    }
+    /// Marks the busy dialog with the bulk operation summary, returns the report if the operation
+    /// is still current
+    unsafe fn finish_bulk_op(
+        &self,
+        u: uuid::Uuid,
+        result: EResult<Vec<BulkResult>>,
+    ) -> Option<Vec<BulkResult>> {
+        if !self.take_op(u) {
+            return None;
+        }
+        match result {
+            Ok(report) => {
+                let failed = report.iter().filter(|r| !r.is_ok()).count();
+                let memo = format!("{} succeeded, {} failed", report.len() - failed, failed);
+                if failed == 0 {
+                    self.busy.mark_completed(&memo);
+                } else {
+                    self.busy.mark_failed(&memo);
+                }
+                Some(report)
+            }
+            Err(e) => {
+                self.busy.mark_failed(&e.to_string());
+                None
             }
         }
     }
@@ -446,7 +720,8 @@ impl Ui {
         self.busy.show();
     }
     unsafe fn clear_workspace(&self) {
-        self.tree_items.lock().unwrap().clear();
+        let clouds = std::mem::take(&mut *self.clouds.lock().unwrap());
+        drop(clouds);
         self.primary_table_items.lock().unwrap().clear();
         self.secondary_table_items.lock().unwrap().clear();
         self.window.clear_workspace();
@@ -483,7 +758,8 @@ impl Ui {
             .unwrap();
         self.clear_workspace();
         self.dialog_about.init();
-        self.busy.init();
+        self.dialog_connect.init();
+        self.busy.init(&self.slot_on_busy_cancel());
         #[cfg(debug_assertions)]
         {
             self.window.i_oid.set_text(&qs("*"));
@@ -500,6 +776,18 @@ impl Ui {
             .main_tree
             .custom_context_menu_requested()
             .connect(&self.slot_on_tree_ctx());
+        // table rows dropped on the tree are inserted by Qt as tree items, which are removed and
+        // processed as copy requests as soon as the drop is completed
+        self.window
+            .main_tree
+            .model()
+            .rows_inserted()
+            .connect(&self.slot_on_tree_rows_inserted());
+        self.tree_drop_timer.set_single_shot(true);
+        self.tree_drop_timer.set_interval(0);
+        self.tree_drop_timer
+            .timeout()
+            .connect(&self.slot_on_tree_drop());
         for table in [&self.window.primary_table, &self.window.secondary_table] {
             table
                 .item_selection_changed()
@@ -536,6 +824,14 @@ impl Ui {
             .triggered()
             .connect(&self.slot_on_action_disconnect());
         self.window.action_reload.triggered().connect(&slot_reload);
+        self.window
+            .action_audit
+            .triggered()
+            .connect(&self.slot_on_audit());
+        self.window
+            .action_read_only
+            .triggered()
+            .connect(&self.slot_on_read_only_toggled());
         self.window
             .action_exit
             .triggered()
@@ -570,10 +866,34 @@ impl Ui {
             .proto
             .activated2()
             .connect(&self.slot_on_proto_selected());
+        self.dialog_connect
+            .profile
+            .activated2()
+            .connect(&self.slot_on_profile_selected());
+        self.dialog_connect
+            .btn_profile_save
+            .clicked()
+            .connect(&self.slot_on_profile_save());
+        self.dialog_connect
+            .btn_profile_delete
+            .clicked()
+            .connect(&self.slot_on_profile_delete());
         self.window
             .action_add_resource
             .triggered()
             .connect(&self.slot_on_add_resource());
+        self.window
+            .action_add_svc_template
+            .triggered()
+            .connect(&self.slot_on_add_svc_template());
+        self.window
+            .action_generate_items
+            .triggered()
+            .connect(&self.slot_on_generate_items());
+        self.window
+            .action_undo
+            .triggered()
+            .connect(&self.slot_on_undo());
         self.window
             .action_edit_resource
             .triggered()
@@ -607,7 +927,11 @@ impl Ui {
             "Please select a resource in the primary table",
         );
     }
-    unsafe fn svc_deploy(self: &Rc<Self>, dialog: Rc<forms::DialogSvcEdit>, node: &str) -> bool {
+    unsafe fn svc_deploy(
+        self: &Rc<Self>,
+        dialog: Rc<forms::DialogSvcEdit>,
+        node: &NodeRef,
+    ) -> bool {
         match dialog.parse_params() {
             Ok(params) => {
                 let nit = Arc::new(NitData::new_svc_deploy(node, params));
@@ -625,7 +949,11 @@ impl Ui {
             }
         }
     }
-    unsafe fn item_deploy(self: &Rc<Self>, dialog: Rc<forms::DialogItemEdit>, node: &str) -> bool {
+    unsafe fn item_deploy(
+        self: &Rc<Self>,
+        dialog: Rc<forms::DialogItemEdit>,
+        node: &NodeRef,
+    ) -> bool {
         match dialog.parse_config() {
             Ok(config) => {
                 let nit = Arc::new(NitData::new_item_deploy(node, config));
@@ -643,7 +971,7 @@ impl Ui {
             }
         }
     }
-    unsafe fn item_edit(self: &Rc<Self>, node: &str, oid: String) {
+    unsafe fn item_edit(self: &Rc<Self>, node: &NodeRef, oid: String) {
         match bus::call::<Value>(Arc::new(NitData::new_item_get_config_x(node, oid))) {
             Ok(val) => {
                 if let Value::Seq(seq) = val {
@@ -668,9 +996,69 @@ impl Ui {
             Err(e) => self.error("Failed to get service params", e),
         }
     }
-    unsafe fn action_watch(self: &Rc<Self>, node: &str, action_uuid: uuid::Uuid) {
+    /// Fetches configs of the items and the node services for the bulk editor
+    unsafe fn item_bulk_edit(self: &Rc<Self>, node: NodeRef, oids: Vec<String>) {
+        let u = uuid::Uuid::new_v4();
+        self.current_op.lock().unwrap().replace(u);
+        self.busy();
+        std::thread::spawn(move || {
+            let result = bus::run_op(u, || {
+                let configs = oids
+                    .into_iter()
+                    .map(|oid| bus::call(Arc::new(NitData::new_item_get_config(&node, oid))))
+                    .collect::<EResult<Vec<Value>>>()?;
+                let services = bus::call(Arc::new(NitData::new_services(&node)))?;
+                Ok((configs, services))
+            });
+            command(Command::ProcessItemBulkEdit(u, node, result));
+        });
+    }
+    unsafe fn show_item_bulk_edit(
+        self: &Rc<Self>,
+        node: &NodeRef,
+        configs: Vec<Value>,
+        services: Vec<SvcData>,
+    ) {
+        let dialog = Rc::new(forms::DialogItemBulkEdit::new(node, configs, services));
+        dialog.init();
+        let this = self.clone();
+        self.item_bulk_edit_dialogs
+            .register(dialog.clone(), node, move |d, n| {
+                let update = match d.parse_update() {
+                    Ok(v) if v.is_empty() => {
+                        this.error_box(Some("Nothing to change"), "No fields are checked");
+                        return false;
+                    }
+                    Ok(v) => v,
+                    Err(e) => {
+                        this.error_box(Some("Invalid value"), e);
+                        return false;
+                    }
+                };
+                let configs: Vec<Value> = d
+                    .configs()
+                    .iter()
+                    .cloned()
+                    .map(|mut config| {
+                        update.apply(&mut config);
+                        config
+                    })
+                    .collect();
+                let cnt = configs.len();
+                let nit = Arc::new(NitData::new_item_deploy_multi(n, configs));
+                this.ui_action(move || {
+                    bus::call::<()>(nit)?;
+                    Ok(format!("{} {} updated", cnt, KIND_ITEM))
+                });
+                true
+            });
+        dialog.show();
+    }
+    unsafe fn action_watch(self: &Rc<Self>, node: &NodeRef, action_uuid: uuid::Uuid) {
         let dialog = Rc::new(forms::DialogActionWatch::new(node, action_uuid));
-        let u = self.action_watch_dialogs.register(dialog.clone());
+        let u = self
+            .action_watch_dialogs
+            .register(dialog.clone(), node.conn());
         dialog.init(u);
         let _r = bus::call::<()>(Arc::new(NitData::start_action_watcher(
             u,
@@ -680,10 +1068,12 @@ impl Ui {
         )));
         dialog.show();
     }
-    unsafe fn item_watch(self: &Rc<Self>, node: &str, oid_str: String) {
+    unsafe fn item_watch(self: &Rc<Self>, node: &NodeRef, oid_str: String) {
         if let Ok(oid) = oid_str.parse::<OID>() {
             let dialog = Rc::new(forms::DialogItemWatch::new(node, &oid));
-            let u = self.item_watch_dialogs.register(dialog.clone());
+            let u = self
+                .item_watch_dialogs
+                .register(dialog.clone(), node.conn());
             dialog.init(u);
             let _r = bus::call::<()>(Arc::new(NitData::start_item_watcher(
                 u,
@@ -695,27 +1085,103 @@ impl Ui {
         }
     }
     #[slot(SlotNoArgs)]
+    unsafe fn on_generate_items(self: &Rc<Self>) {
+        if let Some(nd) = self.current_nd() {
+            if matches!(nd.kind(), NitKind::Items(_, _)) {
+                let dialog = Rc::new(forms::DialogItemGenerator::new(nd.node()));
+                dialog.init();
+                let this = self.clone();
+                self.item_generator_dialogs
+                    .register(dialog.clone(), nd.node(), move |d, n| {
+                        let configs = match d.generate() {
+                            Ok(v) => v,
+                            Err(e) => {
+                                this.error_box(Some("Invalid pattern or template"), e);
+                                return false;
+                            }
+                        };
+                        let cnt = configs.len();
+                        let nit = Arc::new(NitData::new_item_deploy_multi(n, configs));
+                        this.ui_action(move || {
+                            bus::call::<()>(nit)?;
+                            Ok(format!("{} {} generated", cnt, KIND_ITEM))
+                        });
+                        true
+                    });
+                dialog.show();
+                return;
+            }
+        }
+        self.error_box(
+            Some("Nothing to process"),
+            "Please select the items section of a node",
+        );
+    }
+    /// Opens the service add dialog, optionally filled with the params
+    unsafe fn svc_add(self: &Rc<Self>, node: &NodeRef, params: Option<ServiceParams>) {
+        let nit_sp = Arc::new(NitData::new_spoints(node));
+        if let Ok(spoints) = bus::call::<Vec<SPointInfo>>(nit_sp) {
+            let dialog = Rc::new(forms::DialogSvcEdit::load());
+            dialog.init();
+            let ui_c = self.clone();
+            let dialog_c = dialog.clone();
+            let node_c = node.clone();
+            let slot_load_tpl = SlotNoArgs::new(&dialog.widget, move || {
+                forms::on_svc_btn_load_clicked(&dialog_c, &node_c, &ui_c);
+            });
+            dialog.btn_load_tpl.clicked().connect(&slot_load_tpl);
+            let this = self.clone();
+            self.svc_edit_dialogs
+                .register(dialog.clone(), node, move |d, n| this.svc_deploy(d, n));
+            if let Some(params) = params {
+                dialog.show_add_params(node, params, spoints);
+            } else {
+                dialog.show_add(node, spoints);
+            }
+        } else {
+            self.default_error_box(ERR_LOAD_SPOINTS);
+        }
+    }
+    #[slot(SlotNoArgs)]
+    unsafe fn on_add_svc_template(self: &Rc<Self>) {
+        if let Some(nd) = self.current_nd() {
+            if matches!(nd.kind(), NitKind::Services) {
+                let templates = match templates::list() {
+                    Ok(v) => v,
+                    Err(e) => {
+                        self.error_box(Some("Unable to load templates"), e);
+                        return;
+                    }
+                };
+                let dialog = Rc::new(forms::DialogSvcTemplate::new(nd.node(), templates));
+                dialog.init();
+                let this = self.clone();
+                self.svc_template_dialogs
+                    .register(dialog.clone(), nd.node(), move |d, n| match d.params() {
+                        Ok(params) => {
+                            this.svc_add(n, Some(params));
+                            true
+                        }
+                        Err(e) => {
+                            this.error_box(Some("Template error"), e);
+                            false
+                        }
+                    });
+                dialog.show();
+                return;
+            }
+        }
+        self.error_box(
+            Some("Nothing to process"),
+            "Please select the services section of a node",
+        );
+    }
+    #[slot(SlotNoArgs)]
     unsafe fn on_add_resource(self: &Rc<Self>) {
         if let Some(nd) = self.current_nd() {
             match nd.kind() {
                 NitKind::Services => {
-                    let nit_sp = Arc::new(NitData::new_spoints(nd.node()));
-                    if let Ok(spoints) = bus::call::<Vec<SPointInfo>>(nit_sp) {
-                        let dialog = Rc::new(forms::DialogSvcEdit::load());
-                        let ui_c = self.clone();
-                        let dialog_c = dialog.clone();
-                        let node_c = nd.node().to_owned();
-                        let slot_load_tpl = SlotNoArgs::new(&dialog.widget, move || {
-                            forms::on_svc_btn_load_clicked(&dialog_c, &node_c, &ui_c);
-                        });
-                        dialog.btn_load_tpl.clicked().connect(&slot_load_tpl);
-                        let this = self.clone();
-                        self.svc_edit_dialogs
-                            .register(dialog.clone(), nd.node(), move |d, n| this.svc_deploy(d, n));
-                        dialog.show_add(nd.node(), spoints);
-                    } else {
-                        self.default_error_box(ERR_LOAD_SPOINTS);
-                    }
+                    self.svc_add(nd.node(), None);
                 }
                 NitKind::Items(_, _) => {
                     let nit_sp = Arc::new(NitData::new_services(nd.node()));
@@ -817,64 +1283,57 @@ impl Ui {
         if let Some(nd) = self.current_nd() {
             match nd.kind() {
                 NitKind::Services | NitKind::Items(_, _) => {
+                    let is_items = matches!(nd.kind(), NitKind::Items(_, _));
+                    let filter = if is_items {
+                        format!("{};;{}", forms::RESOURCE_FILTER, forms::CSV_FILTER)
+                    } else {
+                        forms::RESOURCE_FILTER.to_owned()
+                    };
                     let fname = QFileDialog::get_open_file_name_4a(
                         &self.window.widget,
                         &qs(forms::IN_FILE),
                         &qs(forms::get_last_dir()),
-                        &qs(forms::YAML_FILTER),
+                        &qs(filter),
                     )
                     .to_std_string();
                     if fname.is_empty() {
                         return;
                     }
-                    self.ui_action(move || {
-                        let mut data: HashMap<String, Value> =
-                            load_yaml(&fname)?.ok_or_else(|| Error::invalid_data("No input"))?;
-                        if data.contains_key("version") {
-                            return Err(Error::invalid_data(
-                                "cloud deploy files can not be imported",
-                            ));
-                        }
-                        let (nd_import, kind, cnt) = match nd.kind() {
-                            NitKind::Services => {
-                                if let Some(svcs) = data.remove("svcs") {
-                                    if let Value::Seq(seq) = svcs {
-                                        let cnt = seq.len();
-                                        (
-                                            NitData::new_svc_deploy_multi(nd.node(), seq),
-                                            KIND_SVC,
-                                            cnt,
-                                        )
-                                    } else {
-                                        return Err(Error::invalid_data("section is not a seq"));
-                                    }
-                                } else {
-                                    return Err(Error::invalid_data("no svcs section"));
-                                }
-                            }
-                            NitKind::Items(_, _) => {
-                                if let Some(items) = data.remove("items") {
-                                    if let Value::Seq(seq) = items {
-                                        let cnt = seq.len();
-                                        (
-                                            NitData::new_item_deploy_multi(nd.node(), seq),
-                                            KIND_ITEM,
-                                            cnt,
-                                        )
-                                    } else {
-                                        return Err(Error::invalid_data("section is not a seq"));
-                                    }
-                                } else {
-                                    return Err(Error::invalid_data("no items section"));
-                                }
+                    if is_items && fname.to_lowercase().ends_with(".csv") {
+                        self.import_csv(nd.node().clone(), &fname);
+                        return;
+                    }
+                    match load_cloud_deploy(&fname) {
+                        Ok(Some(mut deploy)) => {
+                            if self.resolve_vars(
+                                deploy
+                                    .iter_mut()
+                                    .flat_map(|d| d.svcs.iter_mut().chain(d.items.iter_mut()))
+                                    .collect(),
+                            ) {
+                                self.import_cloud_deploy(nd.conn(), fname, deploy);
                             }
-                            _ => {
-                                return Err(Error::unsupported("import kind"));
+                            return;
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            self.default_error_box(e);
+                            return;
+                        }
+                    }
+                    let kind = if is_items {
+                        ResourceKind::Items
+                    } else {
+                        ResourceKind::Svcs
+                    };
+                    match load_import(kind, &fname) {
+                        Ok(mut configs) => {
+                            if self.resolve_vars(configs.iter_mut().collect()) {
+                                self.import_preview(nd.node().clone(), kind, configs);
                             }
-                        };
-                        bus::call::<()>(Arc::new(nd_import))?;
-                        Ok(format!("{cnt} {kind} imported"))
-                    });
+                        }
+                        Err(e) => self.default_error_box(e),
+                    }
                 }
                 _ => self.no_res(),
             }
@@ -882,6 +1341,244 @@ impl Ui {
             self.no_res();
         }
     }
+    /// Validates CSV rows, the items are imported only if all the rows are valid, otherwise the
+    /// per-row report is shown
+    unsafe fn import_csv(self: &Rc<Self>, node: NodeRef, fname: &str) {
+        let rows = match item_csv::load(fname) {
+            Ok(v) => v,
+            Err(e) => {
+                self.default_error_box(e);
+                return;
+            }
+        };
+        if rows.iter().any(|r| r.config.is_err()) {
+            self.show_report(
+                &format!("CSV import {}", fname),
+                node.conn(),
+                item_csv::CsvRow::report(&rows),
+            );
+            return;
+        }
+        let mut configs: Vec<Value> = rows.into_iter().filter_map(|r| r.config.ok()).collect();
+        if self.resolve_vars(configs.iter_mut().collect()) {
+            self.import_preview(node, ResourceKind::Items, configs);
+        }
+    }
+    /// Resolves ${VAR} placeholders of imported resources. The variables are taken from the
+    /// process environment, an environment file or asked from the user. The values are resolved
+    /// even with no placeholders to unescape "$${". Returns false if the import must be aborted
+    unsafe fn resolve_vars(self: &Rc<Self>, values: Vec<&mut Value>) -> bool {
+        let mut names = BTreeSet::new();
+        for value in &values {
+            vars::placeholders(value, &mut names);
+        }
+        let mut var_values = vars::env_vars(&names);
+        let missing = |var_values: &vars::Vars| -> Vec<String> {
+            names
+                .iter()
+                .filter(|n| !var_values.contains_key(*n))
+                .cloned()
+                .collect()
+        };
+        if !missing(&var_values).is_empty()
+            && QMessageBox::question_q_widget2_q_string(
+                &self.window.widget,
+                &qs("Unresolved variables"),
+                &qs(format!(
+                    "The file has unresolved variables:<br><br>{}<br><br>Load an environment file?",
+                    missing(&var_values).join("<br>")
+                )),
+            )
+            .to_int()
+                == 16384
+        {
+            let fname = QFileDialog::get_open_file_name_4a(
+                &self.window.widget,
+                &qs(forms::IN_FILE),
+                &qs(forms::get_last_dir()),
+                &qs(forms::ENV_FILTER),
+            )
+            .to_std_string();
+            if !fname.is_empty() {
+                forms::set_last_dir(&fname);
+                match vars::load_env(&fname) {
+                    Ok(v) => var_values.extend(v),
+                    Err(e) => {
+                        self.default_error_box(e);
+                        return false;
+                    }
+                }
+            }
+        }
+        for name in missing(&var_values) {
+            let mut ok = false;
+            let value = QInputDialog::get_text_6a(
+                &self.window.widget,
+                &qs("Variable"),
+                &qs(format!("{}:", name)),
+                EchoMode::Normal,
+                &qs(""),
+                &mut ok,
+            )
+            .to_std_string();
+            if !ok {
+                break;
+            }
+            var_values.insert(name, value);
+        }
+        let missing = missing(&var_values);
+        if !missing.is_empty() {
+            self.error_box(
+                Some("Unresolved variables"),
+                format!(
+                    "The import is aborted, the following variables are not set:<br><br>{}",
+                    missing.join("<br>")
+                ),
+            );
+            return false;
+        }
+        if let Err(e) = vars::resolve(values, &var_values) {
+            self.default_error_box(e);
+            return false;
+        }
+        true
+    }
+    /// Fetches the deployed configs of the imported resources to compare them before deploying
+    unsafe fn import_preview(
+        self: &Rc<Self>,
+        node: NodeRef,
+        kind: ResourceKind,
+        configs: Vec<Value>,
+    ) {
+        let u = uuid::Uuid::new_v4();
+        self.current_op.lock().unwrap().replace(u);
+        self.busy();
+        std::thread::spawn(move || {
+            let result = bus::run_op(u, || import_entries(&node, kind, configs));
+            command(Command::ProcessImportPreview(u, node, kind, result));
+        });
+    }
+    unsafe fn show_import_preview(
+        self: &Rc<Self>,
+        node: &NodeRef,
+        kind: ResourceKind,
+        entries: Vec<ImportEntry>,
+    ) {
+        let dialog = Rc::new(forms::DialogImportPreview::new(node, kind, entries));
+        dialog.init();
+        let this = self.clone();
+        self.import_preview_dialogs
+            .register(dialog.clone(), node, move |d, n| {
+                let configs = d.selected();
+                if !configs.is_empty() {
+                    let cnt = configs.len();
+                    let kind = d.kind();
+                    let nit = Arc::new(deploy_nit(n, kind, configs));
+                    this.ui_action(move || {
+                        bus::call::<()>(nit)?;
+                        Ok(format!("{} {} imported", cnt, kind.key()))
+                    });
+                }
+                true
+            });
+        dialog.show();
+    }
+    /// Compares svcs and items of every cloud deploy node section with the deployed ones and
+    /// shows the preview
+    unsafe fn import_cloud_deploy(
+        self: &Rc<Self>,
+        conn: ConnId,
+        fname: String,
+        deploy: Vec<NodeDeploy>,
+    ) {
+        if deploy.is_empty() {
+            self.error_box(
+                Some("No content"),
+                "The cloud deploy file has no node sections",
+            );
+            return;
+        }
+        let u = uuid::Uuid::new_v4();
+        self.current_op.lock().unwrap().replace(u);
+        self.busy();
+        std::thread::spawn(move || {
+            let result = bus::run_op(u, || {
+                let mut result = Vec::new();
+                for d in deploy {
+                    let node = NodeRef::new(conn, &d.node);
+                    for (kind, configs) in
+                        [(ResourceKind::Svcs, d.svcs), (ResourceKind::Items, d.items)]
+                    {
+                        for entry in import_entries(&node, kind, configs)? {
+                            result.push((d.node.clone(), kind, entry));
+                        }
+                    }
+                }
+                Ok(result)
+            });
+            command(Command::ProcessCloudDeployPreview(u, conn, fname, result));
+        });
+    }
+    unsafe fn show_cloud_deploy_preview(
+        self: &Rc<Self>,
+        conn: ConnId,
+        fname: String,
+        entries: Vec<(String, ResourceKind, ImportEntry)>,
+    ) {
+        let dialog = Rc::new(forms::DialogImportPreview::new_cloud(&fname, entries));
+        dialog.init();
+        let this = self.clone();
+        self.import_preview_dialogs.register(
+            dialog.clone(),
+            &NodeRef::new(conn, ""),
+            move |d, _| {
+                let deploy = d.selected_deploy();
+                if !deploy.is_empty() {
+                    this.deploy_cloud(conn, fname.clone(), deploy);
+                }
+                true
+            },
+        );
+        dialog.show();
+    }
+    /// Deploys svcs and items of every cloud deploy node section, nodes are processed one by
+    /// one and the per-node results are reported
+    unsafe fn deploy_cloud(self: &Rc<Self>, conn: ConnId, fname: String, deploy: Vec<NodeDeploy>) {
+        let u = uuid::Uuid::new_v4();
+        self.current_op.lock().unwrap().replace(u);
+        self.busy();
+        std::thread::spawn(move || {
+            let total = deploy.len();
+            let result = bus::run_op(u, || {
+                let mut report = Vec::with_capacity(total);
+                for (i, d) in deploy.into_iter().enumerate() {
+                    set_status(
+                        format!("Deploying {} ({}/{})", d.node, i + 1, total),
+                        StatusKind::Info,
+                    );
+                    let mut error = None;
+                    for nit in d.nits(conn) {
+                        if let Err(e) = bus::call::<()>(Arc::new(nit)) {
+                            error = Some(e.to_string());
+                            break;
+                        }
+                    }
+                    report.push(BulkResult {
+                        id: format!("{} ({})", d.node, d.summary()),
+                        error,
+                    });
+                }
+                Ok(report)
+            });
+            if result.is_ok() {
+                set_status(
+                    format!("Cloud deploy {}: {} node(s) processed", fname, total),
+                    StatusKind::Info,
+                );
+            }
+            command(Command::ProcessCloudDeployResult(u, conn, fname, result));
+        });
+    }
     #[slot(SlotNoArgs)]
     #[allow(clippy::too_many_lines)]
     unsafe fn on_export_clicked(self: &Rc<Self>) {
@@ -897,7 +1594,42 @@ impl Ui {
             if let Some(resources) = table.selected_resources() {
                 let res_count = resources.len();
                 let export_config = self.dialog_export.export_config();
+                let secrets =
+                    match vars::parse_paths(export_config.secrets.as_deref().unwrap_or_default()) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            abort!(Some("Invalid secret paths"), e);
+                        }
+                    };
                 if let Some(fname) = export_config.file {
+                    if export_config.kind == ExportKind::Csv {
+                        if !matches!(nd.kind(), NitKind::Items(_, _)) {
+                            abort!(
+                                Some("Unsupported format"),
+                                "CSV export is available for items only"
+                            );
+                        }
+                        self.ui_action(move || {
+                            let mut var_names = Vec::new();
+                            let configs = resources
+                                .iter()
+                                .map(|oid| {
+                                    let mut config = bus::call::<Value>(Arc::new(
+                                        NitData::new_item_get_config(nd.node(), oid.clone()),
+                                    ))?;
+                                    var_names.extend(vars::strip_secrets(
+                                        oid,
+                                        &mut config,
+                                        &secrets,
+                                    ));
+                                    Ok(config)
+                                })
+                                .collect::<EResult<Vec<Value>>>()?;
+                            item_csv::save(&fname, &configs)?;
+                            Ok(exported_msg(res_count, KIND_ITEM, &var_names))
+                        });
+                        return;
+                    }
                     if export_config.kind == ExportKind::CloudDeploy
                         && export_config.cloud_node.is_none()
                     {
@@ -908,50 +1640,18 @@ impl Ui {
                             )
                         );
                     }
-                    macro_rules! create_map {
-                        () => {{
-                            let mut map = BTreeMap::new();
-                            if export_config.kind == ExportKind::CloudDeploy {
-                                map.insert("version".into(), Value::U8(4));
-                            }
-                            map
-                        }};
-                    }
-                    let mut data: BTreeMap<Value, Value> = if export_config.merge {
-                        match load_yaml(&fname) {
-                            Ok(Some(v)) => {
-                                let version_key: Value = "version".into();
-                                let map: &BTreeMap<Value, Value> = &v;
-                                match export_config.kind {
-                                    ExportKind::Resource => {
-                                        if map.contains_key(&version_key) {
-                                            abort!(None::<&str>,
-                                                "Attempt to export as resources into cloud deploy file");
-                                        }
-                                    }
-                                    ExportKind::CloudDeploy => {
-                                        if !map.contains_key(&version_key) {
-                                            abort!(None::<&str>,
-                                                "Attempt to export as cloud deploy into resource file");
-                                        }
-                                    }
-                                }
-                                v
-                            }
-                            Ok(None) => {
-                                create_map!()
-                            }
-                            Err(e) => {
-                                abort!(
-                                    Some("Output file error"),
-                                    format!("Output file parse error: {e}")
-                                );
-                            }
+                    let mut data = match export_data(
+                        &fname,
+                        export_config.kind == ExportKind::CloudDeploy,
+                        export_config.merge,
+                    ) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            abort!(Some("Output file error"), e);
                         }
-                    } else {
-                        create_map!()
                     };
                     self.ui_action(move || {
+                        let mut var_names = Vec::new();
                         let x_kind = match nd.kind() {
                             NitKind::Services => {
                                 let svcs = find_export_entry(
@@ -965,27 +1665,13 @@ impl Ui {
                                         nd.node(),
                                         res.clone(),
                                     ));
-                                    let svc_config = bus::call::<Value>(nit_cfg)?;
-                                    let id_key = Value::String("id".to_owned());
-                                    let mut svc_map = None;
-                                    for svc in &mut *svcs {
-                                        if let Value::Map(m) = svc {
-                                            if let Some(id) = m.get(&id_key) {
-                                                if &id.to_string() == res {
-                                                    svc_map = Some(m);
-                                                    break;
-                                                }
-                                            }
-                                        }
-                                    }
-                                    if let Some(map) = svc_map {
-                                        map.insert("params".into(), svc_config);
-                                    } else {
-                                        let mut map: BTreeMap<Value, Value> = BTreeMap::new();
-                                        map.insert("id".into(), Value::String(res.clone()));
-                                        map.insert("params".into(), svc_config);
-                                        svcs.push(Value::Map(map));
-                                    }
+                                    let mut svc_config = bus::call::<Value>(nit_cfg)?;
+                                    var_names.extend(vars::strip_secrets(
+                                        res,
+                                        &mut svc_config,
+                                        &secrets,
+                                    ));
+                                    export_svc(svcs, res, svc_config);
                                 }
                                 KIND_SVC
                             }
@@ -1001,20 +1687,13 @@ impl Ui {
                                         nd.node(),
                                         res.clone(),
                                     ));
-                                    let item_config = bus::call::<Value>(nit_cfg)?;
-                                    let oid_key = Value::String("oid".to_owned());
-                                    items.retain(|item| {
-                                        if let Value::Map(m) = item {
-                                            if let Some(oid) = m.get(&oid_key) {
-                                                &oid.to_string() != res
-                                            } else {
-                                                true
-                                            }
-                                        } else {
-                                            true
-                                        }
-                                    });
-                                    items.push(item_config);
+                                    let mut item_config = bus::call::<Value>(nit_cfg)?;
+                                    var_names.extend(vars::strip_secrets(
+                                        res,
+                                        &mut item_config,
+                                        &secrets,
+                                    ));
+                                    export_item(items, res, item_config);
                                 }
                                 KIND_ITEM
                             }
@@ -1022,8 +1701,8 @@ impl Ui {
                                 return Err(Error::not_implemented("export kind"));
                             }
                         };
-                        save_yaml(&fname, &data)?;
-                        Ok(format!("{res_count} {x_kind} exported"))
+                        save_data(&fname, &data, export_config.format)?;
+                        Ok(exported_msg(res_count, x_kind, &var_names))
                     });
                 } else {
                     abort!(Some("Failed"), "Output file not specified");
@@ -1031,37 +1710,394 @@ impl Ui {
             }
         }
     }
-    unsafe fn ctx_nodes(self: &Rc<Self>, node: &str, pos: CppBox<QPoint>) {
-        const CA_SAVE: &str = "node_ca_save";
-        const CA_RESTART: &str = "node_ca_restart";
-        let menu = QMenu::new();
-        let action_save = QAction::new();
-        action_save.set_object_name(&qs(CA_SAVE));
-        action_save.set_text(&qs("&Save"));
-        menu.add_action(&action_save);
-        let action_restart = QAction::new();
-        action_restart.set_object_name(&qs(CA_RESTART));
-        action_restart.set_text(&qs("&Restart"));
-        menu.add_action(&action_restart);
-        let selected = menu.exec_1a_mut(&pos);
-        if selected.is_null() {
+    unsafe fn ctx_nodes(self: &Rc<Self>, node: &NodeRef, pos: CppBox<QPoint>) {
+        const CA_SAVE: &str = "node_ca_save";
+        const CA_RESTART: &str = "node_ca_restart";
+        const CA_BACKUP: &str = "node_ca_backup";
+        const CA_RESTORE: &str = "node_ca_restore";
+        const CA_COMPARE: &str = "node_ca_compare";
+        const CA_UNDO: &str = "node_ca_undo";
+        let menu = QMenu::new();
+        let action_save = QAction::new();
+        action_save.set_object_name(&qs(CA_SAVE));
+        action_save.set_text(&qs("&Save"));
+        menu.add_action(&action_save);
+        let action_restart = QAction::new();
+        action_restart.set_object_name(&qs(CA_RESTART));
+        action_restart.set_text(&qs("&Restart"));
+        menu.add_action(&action_restart);
+        menu.add_separator();
+        let action_backup = QAction::new();
+        action_backup.set_object_name(&qs(CA_BACKUP));
+        action_backup.set_text(&qs("&Backup node..."));
+        menu.add_action(&action_backup);
+        let action_restore = QAction::new();
+        action_restore.set_object_name(&qs(CA_RESTORE));
+        action_restore.set_text(&qs("Res&tore node..."));
+        menu.add_action(&action_restore);
+        let action_compare = QAction::new();
+        action_compare.set_object_name(&qs(CA_COMPARE));
+        action_compare.set_text(&qs("&Compare with..."));
+        menu.add_action(&action_compare);
+        menu.add_separator();
+        let action_undo = QAction::new();
+        action_undo.set_object_name(&qs(CA_UNDO));
+        if let Ok(Some(entry)) = undo::last(node) {
+            action_undo.set_text(&qs(format!("&Undo {}", entry.op)));
+        } else {
+            action_undo.set_text(&qs("&Undo last operation"));
+            action_undo.set_enabled(false);
+        }
+        menu.add_action(&action_undo);
+        if bus::is_read_only(node.conn()) {
+            action_save.set_enabled(false);
+            action_restart.set_enabled(false);
+            action_restore.set_enabled(false);
+            action_undo.set_enabled(false);
+        }
+        let selected = menu.exec_1a_mut(&pos);
+        if selected.is_null() {
+            return;
+        }
+        match selected.object_name().to_std_string().as_str() {
+            CA_SAVE => {
+                self.process_action_nit(Arc::new(NitData::new_save(node)));
+            }
+            CA_RESTART => {
+                if self.confirm(&format!(
+                    r#"The node <b>{}</b> is going to be RESTARTED.<br>
+If this is the node Cloud Manager is connected to, the session will be disconnected."#,
+                    node
+                )) {
+                    self.process_action_nit(Arc::new(NitData::new_restart(node)));
+                }
+            }
+            CA_BACKUP => self.node_backup(node),
+            CA_RESTORE => self.node_restore(node),
+            CA_COMPARE => self.node_compare_pick(node),
+            CA_UNDO => self.node_undo(node),
+            _ => {}
+        }
+    }
+    /// Redeploys the resources of the latest destructive operation on the node
+    unsafe fn node_undo(self: &Rc<Self>, node: &NodeRef) {
+        let entry = match undo::last(node) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                self.error_box(
+                    Some("Nothing to undo"),
+                    format!("No destructive operations recorded for {}", node),
+                );
+                return;
+            }
+            Err(e) => {
+                self.default_error_box(e);
+                return;
+            }
+        };
+        if !self.confirm(&format!(
+            "The following operation will be undone on <b>{}</b>:<br><br>{}",
+            node,
+            entry.info()
+        )) {
+            return;
+        }
+        let node = node.clone();
+        self.ui_action(move || Ok(format!("undone: {}", undo::undo(&node, entry)?)));
+    }
+    #[slot(SlotNoArgs)]
+    unsafe fn on_undo(self: &Rc<Self>) {
+        if let Some(nd) = self.current_nd() {
+            self.node_undo(nd.node());
+        } else {
+            self.no_res();
+        }
+    }
+    unsafe fn item_rename(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
+        let dialog = Rc::new(forms::DialogRenameItems::new(node, oids));
+        dialog.init();
+        let this = self.clone();
+        self.rename_dialogs
+            .register(dialog.clone(), node, move |d, n| {
+                this.item_rename_process(&d, n)
+            });
+        dialog.show();
+    }
+    unsafe fn item_rename_process(
+        self: &Rc<Self>,
+        dialog: &forms::DialogRenameItems,
+        node: &NodeRef,
+    ) -> bool {
+        let rename = match dialog.rename() {
+            Ok(v) if v.is_empty() => {
+                self.error_box(Some("Nothing to rename"), "No items match the OID / mask");
+                return false;
+            }
+            Ok(v) => v,
+            Err(e) => {
+                self.error_box(Some("Invalid OID"), e);
+                return false;
+            }
+        };
+        let node = node.clone();
+        self.ui_action(move || {
+            rename.run(&node)?;
+            Ok(format!("{} {} renamed", rename.pairs().len(), KIND_ITEM))
+        });
+        true
+    }
+    unsafe fn copy_resources(
+        self: &Rc<Self>,
+        source: &NodeRef,
+        kind: ResourceKind,
+        resources: Vec<String>,
+        target: Option<&NodeRef>,
+    ) {
+        let dialog = Rc::new(forms::DialogCopyResources::new(
+            source,
+            kind,
+            resources,
+            self.node_choices(None),
+            target,
+        ));
+        let this = self.clone();
+        self.copy_dialogs
+            .register(dialog.clone(), source, move |d, n| this.copy_process(&d, n));
+        dialog.show();
+    }
+    /// Fetches configs of the resources from the source node, replaces the id prefix if required
+    /// and deploys them to the target
+    unsafe fn copy_process(
+        self: &Rc<Self>,
+        dialog: &forms::DialogCopyResources,
+        source: &NodeRef,
+    ) -> bool {
+        let target = if let Some(target) = dialog.target() {
+            target
+        } else {
+            self.error_box(Some("Node not selected"), "Please select the target node");
+            return false;
+        };
+        let prefix = dialog.prefix();
+        if target == *source && prefix.is_none() {
+            self.error_box(
+                Some("Invalid target"),
+                "Resources can be cloned to the same node with a different id prefix only",
+            );
+            return false;
+        }
+        let kind = dialog.kind();
+        let resources = dialog.resources().to_vec();
+        let source = source.clone();
+        self.ui_action(move || {
+            let mut configs = Vec::with_capacity(resources.len());
+            for id in resources {
+                let mut config = match kind {
+                    ResourceKind::Svcs => svc_entry(
+                        &id,
+                        bus::call(Arc::new(NitData::new_svc_get_params(&source, id.clone())))?,
+                    ),
+                    ResourceKind::Items => {
+                        bus::call(Arc::new(NitData::new_item_get_config(&source, id)))?
+                    }
+                };
+                if let Some((ref from, ref to)) = prefix {
+                    replace_id_prefix(kind, &mut config, from, to)?;
+                }
+                configs.push(config);
+            }
+            let cnt = configs.len();
+            bus::call::<()>(Arc::new(deploy_nit(&target, kind, configs)))?;
+            Ok(format!("{} {} copied to {}", cnt, kind.key(), target))
+        });
+        true
+    }
+    /// Nodes of all connections with labels, the connection is specified in labels if there are
+    /// more than one
+    unsafe fn node_choices(&self, exclude: Option<&NodeRef>) -> Vec<(String, NodeRef)> {
+        let mut nodes: Vec<(String, NodeRef)> = Vec::new();
+        let clouds = self.clouds.lock().unwrap();
+        for (conn, cloud) in clouds.iter() {
+            for name in &cloud.node_names {
+                let node = NodeRef::new(*conn, name);
+                if exclude == Some(&node) {
+                    continue;
+                }
+                let label = if clouds.len() > 1 {
+                    format!("{} ({})", name, cloud.root.text(0).to_std_string())
+                } else {
+                    name.clone()
+                };
+                nodes.push((label, node));
+            }
+        }
+        nodes
+    }
+    unsafe fn node_compare_pick(self: &Rc<Self>, node: &NodeRef) {
+        let nodes = self.node_choices(Some(node));
+        if nodes.is_empty() {
+            self.error_box(Some("No nodes"), "No other nodes to compare with");
+            return;
+        }
+        let labels = QStringList::new();
+        for (label, _) in &nodes {
+            labels.append_q_string(&qs(label));
+        }
+        let mut ok = false;
+        let selected = QInputDialog::get_item_7a(
+            &self.window.widget,
+            &qs("Compare nodes"),
+            &qs(format!("Compare {} with:", node)),
+            &labels,
+            0,
+            false,
+            &mut ok,
+        )
+        .to_std_string();
+        if !ok {
+            return;
+        }
+        if let Some((_, other)) = nodes.into_iter().find(|(label, _)| *label == selected) {
+            self.node_compare(node, &other);
+        }
+    }
+    unsafe fn node_compare(self: &Rc<Self>, left: &NodeRef, right: &NodeRef) {
+        let dialog = Rc::new(forms::DialogNodeCompare::new(left, right));
+        let u = self
+            .node_compare_dialogs
+            .register(dialog.clone(), left.conn());
+        let dialog_c = dialog.clone();
+        let slot_reload = SlotNoArgs::new(dialog.widget(), move || {
+            dialog_c.mark_loading();
+            Self::node_compare_reload(u, dialog_c.left().clone(), dialog_c.right().clone());
+        });
+        let this = self.clone();
+        let dialog_c = dialog.clone();
+        let slot_copy_right = SlotNoArgs::new(dialog.widget(), move || {
+            this.node_compare_copy(u, &dialog_c, true);
+        });
+        let this = self.clone();
+        let dialog_c = dialog.clone();
+        let slot_copy_left = SlotNoArgs::new(dialog.widget(), move || {
+            this.node_compare_copy(u, &dialog_c, false);
+        });
+        dialog.init(&slot_reload, &slot_copy_right, &slot_copy_left);
+        dialog.mark_loading();
+        dialog.show();
+        Self::node_compare_reload(u, left.clone(), right.clone());
+    }
+    fn node_compare_reload(u: uuid::Uuid, left: NodeRef, right: NodeRef) {
+        std::thread::spawn(move || {
+            let result = compare_nodes(&left, &right);
+            command(Command::ProcessNodeCompare(u, result));
+        });
+    }
+    /// Deploys the selected resources to the other node, services first as items may refer to
+    /// them. The comparison is reloaded after
+    unsafe fn node_compare_copy(
+        self: &Rc<Self>,
+        u: uuid::Uuid,
+        dialog: &forms::DialogNodeCompare,
+        to_right: bool,
+    ) {
+        let (svcs, items) = dialog.selected(to_right);
+        if svcs.is_empty() && items.is_empty() {
+            self.error_box(
+                Some("Resource not selected"),
+                "Please select resources which exist on the source node",
+            );
             return;
         }
-        match selected.object_name().to_std_string().as_str() {
-            CA_SAVE => {
-                self.process_action_nit(Arc::new(NitData::new_save(node)));
-            }
-            CA_RESTART => {
-                if self.confirm(&format!(
-                    r#"The node <b>{}</b> is going to be RESTARTED.<br>
-If this is the node Cloud Manager is connected to, the session will be disconnected."#,
-                    node
-                )) {
-                    self.process_action_nit(Arc::new(NitData::new_restart(node)));
-                }
+        let (source, target) = if to_right {
+            (dialog.left().clone(), dialog.right().clone())
+        } else {
+            (dialog.right().clone(), dialog.left().clone())
+        };
+        if !self.confirm(&format!(
+            "{} service(s) and {} item(s) are going to be copied from <b>{}</b> to <b>{}</b>",
+            svcs.len(),
+            items.len(),
+            source,
+            target
+        )) {
+            return;
+        }
+        let left = dialog.left().clone();
+        let right = dialog.right().clone();
+        self.ui_action(move || {
+            let cnt = (svcs.len(), items.len());
+            if !svcs.is_empty() {
+                bus::call::<()>(Arc::new(NitData::new_svc_deploy_multi(&target, svcs)))?;
+            }
+            if !items.is_empty() {
+                bus::call::<()>(Arc::new(NitData::new_item_deploy_multi(&target, items)))?;
+            }
+            command(Command::ProcessNodeCompare(u, compare_nodes(&left, &right)));
+            Ok(format!(
+                "{} service(s), {} item(s) copied to {}",
+                cnt.0, cnt.1, target
+            ))
+        });
+    }
+    unsafe fn node_backup(self: &Rc<Self>, node: &NodeRef) {
+        let fname = QFileDialog::get_save_file_name_4a(
+            &self.window.widget,
+            &qs(forms::OUT_FILE),
+            &qs(forms::get_last_dir()),
+            &qs(forms::YAML_FILTER),
+        )
+        .to_std_string();
+        if fname.is_empty() {
+            return;
+        }
+        forms::set_last_dir(&fname);
+        let node = node.clone();
+        self.ui_action(move || {
+            let backup = NodeBackup::create(&node)?;
+            backup.save(&fname)?;
+            Ok(format!("{} backed up: {}", node, backup.summary()))
+        });
+    }
+    unsafe fn node_restore(self: &Rc<Self>, node: &NodeRef) {
+        let fname = QFileDialog::get_open_file_name_4a(
+            &self.window.widget,
+            &qs(forms::IN_FILE),
+            &qs(forms::get_last_dir()),
+            &qs(forms::YAML_FILTER),
+        )
+        .to_std_string();
+        if fname.is_empty() {
+            return;
+        }
+        forms::set_last_dir(&fname);
+        let backup = match NodeBackup::load(&fname) {
+            Ok(v) => v,
+            Err(e) => {
+                self.error_box(Some("Invalid backup"), e);
+                return;
             }
-            _ => {}
+        };
+        let source = if backup.node == node.name() {
+            String::new()
+        } else {
+            format!(" (backed up from <b>{}</b>)", backup.node)
+        };
+        if !self.confirm(&format!(
+            "The backup of {}{}, created at {}, is going to be restored to the node <b>{}</b>.<br>
+Existing services and items with the same ids will be replaced.",
+            backup.summary(),
+            source,
+            backup.created,
+            node
+        )) {
+            return;
         }
+        let node = node.clone();
+        self.ui_action(move || {
+            let summary = backup.summary();
+            backup.restore(&node)?;
+            Ok(format!("{} restored: {}", node, summary))
+        });
     }
     unsafe fn confirm_obj_action(self: &Rc<Self>, kind: &str, op: &str, which: &[String]) -> bool {
         let mut w = which
@@ -1078,37 +2114,176 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             kind, op, w
         ))
     }
-    unsafe fn svc_destroy(self: &Rc<Self>, node: &str, svcs: Vec<String>) {
+    /// Compares the deployed service params with a file, the service can be redeployed from the
+    /// preview dialog after the diff is reviewed
+    unsafe fn svc_compare_file(self: &Rc<Self>, node: &NodeRef, svc: &str) {
+        let fname = QFileDialog::get_open_file_name_4a(
+            &self.window.widget,
+            &qs(forms::IN_FILE),
+            &qs(forms::get_last_dir()),
+            &qs(forms::RESOURCE_FILTER),
+        )
+        .to_std_string();
+        if fname.is_empty() {
+            return;
+        }
+        forms::set_last_dir(&fname);
+        let mut params = match load_svc_params(&fname, svc, node.name()) {
+            Ok(v) => v,
+            Err(e) => {
+                self.default_error_box(e);
+                return;
+            }
+        };
+        if self.resolve_vars(vec![&mut params]) {
+            self.import_preview(
+                node.clone(),
+                ResourceKind::Svcs,
+                vec![svc_entry(svc, params)],
+            );
+        }
+    }
+    unsafe fn svc_save_template(self: &Rc<Self>, node: &NodeRef, svc: String) {
+        let prompt = |label: &str, value: &str| {
+            let mut ok = false;
+            let value = QInputDialog::get_text_6a(
+                &self.window.widget,
+                &qs("Save as template"),
+                &qs(label),
+                EchoMode::Normal,
+                &qs(value),
+                &mut ok,
+            )
+            .to_std_string();
+            if ok {
+                Some(value)
+            } else {
+                None
+            }
+        };
+        let name = if let Some(v) = prompt("Template name:", &svc).filter(|v| !v.is_empty()) {
+            v
+        } else {
+            return;
+        };
+        match templates::exists(&name) {
+            Ok(true) => {
+                if !self.confirm(&format!(
+                    "Template <b>{}</b> already exists and will be overwritten",
+                    name
+                )) {
+                    return;
+                }
+            }
+            Ok(false) => {}
+            Err(e) => {
+                self.default_error_box(e);
+                return;
+            }
+        }
+        let description = if let Some(v) = prompt("Description:", "") {
+            v
+        } else {
+            return;
+        };
+        let paths = if let Some(v) = prompt(
+            "Placeholder fields (e.g. config.modbus.path, config.*.password):",
+            "",
+        ) {
+            match vars::parse_paths(&v) {
+                Ok(paths) => paths,
+                Err(e) => {
+                    self.default_error_box(e);
+                    return;
+                }
+            }
+        } else {
+            return;
+        };
+        let store_defaults = !paths.is_empty()
+            && QMessageBox::question_q_widget2_q_string(
+                &self.window.widget,
+                &qs("Placeholder defaults"),
+                &qs(
+                    "Store the current values of the placeholder fields as defaults?<br><br>\
+                    The values are saved in the template file in plain text",
+                ),
+            )
+            .to_int()
+                == 16384;
+        let node = node.clone();
+        self.ui_action(move || {
+            let val =
+                bus::call::<Value>(Arc::new(NitData::new_svc_get_params(&node, svc.clone())))?;
+            let mut params = ServiceParams::deserialize(val).map_err(Error::invalid_data)?;
+            params.id = Some(svc);
+            let (file, names) =
+                templates::save(&name, &description, &params, &paths, store_defaults)?;
+            if names.is_empty() {
+                Ok(format!("template saved: {}", file.display()))
+            } else {
+                Ok(format!(
+                    "template saved: {}, placeholders: {}",
+                    file.display(),
+                    names.join(", ")
+                ))
+            }
+        });
+    }
+    unsafe fn svc_destroy(self: &Rc<Self>, node: &NodeRef, svcs: Vec<String>) {
         if self.confirm_obj_action(KIND_SVC, "DESTROYED", &svcs) {
-            self.process_action_nit(Arc::new(NitData::new_svc_destroy(node, svcs)));
+            let op = format!("destroy {} {}", svcs.len(), KIND_SVC);
+            let nit = NitData::new_svc_destroy(node, svcs.clone());
+            self.process_undoable_nit(nit, op, svcs, Vec::new());
         }
     }
-    unsafe fn item_announce(self: &Rc<Self>, node: &str, oids: Vec<String>) {
-        self.process_action_nit(Arc::new(NitData::new_item_announce(node, oids)));
+    /// Snapshots the affected resources before the operation is called, the snapshot is pushed to
+    /// the undo stack if the operation succeeds
+    unsafe fn process_undoable_nit(
+        self: &Rc<Self>,
+        nit: NitData,
+        op: String,
+        svcs: Vec<String>,
+        oids: Vec<String>,
+    ) {
+        let nit = Arc::new(nit);
+        self.ui_action(move || {
+            let entry = undo::snapshot(nit.node(), &op, &svcs, &oids)
+                .map_err(|e| Error::failed(format!("unable to take the undo snapshot: {}", e)))?;
+            let result = bus::call::<Value>(nit.clone())?.to_string();
+            undo::push(entry)
+                .map_err(|e| Error::failed(format!("unable to save the undo snapshot: {}", e)))?;
+            Ok(result)
+        });
+    }
+    unsafe fn item_announce(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
+        self.process_bulk_nit(NitData::new_item_announce(node, oids));
     }
-    unsafe fn item_destroy(self: &Rc<Self>, node: &str, oids: Vec<String>) {
+    unsafe fn item_destroy(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
         if self.confirm_obj_action(KIND_ITEM, "DESTROYED", &oids) {
-            self.process_action_nit(Arc::new(NitData::new_item_destroy(node, oids)));
+            let op = format!("destroy {} {}", oids.len(), KIND_ITEM);
+            let nit = NitData::new_item_destroy(node, oids.clone());
+            self.process_undoable_nit(nit, op, Vec::new(), oids);
         }
     }
-    unsafe fn item_disable(self: &Rc<Self>, node: &str, oids: Vec<String>) {
+    unsafe fn item_disable(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
         if self.confirm_obj_action(KIND_ITEM, "DISABLED", &oids) {
-            self.process_action_nit(Arc::new(NitData::new_item_disable(node, oids)));
+            self.process_bulk_nit(NitData::new_item_disable(node, oids));
         }
     }
-    unsafe fn item_enable(self: &Rc<Self>, node: &str, oids: Vec<String>) {
+    unsafe fn item_enable(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
         if self.confirm_obj_action(KIND_ITEM, "ENABLED", &oids) {
-            self.process_action_nit(Arc::new(NitData::new_item_enable(node, oids)));
+            self.process_bulk_nit(NitData::new_item_enable(node, oids));
         }
     }
-    unsafe fn lmacro_run(self: &Rc<Self>, node: &str, current_eva_item: String) {
+    unsafe fn lmacro_run(self: &Rc<Self>, node: &NodeRef, current_eva_item: String) {
         let dialog_lmacro_run = Rc::new(forms::DialogLmacroRun::new());
         dialog_lmacro_run.qdialog.btn_box.disconnect();
         dialog_lmacro_run
             .qdialog
             .widget
             .set_window_title(&qs(format!("{} run", current_eva_item)));
-        let node = node.to_owned();
+        let node = node.clone();
         let this = self.clone();
         let dialog = dialog_lmacro_run.clone();
         dialog_lmacro_run
@@ -1157,7 +2332,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             ));
         dialog_lmacro_run.show();
     }
-    unsafe fn unit_action(self: &Rc<Self>, node: &str, current_eva_item: String) {
+    unsafe fn unit_action(self: &Rc<Self>, node: &NodeRef, current_eva_item: String) {
         let state = if let Ok(oid) = current_eva_item.parse::<OID>() {
             bus::item_state(node, oid).ok()
         } else {
@@ -1167,7 +2342,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         self.dialog_unit_action
             .widget
             .set_window_title(&qs(format!("{} action", current_eva_item)));
-        let node = node.to_owned();
+        let node = node.clone();
         let this = self.clone();
         self.dialog_unit_action
             .btn_box
@@ -1212,7 +2387,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             ));
         self.dialog_unit_action.show(state);
     }
-    unsafe fn unit_action_toggle(self: &Rc<Self>, node: &str, oid: String) {
+    unsafe fn unit_action_toggle(self: &Rc<Self>, node: &NodeRef, oid: String) {
         if self.confirm_obj_action(KIND_UNIT, "ALTERED WITH ACTION-TOGGLE", &[oid.clone()]) {
             match bus::call::<ActionRecord>(Arc::new(NitData::new_unit_action_toggle(node, oid))) {
                 Ok(a) => {
@@ -1224,14 +2399,14 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             }
         }
     }
-    unsafe fn lvar_set(self: &Rc<Self>, node: &str, current_eva_item: &str, oids: Vec<String>) {
+    unsafe fn lvar_set(self: &Rc<Self>, node: &NodeRef, current_eva_item: &str, oids: Vec<String>) {
         let state = if let Ok(oid) = current_eva_item.parse::<OID>() {
             bus::item_state(node, oid).ok()
         } else {
             None
         };
         self.dialog_lvar_set.btn_box.disconnect();
-        let node = node.to_owned();
+        let node = node.clone();
         let this = self.clone();
         self.dialog_lvar_set
             .btn_box
@@ -1242,11 +2417,11 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
                     Ok(p_set) => {
                         this.dialog_lvar_set.widget.close();
                         if this.confirm_obj_action(KIND_LVAR, "SET", &oids) {
-                            this.process_action_nit(Arc::new(NitData::new_lvar_set(
+                            this.process_bulk_nit(NitData::new_lvar_set(
                                 &node,
                                 oids.clone(),
                                 p_set,
-                            )));
+                            ));
                         }
                     }
                     Err(e) => {
@@ -1263,32 +2438,32 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             }));
         self.dialog_lvar_set.show(state);
     }
-    unsafe fn lvar_reset(self: &Rc<Self>, node: &str, oids: Vec<String>) {
+    unsafe fn lvar_reset(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
         if self.confirm_obj_action(KIND_LVAR, "RESETED", &oids) {
-            self.process_action_nit(Arc::new(NitData::new_lvar_reset(node, oids)));
+            self.process_bulk_nit(NitData::new_lvar_reset(node, oids));
         }
     }
-    unsafe fn lvar_clear(self: &Rc<Self>, node: &str, oids: Vec<String>) {
+    unsafe fn lvar_clear(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
         if self.confirm_obj_action(KIND_LVAR, "CLEARED", &oids) {
-            self.process_action_nit(Arc::new(NitData::new_lvar_clear(node, oids)));
+            self.process_bulk_nit(NitData::new_lvar_clear(node, oids));
         }
     }
-    unsafe fn lvar_toggle(self: &Rc<Self>, node: &str, oids: Vec<String>) {
+    unsafe fn lvar_toggle(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
         if self.confirm_obj_action(KIND_LVAR, "TOGGLED", &oids) {
-            self.process_action_nit(Arc::new(NitData::new_lvar_toggle(node, oids)));
+            self.process_bulk_nit(NitData::new_lvar_toggle(node, oids));
         }
     }
-    unsafe fn lvar_incr(self: &Rc<Self>, node: &str, oids: Vec<String>) {
+    unsafe fn lvar_incr(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
         if self.confirm_obj_action(KIND_LVAR, "INCREMENTED", &oids) {
-            self.process_action_nit(Arc::new(NitData::new_lvar_incr(node, oids)));
+            self.process_bulk_nit(NitData::new_lvar_incr(node, oids));
         }
     }
-    unsafe fn lvar_decr(self: &Rc<Self>, node: &str, oids: Vec<String>) {
+    unsafe fn lvar_decr(self: &Rc<Self>, node: &NodeRef, oids: Vec<String>) {
         if self.confirm_obj_action(KIND_LVAR, "DECREMENTED", &oids) {
-            self.process_action_nit(Arc::new(NitData::new_lvar_decr(node, oids)));
+            self.process_bulk_nit(NitData::new_lvar_decr(node, oids));
         }
     }
-    unsafe fn svc_edit(self: &Rc<Self>, node: &str, svc: String) {
+    unsafe fn svc_edit(self: &Rc<Self>, node: &NodeRef, svc: String) {
         match bus::call::<Value>(Arc::new(NitData::new_svc_get_params_x(node, svc.clone()))) {
             Ok(val) => {
                 if let Value::Seq(seq) = val {
@@ -1298,6 +2473,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
                             if let Ok(spoints) = Vec::deserialize(i.next().unwrap()) {
                                 params.id.replace(svc);
                                 let dialog = Rc::new(forms::DialogSvcEdit::load());
+                                dialog.init();
                                 let this = self.clone();
                                 self.svc_edit_dialogs.register(
                                     dialog.clone(),
@@ -1313,14 +2489,14 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             Err(e) => self.error("Failed to get service params", e),
         }
     }
-    unsafe fn svc_call_method(self: &Rc<Self>, node: &str, svc: String) {
+    unsafe fn svc_call_method(self: &Rc<Self>, node: &NodeRef, svc: String) {
         match bus::call::<Value>(Arc::new(NitData::new_svc_get_info(node, svc.clone()))) {
             Ok(val) => match SvcInfo::deserialize(val) {
                 Ok(info) => {
                     let dialog = forms::DialogSvcCall::new(&svc, node, info);
                     dialog.show();
                     let d = dialog.clone();
-                    let u = self.svc_call_dialogs.register(dialog);
+                    let u = self.svc_call_dialogs.register(dialog, node.conn());
                     d.set_uuid(u);
                 }
                 Err(e) => self.error("Failed to parse service info", e),
@@ -1333,12 +2509,15 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         svcs: Vec<String>,
         current_svc: String,
         pos: CppBox<QPoint>,
-        node: &str,
+        node: &NodeRef,
     ) {
         const CA_CALL: &str = "svc_ca_call";
         const CA_EDIT: &str = "svc_ca_edit";
         const CA_EXPORT: &str = "svc_ca_export";
         const CA_IMPORT: &str = "svc_ca_import";
+        const CA_COPY: &str = "svc_ca_copy";
+        const CA_COMPARE_FILE: &str = "svc_ca_compare_file";
+        const CA_SAVE_TEMPLATE: &str = "svc_ca_save_template";
         const CA_RESTART: &str = "svc_ca_restart";
         const CA_DESTROY: &str = "svc_ca_destroy";
         const CA_PURGE: &str = "svc_ca_purge";
@@ -1359,6 +2538,18 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         action_import.set_object_name(&qs(CA_IMPORT));
         action_import.set_text(&qs("&Import"));
         menu.add_action(&action_import);
+        let action_copy = QAction::new();
+        action_copy.set_object_name(&qs(CA_COPY));
+        action_copy.set_text(&qs("C&opy to node..."));
+        menu.add_action(&action_copy);
+        let action_compare_file = QAction::new();
+        action_compare_file.set_object_name(&qs(CA_COMPARE_FILE));
+        action_compare_file.set_text(&qs("Compare with &file..."));
+        menu.add_action(&action_compare_file);
+        let action_save_template = QAction::new();
+        action_save_template.set_object_name(&qs(CA_SAVE_TEMPLATE));
+        action_save_template.set_text(&qs("Save as &template..."));
+        menu.add_action(&action_save_template);
         let action_restart = QAction::new();
         action_restart.set_object_name(&qs(CA_RESTART));
         action_restart.set_text(&qs("&Restart"));
@@ -1372,6 +2563,17 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         action_purge.set_object_name(&qs(CA_PURGE));
         action_purge.set_text(&qs("&Purge"));
         menu.add_action(&action_purge);
+        if bus::is_read_only(node.conn()) {
+            for action in [
+                &action_call,
+                &action_import,
+                &action_restart,
+                &action_destroy,
+                &action_purge,
+            ] {
+                action.set_enabled(false);
+            }
+        }
         let selected = menu.exec_1a_mut(&pos);
         if selected.is_null() {
             return;
@@ -1389,9 +2591,18 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             CA_IMPORT => {
                 self.import_resource();
             }
+            CA_COPY => {
+                self.copy_resources(node, ResourceKind::Svcs, svcs, None);
+            }
+            CA_COMPARE_FILE => {
+                self.svc_compare_file(node, &current_svc);
+            }
+            CA_SAVE_TEMPLATE => {
+                self.svc_save_template(node, current_svc);
+            }
             CA_RESTART => {
                 if self.confirm_obj_action(KIND_SVC, "RESTARTED", &svcs) {
-                    self.process_action_nit(Arc::new(NitData::new_svc_restart(node, svcs)));
+                    self.process_bulk_nit(NitData::new_svc_restart(node, svcs));
                 }
             }
             CA_DESTROY => {
@@ -1399,7 +2610,9 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             }
             CA_PURGE => {
                 if self.confirm_obj_action(KIND_SVC, "PURGED", &svcs) {
-                    self.process_action_nit(Arc::new(NitData::new_svc_purge(node, svcs)));
+                    let op = format!("purge {} {}", svcs.len(), KIND_SVC);
+                    let nit = NitData::new_svc_purge(node, svcs.clone());
+                    self.process_undoable_nit(nit, op, svcs, Vec::new());
                 }
             }
             _ => {}
@@ -1410,7 +2623,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         _items: Vec<String>,
         current_action: String,
         pos: CppBox<QPoint>,
-        node: &str,
+        node: &NodeRef,
     ) {
         const CA_WATCH: &str = "action_ca_watch";
         let menu = QMenu::new();
@@ -1440,12 +2653,15 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         items: Vec<String>,
         current_eva_item: String,
         pos: CppBox<QPoint>,
-        node: &str,
+        node: &NodeRef,
     ) {
         const CA_ANNOUNCE: &str = "item_ca_announce";
         const CA_EDIT: &str = "item_ca_edit";
+        const CA_BULK_EDIT: &str = "item_ca_bulk_edit";
         const CA_EXPORT: &str = "item_ca_export";
         const CA_IMPORT: &str = "item_ca_import";
+        const CA_COPY: &str = "item_ca_copy";
+        const CA_RENAME: &str = "item_ca_rename";
         const CA_DISABLE: &str = "item_ca_disable";
         const CA_ENABLE: &str = "item_ca_enable";
         const CA_DESTROY: &str = "item_ca_destroy";
@@ -1461,6 +2677,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         const CA_LVAR_TOGGLE: &str = "item_ca_lvar_toggle";
         const CA_LVAR_INCR: &str = "item_ca_lvar_incr";
         const CA_LVAR_DECR: &str = "item_ca_lvar_decr";
+        let read_only = bus::is_read_only(node.conn());
         let menu = QMenu::new();
         let mut secondary_actions = Vec::new();
         let mut x_actions = Vec::new();
@@ -1507,11 +2724,13 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
                     let action_action = QAction::new();
                     action_action.set_object_name(&qs(CA_UNIT_ACTION));
                     action_action.set_text(&qs("&Action"));
+                    action_action.set_enabled(!read_only);
                     menu.add_action(&action_action);
                     x_actions.push(action_action);
                     let action_toggle = QAction::new();
                     action_toggle.set_object_name(&qs(CA_UNIT_ACTION_TOGGLE));
                     action_toggle.set_text(&qs("Action &toggle"));
+                    action_toggle.set_enabled(!read_only);
                     menu.add_action(&action_toggle);
                     x_actions.push(action_toggle);
                 }
@@ -1519,6 +2738,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
                     let action_run = QAction::new();
                     action_run.set_object_name(&qs(CA_LMACRO_RUN));
                     action_run.set_text(&qs("&Run"));
+                    action_run.set_enabled(!read_only);
                     menu.add_action(&action_run);
                     x_actions.push(action_run);
                 }
@@ -1548,6 +2768,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         } else {
             let lvars_menu = QMenu::new();
             lvars_menu.set_title(&qs("&Lvar ops"));
+            lvars_menu.set_enabled(!read_only);
             menu.add_menu_q_menu(&lvars_menu);
             let action_lvar_set = QAction::new();
             action_lvar_set.set_object_name(&qs(CA_LVAR_SET));
@@ -1590,6 +2811,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             let action_announce = QAction::new();
             action_announce.set_object_name(&qs(CA_ANNOUNCE));
             action_announce.set_text(&qs("A&nnounce"));
+            action_announce.set_enabled(!read_only);
             menu.add_action(&action_announce);
             x_actions.push(action_announce);
         }
@@ -1600,6 +2822,11 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         action_edit.set_object_name(&qs(CA_EDIT));
         action_edit.set_text(&qs("&Edit"));
         menu.add_action(&action_edit);
+        let action_bulk_edit = QAction::new();
+        action_bulk_edit.set_object_name(&qs(CA_BULK_EDIT));
+        action_bulk_edit.set_text(&qs("B&ulk edit..."));
+        action_bulk_edit.set_enabled(items.len() > 1);
+        menu.add_action(&action_bulk_edit);
         let action_export = QAction::new();
         action_export.set_object_name(&qs(CA_EXPORT));
         action_export.set_text(&qs("E&xport"));
@@ -1608,6 +2835,14 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         action_import.set_object_name(&qs(CA_IMPORT));
         action_import.set_text(&qs("&Import"));
         menu.add_action(&action_import);
+        let action_copy = QAction::new();
+        action_copy.set_object_name(&qs(CA_COPY));
+        action_copy.set_text(&qs("C&opy to node..."));
+        menu.add_action(&action_copy);
+        let action_rename = QAction::new();
+        action_rename.set_object_name(&qs(CA_RENAME));
+        action_rename.set_text(&qs("Re&name..."));
+        menu.add_action(&action_rename);
         let action_disable = QAction::new();
         action_disable.set_object_name(&qs(CA_DISABLE));
         action_disable.set_text(&qs("Disa&ble"));
@@ -1621,6 +2856,18 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         action_destroy.set_object_name(&qs(CA_DESTROY));
         action_destroy.set_text(&qs("&Destroy"));
         menu.add_action(&action_destroy);
+        if read_only {
+            for action in [
+                &action_import,
+                &action_bulk_edit,
+                &action_rename,
+                &action_disable,
+                &action_enable,
+                &action_destroy,
+            ] {
+                action.set_enabled(false);
+            }
+        }
         let selected = menu.exec_1a_mut(&pos);
         if selected.is_null() {
             return;
@@ -1632,6 +2879,9 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             CA_EDIT => {
                 self.item_edit(node, current_eva_item);
             }
+            CA_BULK_EDIT => {
+                self.item_bulk_edit(node.clone(), items);
+            }
             CA_EXPORT => {
                 self.dialog_export.show(node, KIND_ITEM);
             }
@@ -1641,6 +2891,12 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             CA_IMPORT => {
                 self.import_resource();
             }
+            CA_COPY => {
+                self.copy_resources(node, ResourceKind::Items, items, None);
+            }
+            CA_RENAME => {
+                self.item_rename(node, items);
+            }
             CA_DISABLE => {
                 self.item_disable(node, items);
             }
@@ -1736,17 +2992,25 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         table: &QPtr<QTableWidget>,
         current_item: Ptr<QTableWidgetItem>,
     ) {
-        let row = current_item.row();
-        let node = table.item(row, 0).text().to_std_string();
-        let pos = table.visual_item_rect(current_item).bottom_left();
-        let global_pos = current_item.table_widget().map_to_global(&pos);
-        self.ctx_nodes(&node, global_pos);
+        // the node list belongs to the connection of the last displayed state
+        let conn = crate::LAST_NIT
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|nit| nit.conn());
+        if let Some(conn) = conn {
+            let row = current_item.row();
+            let node = NodeRef::new(conn, &table.item(row, 0).text().to_std_string());
+            let pos = table.visual_item_rect(current_item).bottom_left();
+            let global_pos = current_item.table_widget().map_to_global(&pos);
+            self.ctx_nodes(&node, global_pos);
+        }
     }
     unsafe fn svc_list_ctx(
         self: &Rc<Self>,
         table: &QPtr<QTableWidget>,
         current_item: Ptr<QTableWidgetItem>,
-        node: &str,
+        node: &NodeRef,
     ) {
         let row = current_item.row();
         let pos = table.visual_item_rect(current_item).bottom_left();
@@ -1760,7 +3024,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         self: &Rc<Self>,
         table: &QPtr<QTableWidget>,
         current_item: Ptr<QTableWidgetItem>,
-        node: &str,
+        node: &NodeRef,
     ) {
         let row = current_item.row();
         let pos = table.visual_item_rect(current_item).bottom_left();
@@ -1774,7 +3038,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         self: &Rc<Self>,
         table: &QPtr<QTableWidget>,
         current_item: Ptr<QTableWidgetItem>,
-        node: &str,
+        node: &NodeRef,
     ) {
         let row = current_item.row();
         let pos = table.visual_item_rect(current_item).bottom_left();
@@ -1785,6 +3049,76 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         }
     }
     // handle tree popup
+    #[slot(SlotOfQModelIndexIntInt)]
+    unsafe fn on_tree_rows_inserted(
+        self: &Rc<Self>,
+        parent: Ref<QModelIndex>,
+        first: c_int,
+        last: c_int,
+    ) {
+        if self.tree_populating.load(atomic::Ordering::SeqCst) {
+            return;
+        }
+        let mut path = Vec::new();
+        let mut index = QModelIndex::new_copy(parent);
+        while index.is_valid() {
+            path.push(index.data_0a().to_string().to_std_string());
+            index = index.parent();
+        }
+        path.reverse();
+        self.tree_drops.lock().unwrap().push((path, first, last));
+        self.tree_drop_timer.start_0a();
+    }
+    /// Removes tree items, created by Qt from the dropped table rows, and opens the copy dialog
+    /// if the rows have been dropped on a node entry or its child
+    #[allow(clippy::cast_possible_truncation)]
+    #[slot(SlotNoArgs)]
+    unsafe fn on_tree_drop(self: &Rc<Self>) {
+        let drops = std::mem::take(&mut *self.tree_drops.lock().unwrap());
+        let tree = &self.window.main_tree;
+        let mut target = None;
+        for (path, first, last) in drops {
+            let parent = self.find_tree_item(&path);
+            if parent.is_null() && !path.is_empty() {
+                continue;
+            }
+            for i in (first..=last).rev() {
+                let item = if parent.is_null() {
+                    tree.take_top_level_item(i)
+                } else {
+                    parent.take_child(i)
+                };
+                if !item.is_null() {
+                    drop(CppBox::from_raw(item.as_mut_raw_ptr()));
+                }
+            }
+            if path.len() > 1 && target.is_none() {
+                let data = self
+                    .find_tree_item(&path[..1])
+                    .data(0, ItemDataRole::UserRole.into());
+                if data.is_valid() {
+                    target = Some(NodeRef::new(data.to_u_long_long_0a() as ConnId, &path[1]));
+                }
+            }
+        }
+        let target = if let Some(target) = target {
+            target
+        } else {
+            return;
+        };
+        if let Some(nd) = self.current_nd() {
+            let kind = match nd.kind() {
+                NitKind::Services => ResourceKind::Svcs,
+                NitKind::Items(_, _) => ResourceKind::Items,
+                _ => return,
+            };
+            if let Some(resources) = self.window.primary_table.selected_resources() {
+                if !resources.is_empty() {
+                    self.copy_resources(nd.node(), kind, resources, Some(&target));
+                }
+            }
+        }
+    }
     #[slot(SlotNoArgs)]
     unsafe fn on_tree_ctx(self: &Rc<Self>) {
         let current_item = self.window.main_tree.current_item();
@@ -1905,6 +3239,10 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
     }
     #[slot(SlotNoArgs)]
     unsafe fn handle_auto_reload(self: &Rc<Self>) {
+        if self.nit_pending.lock().unwrap().is_some() {
+            // the previous reload is still in progress
+            return;
+        }
         let nit_opt = crate::LAST_NIT.lock().unwrap().clone();
         if let Some(nit) = nit_opt {
             self.process_nit(nit);
@@ -1942,7 +3280,9 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
     }
     #[slot(SlotNoArgs)]
     unsafe fn on_main_tree_activated(self: &Rc<Self>) {
+        self.update_conn_status();
         if let Some(nd) = self.current_nd() {
+            self.set_node_filter_conn(nd.conn());
             self.set_item_filter(false);
             self.set_log_filter(false);
             self.set_action_filter(false);
@@ -1967,7 +3307,7 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             }
         }
     }
-    unsafe fn current_nd(self: &Rc<Self>) -> Option<NitData> {
+    unsafe fn current_tree_path(&self) -> Vec<String> {
         let item = self.window.main_tree.current_item();
         let mut path = Vec::new();
         let mut curr = item;
@@ -1976,13 +3316,122 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             curr = curr.parent();
         }
         path.reverse();
+        path
+    }
+    unsafe fn select_tree_path(&self, path: &[String]) {
+        let item = self.find_tree_item(path);
+        if !item.is_null() {
+            let tree = &self.window.main_tree;
+            tree.set_current_item_1a(item);
+            if !item.parent().is_null() {
+                tree.set_item_expanded(item.parent(), true);
+            }
+        }
+    }
+    /// Returns null if the item is not found or the path is empty
+    unsafe fn find_tree_item(&self, path: &[String]) -> Ptr<QTreeWidgetItem> {
+        let tree = &self.window.main_tree;
+        let mut parent: Ptr<QTreeWidgetItem> = Ptr::null();
+        for name in path {
+            let count = if parent.is_null() {
+                tree.top_level_item_count()
+            } else {
+                parent.child_count()
+            };
+            let mut found: Ptr<QTreeWidgetItem> = Ptr::null();
+            for i in 0..count {
+                let item = if parent.is_null() {
+                    tree.top_level_item(i)
+                } else {
+                    parent.child(i)
+                };
+                if item.text(0).to_std_string() == *name {
+                    found = item;
+                    break;
+                }
+            }
+            if found.is_null() {
+                return Ptr::null();
+            }
+            parent = found;
+        }
+        parent
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    unsafe fn current_conn(&self) -> Option<ConnId> {
+        let mut curr = self.window.main_tree.current_item();
+        if curr.is_null() {
+            return None;
+        }
+        while !curr.parent().is_null() {
+            curr = curr.parent();
+        }
+        let data = curr.data(0, ItemDataRole::UserRole.into());
+        if data.is_valid() {
+            Some(data.to_u_long_long_0a() as ConnId)
+        } else {
+            None
+        }
+    }
+    unsafe fn current_nd(self: &Rc<Self>) -> Option<NitData> {
+        let conn = self.current_conn()?;
+        let path = self.current_tree_path();
+        // the first path element is the connection root
         crate::common::nd_from_path(
+            conn,
             path.iter()
+                .skip(1)
                 .map(String::as_str)
                 .collect::<Vec<&str>>()
                 .as_slice(),
         )
     }
+    /// Sets the window title and the colour tag of the selected connection
+    unsafe fn update_conn_status(&self) {
+        let clouds = self.clouds.lock().unwrap();
+        let selected = self
+            .current_conn()
+            .or_else(|| {
+                if clouds.len() == 1 {
+                    clouds.keys().next().copied()
+                } else {
+                    None
+                }
+            })
+            .and_then(|conn| clouds.get(&conn).map(|cloud| (conn, cloud)));
+        if let Some((conn, cloud)) = selected {
+            self.window.widget.set_window_title(&qs(format!(
+                "{} - {}",
+                cloud.root.text(0).to_std_string(),
+                self.title
+            )));
+            self.window.set_color_tag(
+                self.conn_profiles
+                    .lock()
+                    .unwrap()
+                    .get(&conn)
+                    .and_then(|p| p.color.as_deref()),
+            );
+        } else {
+            self.window.widget.set_window_title(&qs(&self.title));
+            self.window.set_color_tag(None);
+        }
+    }
+    /// Fills the item node filter with nodes of the connection
+    unsafe fn set_node_filter_conn(&self, conn: ConnId) {
+        let mut filter_conn = self.node_filter_conn.lock().unwrap();
+        if *filter_conn == Some(conn) {
+            return;
+        }
+        filter_conn.replace(conn);
+        self.window.i_node.clear();
+        self.window.i_node.add_item_q_string(&qs("*"));
+        if let Some(cloud) = self.clouds.lock().unwrap().get(&conn) {
+            for name in &cloud.node_names {
+                self.window.i_node.add_item_q_string(&qs(name));
+            }
+        }
+    }
     unsafe fn reload(self: &Rc<Self>) {
         let nit_opt = crate::LAST_NIT.lock().unwrap().clone();
         if let Some(nit) = nit_opt {
@@ -2014,12 +3463,6 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         }
     }
     unsafe fn process_nit(self: &Rc<Self>, mut nit: Nit) {
-        macro_rules! err {
-            ($e: expr) => {
-                self.clear_tables();
-                self.error("", $e);
-            };
-        }
         self.window.set_nit_status("");
         match nit.kind() {
             NitKind::Items(oid, node) => {
@@ -2068,20 +3511,71 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
             _ => {}
         }
         crate::LAST_NIT.lock().unwrap().replace(nit.clone());
-        match bus::call::<Value>(nit.clone()) {
-            Ok(v) => {
-                if let Err(e) = output::result(self, nit, v) {
-                    err!(e);
-                }
+        let nit_c = nit.clone();
+        let u = self.call_async(nit, move |this, result| {
+            // drop results of outdated requests
+            if crate::LAST_NIT
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map_or(true, |last| !Arc::ptr_eq(last, &nit_c))
+            {
+                return;
             }
-            Err(e) => {
-                err!(e);
+            this.nit_pending.lock().unwrap().take();
+            match result {
+                Ok(v) => {
+                    if let Err(e) = output::result(this, nit_c, v) {
+                        this.clear_tables();
+                        this.error("", e);
+                    }
+                }
+                Err(e) => {
+                    this.clear_tables();
+                    this.error("", e);
+                }
             }
+        });
+        if self.async_calls.lock().unwrap().contains_key(&u) {
+            self.nit_pending.lock().unwrap().replace(u);
         }
     }
     unsafe fn process_action_nit(self: &Rc<Self>, nit: Nit) {
         self.ui_action(move || Ok(bus::call::<Value>(nit.clone())?.to_string()));
     }
+    /// Bulk operations return a per-resource report if the continue-on-error mode is on
+    unsafe fn process_bulk_nit(self: &Rc<Self>, nit: NitData) {
+        if nit.kind().is_bulk() && self.window.action_continue_on_error.is_checked() {
+            self.process_bulk_report_nit(Arc::new(nit.with_continue_on_error()));
+        } else {
+            self.process_action_nit(Arc::new(nit));
+        }
+    }
+    unsafe fn process_bulk_report_nit(self: &Rc<Self>, nit: Nit) {
+        let u = uuid::Uuid::new_v4();
+        self.current_op.lock().unwrap().replace(u);
+        self.busy();
+        std::thread::spawn(move || {
+            let result = bus::run_op(u, || bus::call::<Vec<BulkResult>>(nit.clone()));
+            command(Command::ProcessBulkResult(u, nit, result));
+        });
+    }
+    unsafe fn bulk_report(self: &Rc<Self>, nit: Nit, report: Vec<BulkResult>) {
+        let conn = nit.conn();
+        let dialog = Rc::new(forms::DialogBulkReport::new(nit));
+        let this = self.clone();
+        let dialog_c = dialog.clone();
+        let slot_retry = SlotNoArgs::new(dialog.widget(), move || {
+            if let Some(nit) = dialog_c.retry_nit() {
+                dialog_c.close();
+                this.process_bulk_report_nit(Arc::new(nit));
+            }
+        });
+        dialog.init(&slot_retry);
+        dialog.set_report(report);
+        self.bulk_report_dialogs.register(dialog.clone(), conn);
+        dialog.show();
+    }
     pub unsafe fn default_error_box<E: fmt::Display>(self: &Rc<Self>, msg: E) {
         self.error_box(None::<&str>, msg);
     }
@@ -2116,6 +3610,138 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
     unsafe fn on_proto_selected(self: &Rc<Self>, current: Ref<QString>) {
         self.dialog_connect.handle_proto(&current.to_std_string());
     }
+    #[slot(SlotOfQString)]
+    unsafe fn on_profile_selected(self: &Rc<Self>, current: Ref<QString>) {
+        let profile = self
+            .config
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|c| c.profile(&current.to_std_string()).cloned());
+        if let Some(profile) = profile {
+            self.dialog_connect.set_profile(&profile);
+        }
+    }
+    #[slot(SlotNoArgs)]
+    unsafe fn on_profile_save(self: &Rc<Self>) {
+        if let Some(profile) = self.dialog_connect.generate_profile() {
+            self.save_profile(profile);
+        } else {
+            self.error_box(Some("Failed"), "Profile name not specified");
+        }
+    }
+    #[slot(SlotNoArgs)]
+    unsafe fn on_profile_delete(self: &Rc<Self>) {
+        let name = self.dialog_connect.profile_name();
+        if name.is_empty()
+            || !self.confirm(&format!(
+                "The connection profile <b>{}</b> is going to be DELETED",
+                name
+            ))
+        {
+            return;
+        }
+        if let Some(config) = self.config.lock().unwrap().as_mut() {
+            config.remove_profile(&name);
+            self.dialog_connect.set_profiles(config.profiles());
+        }
+        self.dialog_connect.profile.set_current_text(&qs(""));
+        self.rebuild_recent_menu();
+    }
+    unsafe fn save_profile(self: &Rc<Self>, profile: ConnectionProfile) {
+        if let Some(config) = self.config.lock().unwrap().as_mut() {
+            config.set_profile(profile);
+            self.dialog_connect.set_profiles(config.profiles());
+        }
+    }
+    unsafe fn rebuild_recent_menu(self: &Rc<Self>) {
+        let menu = &self.window.menu_recent;
+        let mut recent_actions = self.recent_actions.lock().unwrap();
+        // the menu may be rebuilt from a slot of its own action, so actions are deleted later
+        for action in recent_actions.drain(..) {
+            menu.remove_action(&action);
+            action.delete_later();
+        }
+        let recent: Vec<ConnectionProfile> = self
+            .config
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|c| {
+                c.recent()
+                    .iter()
+                    .filter_map(|name| c.profile(name).cloned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        menu.set_disabled(recent.is_empty());
+        for profile in recent {
+            let action =
+                menu.add_action_q_string(&qs(format!("{} ({})", profile.name, profile.path)));
+            let this = self.clone();
+            // the slot is owned by the action and is destroyed together with it
+            action
+                .triggered()
+                .connect(&SlotNoArgs::new(&action, move || {
+                    this.connect_profile(&profile.name);
+                }));
+            recent_actions.push(action);
+        }
+    }
+    /// Connects using a saved profile, the connect dialog is shown if a password is required
+    unsafe fn connect_profile(self: &Rc<Self>, name: &str) {
+        let profile = self
+            .config
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|c| c.profile(name).cloned());
+        if let Some(profile) = profile {
+            self.dialog_connect.set_profile(&profile);
+            let mut opts = profile.connection_options();
+            if let Some(password) = self.args.password() {
+                if let Some(creds) = opts.credentials.as_mut() {
+                    creds.1 = password.to_owned();
+                }
+            }
+            if let Some(conn) = self.connect_or_ask(opts) {
+                self.activate_profile(conn, profile);
+            }
+        } else {
+            self.error_box(
+                Some("Failed"),
+                format!("Connection profile not found: {}", name),
+            );
+            self.dialog_connect.show();
+        }
+    }
+    unsafe fn activate_profile(self: &Rc<Self>, conn: ConnId, profile: ConnectionProfile) {
+        bus::set_conn_read_only(conn, profile.read_only);
+        self.window.auto_reload.set_value(profile.auto_reload);
+        if let Some(config) = self.config.lock().unwrap().as_mut() {
+            config.mark_recent(&profile.name);
+        }
+        self.conn_profiles.lock().unwrap().insert(conn, profile);
+        self.rebuild_recent_menu();
+    }
+    /// Returns None if the connect dialog has been shown to ask for the password
+    unsafe fn connect_or_ask(self: &Rc<Self>, opts: ConnectionOptions) -> Option<ConnId> {
+        self.dialog_connect.set_data(opts.clone());
+        if (opts.path.starts_with("http://") || opts.path.starts_with("https://"))
+            && opts
+                .credentials
+                .as_ref()
+                .map_or(true, |creds| creds.1.is_empty())
+        {
+            self.dialog_connect.show();
+            None
+        } else {
+            if let Some(config) = self.config.lock().unwrap().as_mut() {
+                config.set_connection(&opts);
+            }
+            Some(bus::connect(opts))
+        }
+    }
     //#[slot(SlotNoArgs)]
     //unsafe fn on_button_clicked(self: &Rc<Self>) {
     //self.window.status.set_text(&qs("Reloading..."));
@@ -2133,29 +3759,68 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         if !self.window.widget.is_visible() {
             self.svc_edit_dialogs.close_all();
             self.item_edit_dialogs.close_all();
+            self.import_preview_dialogs.close_all();
+            self.copy_dialogs.close_all();
+            self.rename_dialogs.close_all();
+            self.item_bulk_edit_dialogs.close_all();
+            self.item_generator_dialogs.close_all();
+            self.svc_template_dialogs.close_all();
             self.item_watch_dialogs.close_all();
             self.svc_call_dialogs.close_all();
+            self.bulk_report_dialogs.close_all();
+            self.node_compare_dialogs.close_all();
         }
         self.svc_edit_dialogs.cleanup();
         self.item_edit_dialogs.cleanup();
+        self.import_preview_dialogs.cleanup();
+        self.copy_dialogs.cleanup();
+        self.rename_dialogs.cleanup();
+        self.item_bulk_edit_dialogs.cleanup();
+        self.item_generator_dialogs.cleanup();
+        self.svc_template_dialogs.cleanup();
         self.item_watch_dialogs.cleanup();
         self.svc_call_dialogs.cleanup();
+        self.bulk_report_dialogs.cleanup();
+        self.node_compare_dialogs.cleanup();
     }
     #[slot(SlotNoArgs)]
     unsafe fn on_action_connect(self: &Rc<Self>) {
         self.dialog_connect.show();
     }
-    #[allow(clippy::unused_self)]
+    /// Disconnects the selected connection or all connections if nothing is selected
     #[slot(SlotNoArgs)]
     unsafe fn on_action_disconnect(self: &Rc<Self>) {
-        bus::disconnect();
+        if let Some(conn) = self.current_conn() {
+            bus::disconnect(conn);
+        } else {
+            bus::disconnect_all();
+        }
     }
     #[slot(SlotNoArgs)]
     unsafe fn on_about(self: &Rc<Self>) {
         self.dialog_about.show();
     }
+    #[slot(SlotNoArgs)]
+    unsafe fn on_audit(self: &Rc<Self>) {
+        self.dialog_audit.show();
+    }
+    #[slot(SlotNoArgs)]
+    unsafe fn on_read_only_toggled(self: &Rc<Self>) {
+        self.apply_read_only();
+    }
+    /// Applies the global read-only mode to the bus and the main window actions
+    unsafe fn apply_read_only(self: &Rc<Self>) {
+        let read_only = self.window.action_read_only.is_checked();
+        bus::set_read_only(read_only);
+        self.window.action_add_resource.set_enabled(!read_only);
+        self.window.action_add_svc_template.set_enabled(!read_only);
+        self.window.action_generate_items.set_enabled(!read_only);
+        self.window.action_undo.set_enabled(!read_only);
+        self.window.action_delete_resource.set_enabled(!read_only);
+        self.window.action_import_resource.set_enabled(!read_only);
+    }
     pub unsafe fn terminate(self: &Rc<Self>) {
-        bus::disconnect();
+        bus::disconnect_all();
         self.clear_workspace();
         if let Some(config) = self.config.lock().unwrap().as_ref() {
             config.save_to_disk();
@@ -2167,7 +3832,13 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         if let Some(config) = self.config.lock().unwrap().as_mut() {
             config.set_connection(&opts);
         }
-        bus::connect(opts);
+        let profile = self.dialog_connect.generate_profile();
+        let conn = bus::connect(opts);
+        bus::set_conn_read_only(conn, self.dialog_connect.read_only());
+        if let Some(profile) = profile {
+            self.save_profile(profile.clone());
+            self.activate_profile(conn, profile);
+        }
     }
     pub unsafe fn show(self: &Rc<Self>) {
         macro_rules! default_config {
@@ -2197,23 +3868,28 @@ If this is the node Cloud Manager is connected to, the session will be disconnec
         };
         if loaded {
             config.qt_apply(self);
+        } else {
+            self.dialog_connect
+                .set_auto_reload(self.window.auto_reload.value());
+        }
+        if let Some(max_parallel) = self.args.max_parallel().or_else(|| config.max_parallel()) {
+            bus::set_max_parallel(max_parallel);
         }
         self.config.lock().unwrap().replace(config);
         if !loaded {
             self.init_splitters();
         }
-        if let Some(opts) = self.args.connection_options() {
-            self.dialog_connect.set_data(opts.clone());
-            if (opts.path.starts_with("http://") || opts.path.starts_with("https://"))
-                && opts
-                    .credentials
-                    .as_ref()
-                    .map_or(true, |creds| creds.1.is_empty())
-            {
-                self.dialog_connect.show();
-            } else {
-                bus::connect(opts);
-            }
+        self.rebuild_recent_menu();
+        if self.args.read_only() {
+            // can not be switched off from the UI
+            self.window.action_read_only.set_checked(true);
+            self.window.action_read_only.set_enabled(false);
+            self.apply_read_only();
+        }
+        if let Some(profile) = self.args.profile() {
+            self.connect_profile(profile);
+        } else if let Some(opts) = self.args.connection_options() {
+            self.connect_or_ask(opts);
         } else {
             self.dialog_connect.show();
         }
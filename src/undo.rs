@@ -0,0 +1,141 @@
+use crate::backup::NodeBackup;
+use crate::bus;
+use crate::common::NodeRef;
+use eva_common::prelude::*;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Max entries kept per connection and node, the oldest ones are dropped
+pub const UNDO_MAX_ENTRIES: usize = 20;
+
+lazy_static! {
+    static ref UNDO_LOCK: Mutex<()> = <_>::default();
+}
+
+/// Resources snapshot taken before a destructive operation
+#[derive(Serialize, Deserialize)]
+pub struct UndoEntry {
+    pub id: uuid::Uuid,
+    pub path: String,
+    pub op: String,
+    pub snapshot: NodeBackup,
+}
+
+impl UndoEntry {
+    pub fn info(&self) -> String {
+        format!(
+            "{} at {} ({})",
+            self.op,
+            self.snapshot.created,
+            self.snapshot.summary()
+        )
+    }
+    fn is_for(&self, path: &str, node: &str) -> bool {
+        self.path == path && self.snapshot.node == node
+    }
+}
+
+fn load_all(file: &Path) -> EResult<Vec<UndoEntry>> {
+    if !file.exists() {
+        return Ok(Vec::new());
+    }
+    let mut buf = Vec::new();
+    std::fs::File::open(file)?.read_to_end(&mut buf)?;
+    serde_json::from_slice(&buf).map_err(Error::invalid_data)
+}
+
+fn save_all(file: &Path, entries: &[UndoEntry]) -> EResult<()> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let data = serde_json::to_vec(entries).map_err(Error::invalid_data)?;
+    let mut f = std::fs::OpenOptions::new()
+        .create(true)
+        .append(false)
+        .truncate(true)
+        .write(true)
+        .open(file)?;
+    f.write_all(&data)?;
+    Ok(())
+}
+
+fn conn_path(node: &NodeRef) -> EResult<String> {
+    bus::connection_path(node.conn()).ok_or_else(|| Error::not_found("connection not found"))
+}
+
+/// Snapshots the services and items (with lvar states). Must be called before the resources are
+/// destroyed, the entry is pushed to the stack after the operation succeeds
+pub fn snapshot(node: &NodeRef, op: &str, svcs: &[String], oids: &[String]) -> EResult<UndoEntry> {
+    Ok(UndoEntry {
+        id: uuid::Uuid::new_v4(),
+        path: conn_path(node)?,
+        op: op.to_owned(),
+        snapshot: NodeBackup::snapshot(node, svcs, oids)?,
+    })
+}
+
+/// Pushes the entry to the undo stack of its connection and node
+pub fn push(entry: UndoEntry) -> EResult<()> {
+    let file = if let Some(file) = crate::UNDO_FILE.as_ref() {
+        file
+    } else {
+        return Ok(());
+    };
+    let _lock = UNDO_LOCK.lock().unwrap();
+    let mut entries = load_all(file)?;
+    let count = entries
+        .iter()
+        .filter(|e| e.is_for(&entry.path, &entry.snapshot.node))
+        .count();
+    if count >= UNDO_MAX_ENTRIES {
+        let mut to_drop = count + 1 - UNDO_MAX_ENTRIES;
+        entries.retain(|e| {
+            if to_drop > 0 && e.is_for(&entry.path, &entry.snapshot.node) {
+                to_drop -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+    entries.push(entry);
+    save_all(file, &entries)
+}
+
+/// The latest entry of the connection and node
+pub fn last(node: &NodeRef) -> EResult<Option<UndoEntry>> {
+    let file = if let Some(file) = crate::UNDO_FILE.as_ref() {
+        file
+    } else {
+        return Ok(None);
+    };
+    let path = conn_path(node)?;
+    let _lock = UNDO_LOCK.lock().unwrap();
+    Ok(load_all(file)?
+        .into_iter()
+        .rev()
+        .find(|e| e.is_for(&path, node.name())))
+}
+
+fn remove(id: uuid::Uuid) -> EResult<()> {
+    if let Some(file) = crate::UNDO_FILE.as_ref() {
+        let _lock = UNDO_LOCK.lock().unwrap();
+        let mut entries = load_all(file)?;
+        entries.retain(|e| e.id != id);
+        save_all(file, &entries)?;
+    }
+    Ok(())
+}
+
+/// Redeploys the snapshot and removes the entry from the stack. The entry is kept if the
+/// redeploy fails
+pub fn undo(node: &NodeRef, entry: UndoEntry) -> EResult<String> {
+    let id = entry.id;
+    let info = entry.info();
+    entry.snapshot.restore(node)?;
+    remove(id)?;
+    Ok(info)
+}
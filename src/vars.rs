@@ -0,0 +1,436 @@
+use eva_common::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+
+/// Variable values, as given in an environment file or entered by the user
+pub type Vars = BTreeMap<String, String>;
+
+/// Type of a placeholder which is the whole field, e.g. ${PORT:int}. Values of untyped
+/// placeholders are kept as strings
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum VarType {
+    Str,
+    Int,
+    Float,
+    Bool,
+    Yaml,
+}
+
+impl VarType {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "str" => Some(VarType::Str),
+            "int" => Some(VarType::Int),
+            "float" => Some(VarType::Float),
+            "bool" => Some(VarType::Bool),
+            "yaml" => Some(VarType::Yaml),
+            _ => None,
+        }
+    }
+    /// The type of an exported value, recorded in its placeholder
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => VarType::Str,
+            Value::Bool(_) => VarType::Bool,
+            Value::U8(_)
+            | Value::U16(_)
+            | Value::U32(_)
+            | Value::U64(_)
+            | Value::I8(_)
+            | Value::I16(_)
+            | Value::I32(_)
+            | Value::I64(_) => VarType::Int,
+            Value::F32(_) | Value::F64(_) => VarType::Float,
+            _ => VarType::Yaml,
+        }
+    }
+    fn suffix(self) -> &'static str {
+        match self {
+            VarType::Str => "",
+            VarType::Int => ":int",
+            VarType::Float => ":float",
+            VarType::Bool => ":bool",
+            VarType::Yaml => ":yaml",
+        }
+    }
+    fn value(self, name: &str, raw: &str) -> EResult<Value> {
+        let invalid = || Error::invalid_data(format!("{}: invalid{} value", name, self.suffix()));
+        Ok(match self {
+            VarType::Str => Value::String(raw.to_owned()),
+            VarType::Int => Value::I64(raw.trim().parse().map_err(|_| invalid())?),
+            VarType::Float => Value::F64(raw.trim().parse().map_err(|_| invalid())?),
+            VarType::Bool => match raw.trim().to_lowercase().as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => return Err(invalid()),
+            },
+            VarType::Yaml => serde_yaml::from_str(raw).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// A dot-separated config path, "*" matches any key, e.g. "config.*.password"
+pub struct SecretPath(Vec<String>);
+
+impl SecretPath {
+    fn parse(s: &str) -> EResult<Self> {
+        let parts: Vec<String> = s.split('.').map(ToOwned::to_owned).collect();
+        if parts.iter().any(String::is_empty) {
+            Err(Error::invalid_params(format!("invalid secret path: {}", s)))
+        } else {
+            Ok(Self(parts))
+        }
+    }
+}
+
+/// Parses comma or space separated secret paths
+pub fn parse_paths(s: &str) -> EResult<Vec<SecretPath>> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .map(SecretPath::parse)
+        .collect()
+}
+
+/// Variable name for the resource field, e.g. "eva.db.default" + "config.password" becomes
+/// "EVA_DB_DEFAULT_CONFIG_PASSWORD", an empty id gives "CONFIG_PASSWORD"
+fn var_name(id: &str, path: &[String]) -> String {
+    let mut name: String = std::iter::once(id)
+        .filter(|id| !id.is_empty())
+        .chain(path.iter().map(String::as_str))
+        .collect::<Vec<&str>>()
+        .join("_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn strip_path(
+    id: &str,
+    value: &mut Value,
+    path: &[String],
+    matched: &mut Vec<String>,
+    result: &mut Vec<String>,
+) {
+    let (key, rest) = if let Some(v) = path.split_first() {
+        v
+    } else {
+        if *value != Value::Unit {
+            let name = var_name(id, matched);
+            *value = Value::String(format!("${{{}{}}}", name, VarType::of(value).suffix()));
+            result.push(name);
+        }
+        return;
+    };
+    if let Value::Map(m) = value {
+        for (k, v) in m.iter_mut() {
+            let k = k.to_string();
+            if *key == "*" || *key == k {
+                matched.push(k);
+                strip_path(id, v, rest, matched, result);
+                matched.pop();
+            }
+        }
+    }
+}
+
+/// Escapes "${" in string values as "$${", so literal values are not resolved on import
+fn escape(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = s.replace("${", "$${"),
+        Value::Seq(s) => s.iter_mut().for_each(escape),
+        Value::Map(m) => m.values_mut().for_each(escape),
+        _ => {}
+    }
+}
+
+/// Prepares an exported value: escapes literal "${" and replaces values at the secret paths with
+/// ${VAR} placeholders, returns the variable names. The type of non-string values is recorded,
+/// e.g. ${VAR:int}. Must be called once per value
+pub fn strip_secrets(id: &str, value: &mut Value, paths: &[SecretPath]) -> Vec<String> {
+    escape(value);
+    let mut result = Vec::new();
+    for path in paths {
+        strip_path(id, value, &path.0, &mut Vec::new(), &mut result);
+    }
+    result
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Var(&'a str, VarType),
+}
+
+/// Splits a string into text and ${VAR} or ${VAR:type} tokens, "$${" is an escaped "${"
+fn tokenize(s: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = s;
+    while let Some(pos) = rest.find("${") {
+        if rest[..pos].ends_with('$') {
+            tokens.push(Token::Text(&rest[..pos - 1]));
+            tokens.push(Token::Text("${"));
+            rest = &rest[pos + 2..];
+            continue;
+        }
+        let var = rest[pos + 2..].find('}').and_then(|len| {
+            let inner = &rest[pos + 2..pos + 2 + len];
+            let (name, kind) = if let Some((name, kind)) = inner.split_once(':') {
+                (name, VarType::parse(kind)?)
+            } else {
+                (inner, VarType::Str)
+            };
+            if is_var_name(name) {
+                Some((len, name, kind))
+            } else {
+                None
+            }
+        });
+        if let Some((len, name, kind)) = var {
+            tokens.push(Token::Text(&rest[..pos]));
+            tokens.push(Token::Var(name, kind));
+            rest = &rest[pos + 3 + len..];
+        } else {
+            tokens.push(Token::Text(&rest[..pos + 2]));
+            rest = &rest[pos + 2..];
+        }
+    }
+    tokens.push(Token::Text(rest));
+    tokens.retain(|t| !matches!(t, Token::Text("")));
+    tokens
+}
+
+fn is_var_name(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with(|c: char| c.is_ascii_digit())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Collects the placeholder names of the value
+pub fn placeholders(value: &Value, result: &mut BTreeSet<String>) {
+    match value {
+        Value::String(s) => {
+            for token in tokenize(s) {
+                if let Token::Var(name, _) = token {
+                    result.insert(name.to_owned());
+                }
+            }
+        }
+        Value::Seq(s) => s.iter().for_each(|v| placeholders(v, result)),
+        Value::Map(m) => m.values().for_each(|v| placeholders(v, result)),
+        _ => {}
+    }
+}
+
+/// Name of the variable if the value is a whole-field placeholder
+pub fn placeholder_name(value: &Value) -> Option<&str> {
+    if let Value::String(s) = value {
+        if let [Token::Var(name, _)] = tokenize(s)[..] {
+            return Some(name);
+        }
+    }
+    None
+}
+
+/// Substitutes the placeholders. All the variables must be resolved and match the placeholder
+/// types, otherwise the error lists the problems and the values are left untouched
+pub fn resolve(values: Vec<&mut Value>, vars: &Vars) -> EResult<()> {
+    let mut names = BTreeSet::new();
+    for value in &values {
+        placeholders(value, &mut names);
+    }
+    let missing: Vec<String> = names
+        .into_iter()
+        .filter(|n| !vars.contains_key(n))
+        .collect();
+    if !missing.is_empty() {
+        return Err(Error::not_found(format!(
+            "unresolved variables: {}",
+            missing.join(", ")
+        )));
+    }
+    let mut resolved = Vec::with_capacity(values.len());
+    for value in &values {
+        let mut v = Value::clone(value);
+        resolve_value(&mut v, vars)?;
+        resolved.push(v);
+    }
+    for (value, v) in values.into_iter().zip(resolved) {
+        *value = v;
+    }
+    Ok(())
+}
+
+fn resolve_value(value: &mut Value, vars: &Vars) -> EResult<()> {
+    match value {
+        Value::String(s) => {
+            let tokens = tokenize(s);
+            if let [Token::Var(name, kind)] = tokens[..] {
+                *value = kind.value(name, &vars[name])?;
+                return Ok(());
+            }
+            let mut result = String::with_capacity(s.len());
+            for token in tokens {
+                match token {
+                    Token::Text(t) => result.push_str(t),
+                    Token::Var(name, _) => result.push_str(&vars[name]),
+                }
+            }
+            *s = result;
+        }
+        Value::Seq(s) => {
+            for v in s {
+                resolve_value(v, vars)?;
+            }
+        }
+        Value::Map(m) => {
+            for v in m.values_mut() {
+                resolve_value(v, vars)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Resolves the placeholders from the environment files and the process environment, the
+/// process environment has the priority
+pub fn resolve_env(values: Vec<&mut Value>, env_files: &[String]) -> EResult<()> {
+    let mut names = BTreeSet::new();
+    for value in &values {
+        placeholders(value, &mut names);
+    }
+    let mut vars = Vars::new();
+    for fname in env_files {
+        vars.extend(load_env(fname)?);
+    }
+    vars.extend(env_vars(&names));
+    resolve(values, &vars)
+}
+
+/// Loads an environment file: NAME=VALUE lines, "export" prefixes and comments are allowed
+pub fn load_env(fname: &str) -> EResult<Vars> {
+    let mut f = std::fs::File::open(fname)?;
+    let mut buf = String::new();
+    f.read_to_string(&mut buf)?;
+    let mut vars = Vars::new();
+    for (i, line) in buf.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line
+            .split_once('=')
+            .filter(|(name, _)| is_var_name(name.trim()))
+            .ok_or_else(|| Error::invalid_data(format!("{}: invalid line {}", fname, i + 1)))?;
+        vars.insert(name.trim().to_owned(), value.trim().to_owned());
+    }
+    Ok(vars)
+}
+
+/// Takes the listed variables from the process environment
+pub fn env_vars<'a>(names: impl IntoIterator<Item = &'a String>) -> Vars {
+    names
+        .into_iter()
+        .filter_map(|n| std::env::var(n).ok().map(|v| (n.clone(), v)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    fn names(value: &Value) -> Vec<String> {
+        let mut result = BTreeSet::new();
+        placeholders(value, &mut result);
+        result.into_iter().collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let value = Value::String("a ${X}/${Y:int} $${Z} ${1A} ${B:bad} ${".to_owned());
+        assert_eq!(names(&value), vec!["X", "Y"]);
+        let mut vars = Vars::new();
+        vars.insert("X".to_owned(), "x".to_owned());
+        vars.insert("Y".to_owned(), "5".to_owned());
+        let mut v = value.clone();
+        resolve(vec![&mut v], &vars).unwrap();
+        assert_eq!(v, Value::String("a x/5 ${Z} ${1A} ${B:bad} ${".to_owned()));
+        assert_eq!(
+            placeholder_name(&Value::String("${X:float}".to_owned())),
+            Some("X")
+        );
+        assert_eq!(placeholder_name(&Value::String("$${X}".to_owned())), None);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let source = yaml(
+            "command: echo ${HOME} $${X} $\nconfig:\n  password: secret\n  port: 502.5\n  url: http://${host}/",
+        );
+        let mut exported = source.clone();
+        let vars_found = strip_secrets(
+            "eva.svc",
+            &mut exported,
+            &parse_paths("config.password config.port").unwrap(),
+        );
+        assert_eq!(
+            vars_found,
+            vec!["EVA_SVC_CONFIG_PASSWORD", "EVA_SVC_CONFIG_PORT"]
+        );
+        assert_eq!(
+            names(&exported),
+            vec!["EVA_SVC_CONFIG_PASSWORD", "EVA_SVC_CONFIG_PORT"]
+        );
+        let mut vars = Vars::new();
+        vars.insert("EVA_SVC_CONFIG_PASSWORD".to_owned(), "secret".to_owned());
+        vars.insert("EVA_SVC_CONFIG_PORT".to_owned(), "502.5".to_owned());
+        resolve(vec![&mut exported], &vars).unwrap();
+        assert_eq!(exported, source);
+        // no placeholders, literal values are unescaped only
+        let mut exported = source.clone();
+        strip_secrets("", &mut exported, &[]);
+        assert_ne!(exported, source);
+        resolve(vec![&mut exported], &Vars::new()).unwrap();
+        assert_eq!(exported, source);
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let mut value = yaml("a: ${A}\nb: ${B:int}\nc: ${C:float}\nd: ${D:bool}\ne: ${E:yaml}");
+        let mut vars = Vars::new();
+        for (k, v) in [
+            ("A", "007"),
+            ("B", "7"),
+            ("C", "1.5"),
+            ("D", "true"),
+            ("E", "[1, 2]"),
+        ] {
+            vars.insert(k.to_owned(), v.to_owned());
+        }
+        resolve(vec![&mut value], &vars).unwrap();
+        let mut expected = yaml("a: '007'\nb: 0\nc: 1.5\nd: true\ne: [1, 2]");
+        if let Value::Map(ref mut m) = expected {
+            m.insert("b".into(), Value::I64(7));
+        }
+        assert_eq!(value, expected);
+        let source = yaml("b: ${B:int}\na: ${A}");
+        let mut value = source.clone();
+        vars.insert("B".to_owned(), "x".to_owned());
+        assert!(resolve(vec![&mut value], &vars).is_err());
+        assert_eq!(value, source);
+    }
+}